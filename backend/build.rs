@@ -0,0 +1,13 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // protoc incluido en protoc-bin-vendored (PROTOC permite usar otro)
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+
+    println!("cargo:rerun-if-changed=proto/internal.proto");
+    tonic_build::configure()
+        .build_client(false)
+        .compile_protos(&["proto/internal.proto"], &["proto"])?;
+
+    Ok(())
+}
@@ -0,0 +1,16 @@
+-- Fusión de cuentas duplicadas y registro de auditoría de acciones de admin
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS merged_into INTEGER REFERENCES users(id);
+
+CREATE TABLE IF NOT EXISTS admin_audit_log (
+    id SERIAL PRIMARY KEY,
+    admin_id INTEGER NOT NULL REFERENCES users(id),
+    action VARCHAR(100) NOT NULL,
+    target_user_id INTEGER REFERENCES users(id),
+    details JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_admin_audit_log_target_user ON admin_audit_log (target_user_id);
+CREATE INDEX IF NOT EXISTS idx_admin_audit_log_created_at ON admin_audit_log (created_at);
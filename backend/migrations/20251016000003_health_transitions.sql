@@ -0,0 +1,9 @@
+-- Historial de transiciones del estado general de salud (para la página de estado)
+
+CREATE TABLE IF NOT EXISTS health_transitions (
+    id SERIAL PRIMARY KEY,
+    status VARCHAR(20) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_health_transitions_created_at ON health_transitions (created_at);
@@ -0,0 +1,6 @@
+-- Autenticación en dos pasos (TOTP). El secreto se guarda cifrado (ver APP_ENCRYPTION_KEY)
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS totp_secret TEXT,
+    ADD COLUMN IF NOT EXISTS totp_enabled BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN IF NOT EXISTS totp_last_step BIGINT;
@@ -0,0 +1,17 @@
+-- Registro persistente (opcional, OUTBOUND_LOG_PERSIST) de llamadas HTTP salientes
+
+CREATE TABLE IF NOT EXISTS outbound_calls (
+    id BIGSERIAL PRIMARY KEY,
+    destination VARCHAR(64) NOT NULL,
+    method VARCHAR(10) NOT NULL,
+    url TEXT NOT NULL,
+    status INTEGER,
+    success BOOLEAN NOT NULL,
+    duration_ms BIGINT NOT NULL,
+    error TEXT,
+    response_body TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_outbound_calls_destination_created_at
+    ON outbound_calls (destination, created_at DESC);
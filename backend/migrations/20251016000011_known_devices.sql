@@ -0,0 +1,15 @@
+-- Dispositivos desde los que cada usuario inició sesión (huella aproximada: navegador,
+-- sistema operativo y red); un login desde uno desconocido genera una alerta
+
+CREATE TABLE IF NOT EXISTS known_devices (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    fingerprint VARCHAR(64) NOT NULL,
+    browser VARCHAR(100) NOT NULL,
+    os VARCHAR(100) NOT NULL,
+    network VARCHAR(64) NOT NULL,
+    location VARCHAR(200),
+    first_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, fingerprint)
+);
@@ -0,0 +1,3 @@
+-- Versión de tokens por usuario: subirla invalida de una vez todos sus tokens emitidos
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS token_version INTEGER NOT NULL DEFAULT 1;
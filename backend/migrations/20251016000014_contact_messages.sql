@@ -0,0 +1,18 @@
+-- Mensajes del formulario público de contacto (fraude, soporte, cuenta...).
+-- Los atienden los admins: se asignan y se marcan como atendidos
+
+CREATE TABLE IF NOT EXISTS contact_messages (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    subject VARCHAR(20) NOT NULL,
+    message TEXT NOT NULL,
+    ip_address VARCHAR(64) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'open',
+    assigned_to INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    handled_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    handled_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_contact_messages_status ON contact_messages (status, created_at DESC);
@@ -0,0 +1,9 @@
+-- Roles de usuario (user < moderator < admin) en lugar del booleano is_admin.
+-- is_admin se conserva sincronizado (role = 'admin') para versiones anteriores del backend
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role VARCHAR(20) NOT NULL DEFAULT 'user';
+
+UPDATE users SET role = 'admin' WHERE is_admin = true AND role = 'user';
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_role_check;
+ALTER TABLE users ADD CONSTRAINT users_role_check CHECK (role IN ('user', 'moderator', 'admin'));
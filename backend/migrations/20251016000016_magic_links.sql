@@ -0,0 +1,14 @@
+-- Enlaces de acceso sin contraseña (magic link). Se guarda solo el hash SHA-256 del token;
+-- used_at distingue un enlace ya usado de uno desconocido
+
+CREATE TABLE IF NOT EXISTS magic_links (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_magic_links_user_id ON magic_links (user_id);
+CREATE INDEX IF NOT EXISTS idx_magic_links_expires_at ON magic_links (expires_at);
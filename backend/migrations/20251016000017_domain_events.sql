@@ -0,0 +1,14 @@
+-- Registro de eventos de dominio, solo de inserción. Los consumidores (notificaciones,
+-- webhooks, analítica) lo leen en orden de sequence en vez de engancharse a cada handler
+
+CREATE TABLE IF NOT EXISTS domain_events (
+    sequence BIGSERIAL PRIMARY KEY,
+    event_type VARCHAR(100) NOT NULL,
+    aggregate_type VARCHAR(50) NOT NULL,
+    aggregate_id BIGINT NOT NULL,
+    payload JSONB NOT NULL DEFAULT '{}'::jsonb,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_domain_events_aggregate ON domain_events (aggregate_type, aggregate_id);
+CREATE INDEX IF NOT EXISTS idx_domain_events_occurred_at ON domain_events (occurred_at);
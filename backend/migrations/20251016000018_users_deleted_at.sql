@@ -0,0 +1,4 @@
+-- Baja de cuenta por el propio usuario (DELETE /api/v1/auth/me): la fila se conserva
+-- anonimizada para no romper referencias; deleted_at la distingue de una desactivación
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
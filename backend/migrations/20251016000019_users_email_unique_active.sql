@@ -0,0 +1,13 @@
+-- Unicidad de email solo entre cuentas no dadas de baja: una cuenta eliminada no bloquea
+-- el registro de su email. Al restaurarla el mismo índice detecta si el email ya se volvió a usar
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key;
+DROP INDEX IF EXISTS users_email_key;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_email_active ON users (email) WHERE deleted_at IS NULL;
+
+-- Los datos personales se borran al terminar el plazo de restauración (ver ACCOUNT_DELETION_GRACE_DAYS)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS anonymized_at TIMESTAMPTZ;
+
+-- Las bajas anteriores ya se anonimizaban en el momento
+UPDATE users SET anonymized_at = deleted_at WHERE deleted_at IS NOT NULL AND anonymized_at IS NULL;
@@ -0,0 +1,7 @@
+-- Nombre de usuario opcional para iniciar sesión con él en lugar del email.
+-- Se guarda en minúsculas; único entre cuentas no dadas de baja (igual que el email)
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS username VARCHAR(30);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_username_active ON users (username)
+    WHERE deleted_at IS NULL AND username IS NOT NULL;
@@ -0,0 +1,7 @@
+-- Sesión a la que pertenece cada refresh token, para revocarlos junto con ella (logout).
+-- Los emitidos antes de esta migración quedan en NULL y solo se cortan por la sesión
+
+ALTER TABLE refresh_tokens
+    ADD COLUMN IF NOT EXISTS session_id INTEGER REFERENCES sessions(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_session_id ON refresh_tokens (session_id);
@@ -0,0 +1,35 @@
+use sqlx::{Executor, Postgres};
+
+// Registrar una acción administrativa en admin_audit_log
+// Acepta pool o transacción para que la auditoría sea parte de la misma operación
+pub async fn record<'c, E>(
+    executor: E,
+    admin_id: i32,
+    action: &str,
+    target_user_id: Option<i32>,
+    details: serde_json::Value,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query(
+        "INSERT INTO admin_audit_log (admin_id, action, target_user_id, details)
+         VALUES ($1, $2, $3, $4)"
+    )
+    .bind(admin_id)
+    .bind(action)
+    .bind(target_user_id)
+    .bind(details)
+    .execute(executor)
+    .await?;
+
+    tracing::info!(
+        event = "admin_audit",
+        admin_id = admin_id,
+        action = %action,
+        target_user_id = ?target_user_id,
+        "📝 Acción de admin auditada"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use sqlx::{PgConnection, PgPool};
+use std::sync::OnceLock;
+use crate::events;
+use crate::models::user::User;
+
+// Días entre la baja de una cuenta y su anonimización (ACCOUNT_DELETION_GRACE_DAYS,
+// por defecto 30). Durante ese plazo un admin puede restaurarla; 0 = anonimizar en el momento
+pub fn grace_days() -> u32 {
+    static DAYS: OnceLock<u32> = OnceLock::new();
+    *DAYS.get_or_init(|| {
+        std::env::var("ACCOUNT_DELETION_GRACE_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30)
+    })
+}
+
+// Dar de baja dentro de la transacción del llamador: la cuenta queda inactiva, todos sus
+// tokens dejan de servir y se borran en el momento los datos que no hacen falta para
+// restaurarla (nombre, secreto TOTP, sesiones, dispositivos). Email, username y contraseña
+// se conservan hasta la anonimización: restore los necesita para detectar conflictos con
+// cuentas nuevas y para que el usuario vuelva a entrar con sus credenciales
+pub async fn soft_delete(conn: &mut PgConnection, user_id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE users SET is_active = false, deleted_at = NOW(), token_version = token_version + 1,
+             name = 'Usuario eliminado', totp_secret = NULL, totp_enabled = false, updated_at = NOW()
+         WHERE id = $1"
+    )
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query("UPDATE api_keys SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    // Datos que solo identifican a la persona (IPs, navegadores, enlaces). Las identidades
+    // externas también: la misma cuenta de Google debe poder registrarse de nuevo
+    for table in ["sessions", "known_devices", "user_identities", "password_resets", "magic_links"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE user_id = $1", table))
+            .bind(user_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    if grace_days() == 0 {
+        anonymize(&mut *conn, user_id).await?;
+    }
+
+    events::record(
+        &mut *conn,
+        events::EVENT_USER_DELETED,
+        events::AGGREGATE_USER,
+        user_id.into(),
+        serde_json::json!({ "grace_days": grace_days() }),
+    )
+    .await?;
+
+    Ok(())
+}
+
+// Datos personales reemplazados por valores de relleno (el email de relleno es único por id)
+const ANONYMIZE: &str = "UPDATE users SET
+     name = 'Usuario eliminado',
+     email = 'eliminado-' || id || '@deleted.invalid',
+     password_hash = NULL,
+     totp_secret = NULL,
+     totp_enabled = false,
+     username = NULL,
+     anonymized_at = NOW()";
+
+async fn anonymize(conn: &mut PgConnection, user_id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!(
+        "{} WHERE id = $1 AND deleted_at IS NOT NULL AND anonymized_at IS NULL",
+        ANONYMIZE
+    ))
+    .bind(user_id)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+// Anonimizar las bajas cuyo plazo de restauración terminó (tarea de limpieza)
+pub async fn anonymize_expired(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(&format!(
+        "{} WHERE deleted_at < NOW() - make_interval(days => $1) AND anonymized_at IS NULL",
+        ANONYMIZE
+    ))
+    .bind(grace_days() as i32)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// Resultado de restaurar una cuenta dada de baja
+pub enum Restoration {
+    Restored(User),
+    NotFound,
+    NotDeleted,
+    // Ya pasó el plazo: no quedan datos que restaurar
+    Anonymized,
+    // Otra cuenta activa usa el email (se registró después de la baja)
+    EmailTaken,
+    // Otra cuenta activa tomó el nombre de usuario después de la baja
+    UsernameTaken,
+}
+
+// Restaurar una cuenta dentro del plazo. Los conflictos de email y username los detectan
+// los índices únicos parciales, así que también cubre un registro concurrente. El nombre y la 2FA borrados en la
+// baja no vuelven: la cuenta queda como "Usuario eliminado" hasta que se edite
+pub async fn restore(pool: &PgPool, user_id: i32) -> Result<Restoration, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let state: Option<(bool, bool)> = sqlx::query_as(
+        "SELECT deleted_at IS NOT NULL, anonymized_at IS NOT NULL FROM users WHERE id = $1 FOR UPDATE"
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    match state {
+        None => return Ok(Restoration::NotFound),
+        Some((false, _)) => return Ok(Restoration::NotDeleted),
+        Some((true, true)) => return Ok(Restoration::Anonymized),
+        Some((true, false)) => {}
+    }
+
+    let restored = sqlx::query_as::<_, User>(
+        "UPDATE users SET deleted_at = NULL, is_active = true, updated_at = NOW()
+         WHERE id = $1
+         RETURNING id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at"
+    )
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await;
+
+    let user = match restored {
+        Ok(user) => user,
+        Err(sqlx::Error::Database(ref db_error)) if db_error.is_unique_violation() => {
+            return match db_error.constraint() {
+                Some("idx_users_username_active") => Ok(Restoration::UsernameTaken),
+                _ => Ok(Restoration::EmailTaken),
+            };
+        }
+        Err(e) => return Err(e),
+    };
+
+    events::record(
+        &mut tx,
+        events::EVENT_USER_RESTORED,
+        events::AGGREGATE_USER,
+        user_id.into(),
+        serde_json::json!({}),
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(Restoration::Restored(user))
+}
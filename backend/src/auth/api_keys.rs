@@ -0,0 +1,157 @@
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use crate::models::auth::{Claims, TOKEN_TYPE_API_KEY};
+use crate::models::user::User;
+
+// Scopes que se pueden asignar a una API key
+pub const SCOPE_METRICS_READ: &str = "metrics:read";
+pub const SCOPE_TOKENS_INTROSPECT: &str = "tokens:introspect";
+pub const KNOWN_SCOPES: &[&str] = &[SCOPE_METRICS_READ, SCOPE_TOKENS_INTROSPECT];
+
+// Prefijo visible de las keys: "vl_<id público>_<secreto>"
+const KEY_PREFIX: &str = "vl_";
+
+// API key tal como se lista (nunca incluye la key ni su hash)
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ApiKey {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    #[serde(with = "crate::models::timestamp::option")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::models::timestamp::option")]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::models::timestamp::option")]
+    pub revoked_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::models::timestamp")]
+    pub created_at: DateTime<Utc>,
+}
+
+// Las keys tienen 256 bits de entropía: basta con SHA-256 (sin sal) para buscarlas
+fn hash_key(raw_key: &str) -> String {
+    Sha256::digest(raw_key.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn random_hex(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut buf);
+    buf.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Crear una key; devuelve la key completa (solo se muestra esta vez) y sus datos
+pub async fn create(
+    pool: &PgPool,
+    user_id: i32,
+    name: &str,
+    scopes: &[String],
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<(String, ApiKey), sqlx::Error> {
+    let key_prefix = format!("{}{}", KEY_PREFIX, random_hex(4));
+    let raw_key = format!("{}_{}", key_prefix, random_hex(32));
+
+    let api_key = sqlx::query_as::<_, ApiKey>(
+        "INSERT INTO api_keys (user_id, name, key_prefix, key_hash, scopes, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING id, user_id, name, key_prefix, scopes, expires_at, last_used_at, revoked_at, created_at"
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(&key_prefix)
+    .bind(hash_key(&raw_key))
+    .bind(scopes)
+    .bind(expires_at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok((raw_key, api_key))
+}
+
+pub async fn list(pool: &PgPool) -> Result<Vec<ApiKey>, sqlx::Error> {
+    sqlx::query_as::<_, ApiKey>(
+        "SELECT id, user_id, name, key_prefix, scopes, expires_at, last_used_at, revoked_at, created_at
+         FROM api_keys ORDER BY id DESC"
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// Keys de un usuario, incluidas las revocadas (exportación de datos personales)
+pub async fn list_for_user(pool: &PgPool, user_id: i32) -> Result<Vec<ApiKey>, sqlx::Error> {
+    sqlx::query_as::<_, ApiKey>(
+        "SELECT id, user_id, name, key_prefix, scopes, expires_at, last_used_at, revoked_at, created_at
+         FROM api_keys WHERE user_id = $1 ORDER BY id DESC"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+// Revocar una key; None si no existe o ya estaba revocada
+pub async fn revoke(pool: &PgPool, id: i32) -> Result<Option<ApiKey>, sqlx::Error> {
+    sqlx::query_as::<_, ApiKey>(
+        "UPDATE api_keys SET revoked_at = NOW()
+         WHERE id = $1 AND revoked_at IS NULL
+         RETURNING id, user_id, name, key_prefix, scopes, expires_at, last_used_at, revoked_at, created_at"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+// Resolver una key vigente a su dueño (activo) y sus scopes; registra el uso
+pub async fn authenticate(pool: &PgPool, raw_key: &str) -> Result<Option<(User, ApiKey)>, sqlx::Error> {
+    let api_key = sqlx::query_as::<_, ApiKey>(
+        "UPDATE api_keys SET last_used_at = NOW()
+         WHERE key_hash = $1 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())
+         RETURNING id, user_id, name, key_prefix, scopes, expires_at, last_used_at, revoked_at, created_at"
+    )
+    .bind(hash_key(raw_key))
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(api_key) = api_key else {
+        return Ok(None);
+    };
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at
+         FROM users WHERE id = $1 AND is_active = true"
+    )
+    .bind(api_key.user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user.map(|user| (user, api_key)))
+}
+
+// Claims equivalentes para que los handlers traten igual a keys y JWT
+pub fn claims_for(user: &User, api_key: &ApiKey) -> Claims {
+    Claims {
+        sub: user.id.to_string(),
+        email: user.email.clone(),
+        name: user.name.clone(),
+        is_admin: user.is_admin(),
+        role: user.role,
+        exp: api_key
+            .expires_at
+            .map(|t| t.timestamp() as usize)
+            .unwrap_or(usize::MAX),
+        iat: api_key.created_at.timestamp() as usize,
+        typ: TOKEN_TYPE_API_KEY.to_string(),
+        jti: None,
+        iss: String::new(),
+        aud: String::new(),
+        ver: user.token_version,
+        sid: None,
+        rem: false,
+        impersonated_by: None,
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+
+// Máximo de usuarios rastreados antes de purgar contadores en cero
+const MAX_TRACKED_USERS: usize = 50_000;
+
+// Límite de requests simultáneos por usuario autenticado
+// (USER_MAX_CONCURRENT_REQUESTS, por defecto 20; 0 lo desactiva)
+pub struct UserConcurrency {
+    max: usize,
+    in_flight: RwLock<HashMap<i32, Arc<AtomicUsize>>>,
+}
+
+// Permiso de un request en curso; al soltarse libera el cupo
+pub struct ConcurrencyPermit {
+    counter: Arc<AtomicUsize>,
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl UserConcurrency {
+    pub fn from_env() -> Self {
+        let max = std::env::var("USER_MAX_CONCURRENT_REQUESTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(20usize);
+
+        Self::new(max)
+    }
+
+    pub fn new(max: usize) -> Self {
+        Self {
+            max,
+            in_flight: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn counter(&self, user_id: i32) -> Arc<AtomicUsize> {
+        if let Some(counter) = self
+            .in_flight
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&user_id)
+        {
+            return counter.clone();
+        }
+
+        let mut in_flight = self.in_flight.write().unwrap_or_else(|e| e.into_inner());
+
+        // Solo se purgan contadores sin requests ni permisos vivos
+        if in_flight.len() >= MAX_TRACKED_USERS {
+            in_flight.retain(|_, counter| {
+                Arc::strong_count(counter) > 1 || counter.load(Ordering::Acquire) > 0
+            });
+        }
+
+        in_flight.entry(user_id).or_default().clone()
+    }
+
+    // Reservar un cupo para el usuario; None si ya alcanzó el límite
+    pub fn try_acquire(&self, user_id: i32) -> Option<ConcurrencyPermit> {
+        let counter = self.counter(user_id);
+
+        let previous = counter.fetch_add(1, Ordering::AcqRel);
+
+        if self.max > 0 && previous >= self.max {
+            counter.fetch_sub(1, Ordering::AcqRel);
+            return None;
+        }
+
+        Some(ConcurrencyPermit { counter })
+    }
+
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    // Usuarios con más requests en curso (para métricas de admin)
+    pub fn top_users(&self, limit: usize) -> Vec<(i32, usize)> {
+        let mut users: Vec<(i32, usize)> = self
+            .in_flight
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(user_id, counter)| (*user_id, counter.load(Ordering::Acquire)))
+            .filter(|(_, in_flight)| *in_flight > 0)
+            .collect();
+
+        users.sort_by_key(|(_, in_flight)| std::cmp::Reverse(*in_flight));
+        users.truncate(limit);
+        users
+    }
+}
+
+// Instancia global compartida por el middleware de auth
+pub fn user_concurrency() -> &'static UserConcurrency {
+    static CONCURRENCY: OnceLock<UserConcurrency> = OnceLock::new();
+    CONCURRENCY.get_or_init(UserConcurrency::from_env)
+}
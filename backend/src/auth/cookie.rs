@@ -0,0 +1,166 @@
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::Response,
+    Json,
+};
+use std::sync::OnceLock;
+use crate::auth::jwt::extract_token_from_header;
+use crate::models::auth::AuthError;
+
+// Transporte del token de acceso (AUTH_TRANSPORT): "header" (por defecto, el frontend
+// guarda el token y lo envía en Authorization) o "cookie" (cookie HttpOnly emitida por el backend)
+struct CookieConfig {
+    enabled: bool,
+    name: String,
+}
+
+fn config() -> &'static CookieConfig {
+    static CONFIG: OnceLock<CookieConfig> = OnceLock::new();
+    CONFIG.get_or_init(|| CookieConfig {
+        enabled: std::env::var("AUTH_TRANSPORT")
+            .map(|v| v.trim().eq_ignore_ascii_case("cookie"))
+            .unwrap_or(false),
+        name: std::env::var("AUTH_COOKIE_NAME").unwrap_or_else(|_| "vl_token".to_string()),
+    })
+}
+
+pub fn cookie_transport_enabled() -> bool {
+    config().enabled
+}
+
+fn cookie_header(value: &str, max_age_secs: i64) -> Option<HeaderValue> {
+    HeaderValue::from_str(&format!(
+        "{}={}; Path=/; Max-Age={}; HttpOnly; Secure; SameSite=Lax",
+        config().name,
+        value,
+        max_age_secs
+    ))
+    .ok()
+}
+
+// Agregar la cookie de sesión con el token de acceso (solo con AUTH_TRANSPORT=cookie);
+// dura lo mismo que el token (`expires_at`, timestamp). El token sigue viajando también
+// en el cuerpo para clientes que usan el header
+pub fn set_token_cookie(response: &mut Response, token: &str, expires_at: i64) {
+    if !cookie_transport_enabled() {
+        return;
+    }
+    let max_age = (expires_at - chrono::Utc::now().timestamp()).max(0);
+    if let Some(cookie) = cookie_header(token, max_age) {
+        response.headers_mut().append(header::SET_COOKIE, cookie);
+    }
+}
+
+// Borrar la cookie de sesión (logout)
+pub fn clear_token_cookie(response: &mut Response) {
+    if !cookie_transport_enabled() {
+        return;
+    }
+    if let Some(cookie) = cookie_header("", 0) {
+        response.headers_mut().append(header::SET_COOKIE, cookie);
+    }
+}
+
+fn token_from_cookie<'h>(headers: &'h HeaderMap, name: &str) -> Option<&'h str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(key, value)| *key == name && !value.is_empty())
+        .map(|(_, value)| value)
+}
+
+// Token de acceso del request: header Authorization y, si no viene y el transporte
+// por cookie está activo, la cookie de sesión
+pub fn request_token(headers: &HeaderMap) -> Result<&str, (StatusCode, Json<AuthError>)> {
+    token_with_config(headers, config())
+}
+
+fn token_with_config<'h>(
+    headers: &'h HeaderMap,
+    config: &CookieConfig,
+) -> Result<&'h str, (StatusCode, Json<AuthError>)> {
+    match headers.get(header::AUTHORIZATION).and_then(|h| h.to_str().ok()) {
+        Some(auth_header) => extract_token_from_header(auth_header).ok_or_else(|| {
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(AuthError::new("invalid_format", "Formato de token inválido")),
+            )
+        }),
+        None => config
+            .enabled
+            .then(|| token_from_cookie(headers, &config.name))
+            .flatten()
+            .ok_or_else(|| {
+                (
+                    StatusCode::UNAUTHORIZED,
+                    Json(AuthError::new("missing_token", "Token de autorización requerido")),
+                )
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cookie_config(enabled: bool) -> CookieConfig {
+        CookieConfig {
+            enabled,
+            name: "vl_token".to_string(),
+        }
+    }
+
+    fn headers(authorization: Option<&str>, cookie: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = authorization {
+            headers.insert(header::AUTHORIZATION, HeaderValue::from_str(value).unwrap());
+        }
+        if let Some(value) = cookie {
+            headers.insert(header::COOKIE, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn error_code(result: Result<&str, (StatusCode, Json<AuthError>)>) -> String {
+        let (status, Json(error)) = result.expect_err("se esperaba un error");
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        error.error
+    }
+
+    #[test]
+    fn reads_token_from_header() {
+        let headers = headers(Some("Bearer del-header"), None);
+        assert_eq!(token_with_config(&headers, &cookie_config(false)).unwrap(), "del-header");
+        assert_eq!(token_with_config(&headers, &cookie_config(true)).unwrap(), "del-header");
+    }
+
+    #[test]
+    fn reads_token_from_cookie_when_enabled() {
+        let headers = headers(None, Some("tema=oscuro; vl_token=de-la-cookie"));
+        assert_eq!(token_with_config(&headers, &cookie_config(true)).unwrap(), "de-la-cookie");
+    }
+
+    #[test]
+    fn header_takes_precedence_over_cookie() {
+        let headers = headers(Some("Bearer del-header"), Some("vl_token=de-la-cookie"));
+        assert_eq!(token_with_config(&headers, &cookie_config(true)).unwrap(), "del-header");
+    }
+
+    #[test]
+    fn ignores_cookie_with_header_transport() {
+        let headers = headers(None, Some("vl_token=de-la-cookie"));
+        assert_eq!(error_code(token_with_config(&headers, &cookie_config(false))), "missing_token");
+    }
+
+    #[test]
+    fn rejects_malformed_header_and_empty_cookie() {
+        let malformed = headers(Some("Token abc"), Some("vl_token=de-la-cookie"));
+        assert_eq!(error_code(token_with_config(&malformed, &cookie_config(true))), "invalid_format");
+
+        let empty_cookie = headers(None, Some("vl_token="));
+        assert_eq!(error_code(token_with_config(&empty_cookie, &cookie_config(true))), "missing_token");
+    }
+}
@@ -0,0 +1,191 @@
+use chrono::{DateTime, Utc};
+use maxminddb::{geoip2, Reader};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use std::net::IpAddr;
+use std::sync::OnceLock;
+use crate::models::user::User;
+
+// Dispositivo aproximado: familia del navegador + sistema operativo + red (/24 en IPv4,
+// /48 en IPv6). Es deliberadamente grueso: cambia de dispositivo, no de versión o IP puntual
+pub struct DeviceInfo {
+    pub fingerprint: String,
+    pub browser: String,
+    pub os: String,
+    pub network: String,
+}
+
+impl DeviceInfo {
+    pub fn from_request(user_agent: Option<&str>, ip: &str) -> Self {
+        let (browser, os) = user_agent
+            .and_then(|ua| user_agent_parser().parse(ua))
+            .map(|parsed| (parsed.name.to_string(), parsed.os.to_string()))
+            .unwrap_or_else(|| ("UNKNOWN".to_string(), "UNKNOWN".to_string()));
+        let network = network_of(ip);
+
+        let fingerprint = Sha256::digest(format!("{}|{}|{}", browser, os, network).as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        Self { fingerprint, browser, os, network }
+    }
+}
+
+fn user_agent_parser() -> &'static woothee::parser::Parser {
+    static PARSER: OnceLock<woothee::parser::Parser> = OnceLock::new();
+    PARSER.get_or_init(woothee::parser::Parser::new)
+}
+
+fn network_of(ip: &str) -> String {
+    match ip.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            let [a, b, c, _] = ip.octets();
+            format!("{}.{}.{}.0/24", a, b, c)
+        }
+        Ok(IpAddr::V6(ip)) => {
+            let segments = ip.segments();
+            format!("{:x}:{:x}:{:x}::/48", segments[0], segments[1], segments[2])
+        }
+        Err(_) => ip.to_string(),
+    }
+}
+
+// Base GeoIP local opcional (GEOIP_DB_PATH, formato MaxMind City); sin ella solo se muestra la IP
+fn geoip_reader() -> Option<&'static Reader<Vec<u8>>> {
+    static READER: OnceLock<Option<Reader<Vec<u8>>>> = OnceLock::new();
+    READER
+        .get_or_init(|| {
+            let path = std::env::var("GEOIP_DB_PATH").ok().filter(|p| !p.trim().is_empty())?;
+            match Reader::open_readfile(&path) {
+                Ok(reader) => Some(reader),
+                Err(e) => {
+                    tracing::warn!(error = %e, path = %path, "⚠️ No se pudo abrir la base GeoIP");
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+// Ubicación aproximada, p. ej. "La Paz, Bolivia"
+pub fn approximate_location(ip: &str) -> Option<String> {
+    let reader = geoip_reader()?;
+    let city: geoip2::City = reader.lookup(ip.parse().ok()?).ok()?;
+
+    let name = |names: Option<&std::collections::BTreeMap<&str, &str>>| {
+        names.and_then(|n| n.get("es").or_else(|| n.get("en")).map(|s| s.to_string()))
+    };
+    let parts: Vec<String> = [
+        name(city.city.as_ref().and_then(|c| c.names.as_ref())),
+        name(city.country.as_ref().and_then(|c| c.names.as_ref())),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+// Dispositivo conocido tal como se lista al usuario
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct KnownDevice {
+    pub id: i32,
+    pub browser: String,
+    pub os: String,
+    pub network: String,
+    pub location: Option<String>,
+    #[serde(with = "crate::models::timestamp")]
+    pub first_seen_at: DateTime<Utc>,
+    #[serde(with = "crate::models::timestamp")]
+    pub last_seen_at: DateTime<Utc>,
+}
+
+// Registrar un login; devuelve true si el dispositivo es nuevo para un usuario que ya
+// tenía otros (el primero de cada cuenta no se considera sospechoso)
+pub async fn record_login(
+    pool: &PgPool,
+    user_id: i32,
+    device: &DeviceInfo,
+    location: Option<&str>,
+) -> Result<bool, sqlx::Error> {
+    let has_devices: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM known_devices WHERE user_id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+    // xmax = 0 solo en filas recién insertadas
+    let inserted: bool = sqlx::query_scalar(
+        "INSERT INTO known_devices (user_id, fingerprint, browser, os, network, location)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (user_id, fingerprint) DO UPDATE
+            SET last_seen_at = NOW(), location = COALESCE(EXCLUDED.location, known_devices.location)
+         RETURNING (xmax = 0)"
+    )
+    .bind(user_id)
+    .bind(&device.fingerprint)
+    .bind(&device.browser)
+    .bind(&device.os)
+    .bind(&device.network)
+    .bind(location)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(inserted && has_devices)
+}
+
+pub async fn list(pool: &PgPool, user_id: i32) -> Result<Vec<KnownDevice>, sqlx::Error> {
+    sqlx::query_as::<_, KnownDevice>(
+        "SELECT id, browser, os, network, location, first_seen_at, last_seen_at
+         FROM known_devices WHERE user_id = $1 ORDER BY last_seen_at DESC"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+// Olvidar un dispositivo: el próximo login desde él vuelve a generar alerta
+pub async fn revoke(pool: &PgPool, user_id: i32, device_id: i32) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM known_devices WHERE id = $1 AND user_id = $2")
+        .bind(device_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Enlace "¿no fuiste tú?" de las alertas (PASSWORD_RESET_URL)
+fn password_reset_url() -> String {
+    std::env::var("PASSWORD_RESET_URL")
+        .unwrap_or_else(|_| "http://localhost:5173/forgot-password".to_string())
+}
+
+// Registrar el login y alertar si viene de un dispositivo nuevo. No falla el login:
+// los errores solo se registran
+pub async fn track_login(pool: &PgPool, user: &User, user_agent: Option<&str>, ip: &str) {
+    let device = DeviceInfo::from_request(user_agent, ip);
+    let location = approximate_location(ip);
+
+    match record_login(pool, user.id, &device, location.as_deref()).await {
+        Ok(true) => {
+            // Aún no hay envío de emails ni notificaciones: la alerta queda en los logs
+            tracing::warn!(
+                event = "new_device_login",
+                user_id = user.id,
+                browser = %device.browser,
+                os = %device.os,
+                ip = %ip,
+                location = %location.as_deref().unwrap_or(ip),
+                at = %crate::models::timestamp::now(),
+                reset_url = %password_reset_url(),
+                "📱 Inicio de sesión desde un dispositivo nuevo"
+            );
+        }
+        Ok(false) => {}
+        Err(e) => {
+            tracing::error!(error = %e, user_id = user.id, "🚨 Error al registrar el dispositivo del login");
+        }
+    }
+}
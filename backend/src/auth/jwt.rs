@@ -1,65 +1,432 @@
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, Jwk, JwkSet, KeyAlgorithm, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use simple_asn1::ASN1Block;
+use std::collections::HashMap;
 use std::env;
+use std::sync::OnceLock;
 use chrono::{Duration, Utc};
-use crate::models::auth::Claims;
+use crate::models::auth::{Claims, TOKEN_TYPE_2FA_CHALLENGE, TOKEN_TYPE_ACCESS, TOKEN_TYPE_REFRESH};
 use crate::models::user::User;
 
+// Secreto de desarrollo usado si falta JWT_SECRET (ver validate)
+const DEFAULT_SECRET: &str = "your-super-secret-jwt-key-change-in-production";
+
+// Largo mínimo del secreto HS256 (256 bits)
+const MIN_SECRET_BYTES: usize = 32;
+
 // Configuración JWT
+// JWT_ALGORITHM=HS256 (por defecto, con JWT_SECRET) o RS256 (con JWT_PRIVATE_KEY_PATH y
+// JWT_PUBLIC_KEY_PATH en PEM); con RS256 otros servicios verifican solo con la clave pública.
+// Rotación: la clave actual se identifica con JWT_KEY_ID (va como `kid` en el header) y
+// JWT_PREVIOUS_KEYS="kid:valor,..." lista las retiradas, que solo verifican (el valor es
+// el secreto con HS256 o la ruta de la clave pública PEM con RS256)
 pub struct JwtConfig {
     pub secret: String,
     pub expiration_hours: i64,
+    // Duración del token de acceso con "recordarme" (JWT_REMEMBER_EXPIRATION_DAYS, por defecto 30)
+    pub remember_expiration_days: i64,
+    pub refresh_expiration_days: i64,
+    // Tolerancia en segundos al validar exp (JWT_LEEWAY_SECS, por defecto 30)
+    pub leeway_secs: u64,
+    // Emisor y audiencia de los tokens (JWT_ISSUER, JWT_AUDIENCE); distintos por
+    // entorno para que un token de staging no sirva en producción
+    pub issuer: String,
+    pub audience: String,
+    pub algorithm: Algorithm,
+    // Identificador de la clave con la que se firma
+    pub key_id: String,
+    encoding_key: EncodingKey,
+    // Claves de verificación por kid (la actual y las retiradas)
+    decoding_keys: HashMap<String, DecodingKey>,
+    // Claves públicas publicadas en /.well-known/jwks.json (vacío con HS256)
+    jwks: JwkSet,
 }
 
 impl JwtConfig {
-    pub fn from_env() -> Self {
-        Self {
-            secret: env::var("JWT_SECRET")
-                .unwrap_or_else(|_| "your-super-secret-jwt-key-change-in-production".to_string()),
-            expiration_hours: env::var("JWT_EXPIRATION_HOURS")
-                .unwrap_or_else(|_| "24".to_string())
-                .parse()
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    // Construir la configuración leyendo cada variable con `var` (el entorno en from_env)
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let secret = var("JWT_SECRET").unwrap_or_else(|| DEFAULT_SECRET.to_string());
+
+        let algorithm = match var("JWT_ALGORITHM")
+            .unwrap_or_else(|| "HS256".to_string())
+            .to_uppercase()
+            .as_str()
+        {
+            "HS256" => Algorithm::HS256,
+            "RS256" => Algorithm::RS256,
+            other => return Err(format!("JWT_ALGORITHM no soportado: {}", other)),
+        };
+
+        let key_id = var("JWT_KEY_ID")
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| "default".to_string());
+        let mut jwks = JwkSet { keys: Vec::new() };
+
+        let (encoding_key, decoding_key) = match algorithm {
+            Algorithm::RS256 => {
+                let read_pem = |name: &str| {
+                    let path = var(name).ok_or_else(|| format!("{} es requerido con RS256", name))?;
+                    std::fs::read(&path).map_err(|e| format!("no se pudo leer {} ({}): {}", name, path, e))
+                };
+                let private_pem = read_pem("JWT_PRIVATE_KEY_PATH")?;
+                let public_pem = read_pem("JWT_PUBLIC_KEY_PATH")?;
+                jwks.keys.push(
+                    rsa_public_jwk(&key_id, &public_pem)
+                        .map_err(|e| format!("clave pública RSA inválida: {}", e))?,
+                );
+
+                (
+                    EncodingKey::from_rsa_pem(&private_pem)
+                        .map_err(|e| format!("clave privada RSA inválida: {}", e))?,
+                    DecodingKey::from_rsa_pem(&public_pem)
+                        .map_err(|e| format!("clave pública RSA inválida: {}", e))?,
+                )
+            }
+            _ => (
+                EncodingKey::from_secret(secret.as_ref()),
+                DecodingKey::from_secret(secret.as_ref()),
+            ),
+        };
+
+        let mut decoding_keys = HashMap::new();
+        for (position, entry) in var("JWT_PREVIOUS_KEYS").unwrap_or_default().split(',').enumerate() {
+            let entry = entry.trim();
+            if entry.is_empty() {
+                continue;
+            }
+            let (kid, value) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("JWT_PREVIOUS_KEYS: se esperaba kid:valor en la entrada {}", position + 1))?;
+            let key = match algorithm {
+                Algorithm::RS256 => {
+                    let pem = std::fs::read(value)
+                        .map_err(|e| format!("no se pudo leer la clave retirada '{}' ({}): {}", kid, value, e))?;
+                    // Las retiradas se siguen publicando mientras haya tokens firmados con ellas
+                    jwks.keys.push(
+                        rsa_public_jwk(kid, &pem)
+                            .map_err(|e| format!("clave pública RSA retirada '{}' inválida: {}", kid, e))?,
+                    );
+                    DecodingKey::from_rsa_pem(&pem)
+                        .map_err(|e| format!("clave pública RSA retirada '{}' inválida: {}", kid, e))?
+                }
+                _ => DecodingKey::from_secret(value.as_bytes()),
+            };
+            decoding_keys.insert(kid.to_string(), key);
+        }
+
+        if decoding_keys.contains_key(&key_id) {
+            return Err(format!("JWT_KEY_ID '{}' también figura en JWT_PREVIOUS_KEYS", key_id));
+        }
+        decoding_keys.insert(key_id.clone(), decoding_key);
+
+        Ok(Self {
+            secret,
+            expiration_hours: var("JWT_EXPIRATION_HOURS")
+                .and_then(|v| v.parse().ok())
                 .unwrap_or(24),
+            remember_expiration_days: var("JWT_REMEMBER_EXPIRATION_DAYS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            refresh_expiration_days: var("JWT_REFRESH_EXPIRATION_DAYS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            leeway_secs: var("JWT_LEEWAY_SECS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            issuer: var("JWT_ISSUER").unwrap_or_else(|| "venta-libre-api".to_string()),
+            audience: var("JWT_AUDIENCE").unwrap_or_else(|| "venta-libre".to_string()),
+            algorithm,
+            key_id,
+            encoding_key,
+            decoding_keys,
+            jwks,
+        })
+    }
+
+    // Firma HS256 con el secreto de desarrollo: cualquiera puede emitir tokens válidos
+    pub fn uses_default_secret(&self) -> bool {
+        self.algorithm == Algorithm::HS256 && self.secret == DEFAULT_SECRET
+    }
+
+    // Verificar que el secreto HS256 sea seguro (configurado y de al menos 32 bytes).
+    // En producción main no arranca si falla; en desarrollo solo lo advierte
+    pub fn validate(&self) -> Result<(), String> {
+        if self.algorithm != Algorithm::HS256 {
+            return Ok(());
         }
+        if self.uses_default_secret() {
+            return Err("JWT_SECRET no está configurado (se usaría el secreto por defecto)".to_string());
+        }
+        if self.secret.len() < MIN_SECRET_BYTES {
+            return Err(format!(
+                "JWT_SECRET es demasiado corto ({} bytes, mínimo {})",
+                self.secret.len(),
+                MIN_SECRET_BYTES
+            ));
+        }
+        Ok(())
+    }
+
+    // Claves retiradas que todavía verifican tokens
+    pub fn previous_key_count(&self) -> usize {
+        self.decoding_keys.len() - 1
+    }
+
+    // Claves públicas para que otros servicios verifiquen tokens (None con HS256:
+    // el secreto compartido nunca se publica)
+    pub fn jwks(&self) -> Option<&JwkSet> {
+        (self.algorithm == Algorithm::RS256).then_some(&self.jwks)
     }
 }
 
-// Generar token JWT
-pub fn generate_token(user: &User) -> Result<String, jsonwebtoken::errors::Error> {
-    let config = JwtConfig::from_env();
+// JWK de una clave pública RSA en PEM ("PUBLIC KEY" SPKI o "RSA PUBLIC KEY" PKCS#1)
+fn rsa_public_jwk(kid: &str, public_pem: &[u8]) -> Result<Jwk, String> {
+    let pem = pem::parse(public_pem).map_err(|e| e.to_string())?;
+    let der = match pem.tag() {
+        // SubjectPublicKeyInfo: SEQUENCE { algoritmo, BIT STRING con la clave PKCS#1 }
+        "PUBLIC KEY" => match simple_asn1::from_der(pem.contents()).map_err(|e| e.to_string())?.first() {
+            Some(ASN1Block::Sequence(_, fields)) => match fields.get(1) {
+                Some(ASN1Block::BitString(_, _, key)) => key.clone(),
+                _ => return Err("SubjectPublicKeyInfo sin clave".to_string()),
+            },
+            _ => return Err("SubjectPublicKeyInfo inválido".to_string()),
+        },
+        "RSA PUBLIC KEY" => pem.contents().to_vec(),
+        other => return Err(format!("PEM '{}' no es una clave pública", other)),
+    };
+
+    // RSAPublicKey: SEQUENCE { n INTEGER, e INTEGER }
+    let (n, e) = match simple_asn1::from_der(&der).map_err(|e| e.to_string())?.first() {
+        Some(ASN1Block::Sequence(_, fields)) => match fields.as_slice() {
+            [ASN1Block::Integer(_, n), ASN1Block::Integer(_, e), ..] => (n.to_bytes_be().1, e.to_bytes_be().1),
+            _ => return Err("RSAPublicKey inválida".to_string()),
+        },
+        _ => return Err("RSAPublicKey inválida".to_string()),
+    };
+
+    Ok(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(KeyAlgorithm::RS256),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+            key_type: RSAKeyType::RSA,
+            n: URL_SAFE_NO_PAD.encode(n),
+            e: URL_SAFE_NO_PAD.encode(e),
+        }),
+    })
+}
+
+static JWT_CONFIG: OnceLock<JwtConfig> = OnceLock::new();
+
+// Cargar la configuración (y las claves) una sola vez al iniciar
+pub fn init_jwt_config() -> Result<&'static JwtConfig, String> {
+    if let Some(config) = JWT_CONFIG.get() {
+        return Ok(config);
+    }
+    let config = JwtConfig::from_env()?;
+    Ok(JWT_CONFIG.get_or_init(|| config))
+}
+
+pub fn jwt_config() -> &'static JwtConfig {
+    JWT_CONFIG.get_or_init(|| {
+        JwtConfig::from_env().unwrap_or_else(|e| panic!("configuración JWT inválida: {}", e))
+    })
+}
+
+// Firmar claims con el algoritmo y la clave configurados
+fn sign(claims: &Claims) -> Result<String, jsonwebtoken::errors::Error> {
+    sign_with(jwt_config(), claims)
+}
+
+fn sign_with(config: &JwtConfig, claims: &Claims) -> Result<String, jsonwebtoken::errors::Error> {
+    let mut header = Header::new(config.algorithm);
+    header.kid = Some(config.key_id.clone());
+    encode(&header, claims, &config.encoding_key)
+}
+
+// Generar token JWT de acceso dentro de una sesión (devuelve también sus claims).
+// Con `remember` dura JWT_REMEMBER_EXPIRATION_DAYS y queda marcado en el claim `rem`
+pub fn generate_token(
+    user: &User,
+    session_id: Option<i32>,
+    remember: bool,
+) -> Result<(String, Claims), jsonwebtoken::errors::Error> {
+    let config = jwt_config();
     let now = Utc::now();
-    let expiration = now + Duration::hours(config.expiration_hours);
+    let expiration = if remember {
+        now + Duration::days(config.remember_expiration_days)
+    } else {
+        now + Duration::hours(config.expiration_hours)
+    };
     
     let claims = Claims {
         sub: user.id.to_string(),
         email: user.email.clone(),
         name: user.name.clone(),
-        is_admin: user.is_admin,
+        is_admin: user.is_admin(),
+        role: user.role,
         exp: expiration.timestamp() as usize,
         iat: now.timestamp() as usize,
+        typ: TOKEN_TYPE_ACCESS.to_string(),
+        jti: Some(uuid::Uuid::new_v4().to_string()),
+        iss: config.issuer.clone(),
+        aud: config.audience.clone(),
+        ver: user.token_version,
+        sid: session_id,
+        rem: remember,
+        impersonated_by: None,
     };
     
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(config.secret.as_ref()),
-    )
+    let token = sign(&claims)?;
+
+    Ok((token, claims))
 }
 
-// Verificar y decodificar token JWT
-pub fn verify_token(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
-    let config = JwtConfig::from_env();
-    
-    let validation = Validation::default();
+// Decodificar y validar firma/expiración de un token JWT
+fn decode_claims(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    decode_with(jwt_config(), token)
+}
+
+fn decode_with(config: &JwtConfig, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    // Solo se acepta el algoritmo configurado (evita confusión HS256/RS256);
+    // expiración obligatoria con una tolerancia corta por desfase de reloj
+    let mut validation = Validation::new(config.algorithm);
+    validation.validate_exp = true;
+    validation.leeway = config.leeway_secs;
+    // iss/aud obligatorios y del entorno actual
+    validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+    validation.set_issuer(&[&config.issuer]);
+    validation.set_audience(&[&config.audience]);
     
+    // La clave la elige el kid del header; un kid desconocido se rechaza.
+    // Sin kid (tokens previos a la rotación) se usa la clave actual
+    let header = decode_header(token)?;
+    let kid = header.kid.as_deref().unwrap_or(&config.key_id);
+    let decoding_key = config
+        .decoding_keys
+        .get(kid)
+        .ok_or(jsonwebtoken::errors::ErrorKind::InvalidToken)?;
+
     let token_data = decode::<Claims>(
         token,
-        &DecodingKey::from_secret(config.secret.as_ref()),
+        decoding_key,
         &validation,
     )?;
     
     Ok(token_data.claims)
 }
 
+// Verificar y decodificar token JWT de acceso
+pub fn verify_token(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let claims = decode_claims(token)?;
+    
+    // Un refresh token no sirve como token de acceso
+    if claims.typ != TOKEN_TYPE_ACCESS {
+        return Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into());
+    }
+    
+    Ok(claims)
+}
+
+// Generar refresh token de larga duración (devuelve también sus claims para persistirlo).
+// Conserva `rem` para que los tokens refrescados mantengan el modo de la sesión
+pub fn generate_refresh_token(
+    user: &User,
+    session_id: Option<i32>,
+    remember: bool,
+) -> Result<(String, Claims), jsonwebtoken::errors::Error> {
+    let config = jwt_config();
+    let now = Utc::now();
+    let expiration = now + Duration::days(config.refresh_expiration_days);
+    
+    let claims = Claims {
+        sub: user.id.to_string(),
+        email: user.email.clone(),
+        name: user.name.clone(),
+        is_admin: user.is_admin(),
+        role: user.role,
+        exp: expiration.timestamp() as usize,
+        iat: now.timestamp() as usize,
+        typ: TOKEN_TYPE_REFRESH.to_string(),
+        jti: Some(uuid::Uuid::new_v4().to_string()),
+        iss: config.issuer.clone(),
+        aud: config.audience.clone(),
+        ver: user.token_version,
+        sid: session_id,
+        rem: remember,
+        impersonated_by: None,
+    };
+    
+    let token = sign(&claims)?;
+    
+    Ok((token, claims))
+}
+
+// Token de desafío 2FA: corta duración (TOTP_CHALLENGE_TTL_SECS, por defecto 300).
+// `remember` se traslada a los tokens emitidos al completar el desafío
+pub fn generate_challenge_token(user: &User, remember: bool) -> Result<(String, Claims), jsonwebtoken::errors::Error> {
+    let config = jwt_config();
+    let ttl_secs: i64 = env::var("TOTP_CHALLENGE_TTL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(300);
+    let now = Utc::now();
+    let expiration = now + Duration::seconds(ttl_secs);
+    
+    let claims = Claims {
+        sub: user.id.to_string(),
+        email: user.email.clone(),
+        name: user.name.clone(),
+        is_admin: user.is_admin(),
+        role: user.role,
+        exp: expiration.timestamp() as usize,
+        iat: now.timestamp() as usize,
+        typ: TOKEN_TYPE_2FA_CHALLENGE.to_string(),
+        jti: Some(uuid::Uuid::new_v4().to_string()),
+        iss: config.issuer.clone(),
+        aud: config.audience.clone(),
+        ver: user.token_version,
+        sid: None,
+        rem: remember,
+        impersonated_by: None,
+    };
+    
+    let token = sign(&claims)?;
+    
+    Ok((token, claims))
+}
+
+pub fn verify_challenge_token(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let claims = decode_claims(token)?;
+    
+    if claims.typ != TOKEN_TYPE_2FA_CHALLENGE {
+        return Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into());
+    }
+    
+    Ok(claims)
+}
+
+// Verificar refresh token (firma, expiración y tipo)
+pub fn verify_refresh_token(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let claims = decode_claims(token)?;
+    
+    if claims.typ != TOKEN_TYPE_REFRESH || claims.jti.is_none() {
+        return Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into());
+    }
+    
+    Ok(claims)
+}
+
 // Extraer token del header Authorization
 pub fn extract_token_from_header(auth_header: &str) -> Option<&str> {
     if auth_header.starts_with("Bearer ") {
@@ -69,36 +436,470 @@ pub fn extract_token_from_header(auth_header: &str) -> Option<&str> {
     }
 }
 
-// Validar que el token no haya expirado
-pub fn is_token_expired(claims: &Claims) -> bool {
-    let now = Utc::now().timestamp() as usize;
-    claims.exp < now
+// El token es anterior a la última invalidación global de tokens del usuario
+pub fn is_token_stale(claims: &Claims, user: &User) -> bool {
+    claims.ver != user.token_version
 }
 
-// Generar token de larga duración para admins
-pub fn generate_admin_token(user: &User) -> Result<String, jsonwebtoken::errors::Error> {
-    if !user.is_admin {
+// Duración máxima (y por defecto) de un token de admin: 7 días
+pub const ADMIN_TOKEN_MAX_TTL_HOURS: i64 = 168;
+
+// Generar token de larga duración para admins (ttl_hours se limita a 1..=168).
+// Un usuario no admin devuelve InvalidToken: el llamador debe verificarlo antes
+pub fn generate_admin_token(
+    user: &User,
+    ttl_hours: i64,
+    session_id: Option<i32>,
+) -> Result<(String, Claims), jsonwebtoken::errors::Error> {
+    if !user.is_admin() {
         return Err(jsonwebtoken::errors::Error::from(
             jsonwebtoken::errors::ErrorKind::InvalidToken
         ));
     }
     
-    let config = JwtConfig::from_env();
+    let config = jwt_config();
     let now = Utc::now();
-    let expiration = now + Duration::days(7); // Token de admin dura 7 días
+    let expiration = now + Duration::hours(ttl_hours.clamp(1, ADMIN_TOKEN_MAX_TTL_HOURS));
     
     let claims = Claims {
         sub: user.id.to_string(),
         email: user.email.clone(),
         name: user.name.clone(),
-        is_admin: user.is_admin,
+        is_admin: user.is_admin(),
+        role: user.role,
         exp: expiration.timestamp() as usize,
         iat: now.timestamp() as usize,
+        typ: TOKEN_TYPE_ACCESS.to_string(),
+        jti: Some(uuid::Uuid::new_v4().to_string()),
+        iss: config.issuer.clone(),
+        aud: config.audience.clone(),
+        ver: user.token_version,
+        sid: session_id,
+        rem: false,
+        impersonated_by: None,
     };
     
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(config.secret.as_ref()),
-    )
-}
\ No newline at end of file
+    let token = sign(&claims)?;
+
+    Ok((token, claims))
+}
+// Duración de un token de suplantación
+pub const IMPERSONATION_TTL_MINUTES: i64 = 15;
+
+// Generar token de acceso para que un admin vea la app como `user` (claim `impersonated_by`).
+// Sin sesión ni refresh token: vence a los IMPERSONATION_TTL_MINUTES
+pub fn generate_impersonation_token(
+    user: &User,
+    admin_id: i32,
+) -> Result<(String, Claims), jsonwebtoken::errors::Error> {
+    let config = jwt_config();
+    let now = Utc::now();
+    let expiration = now + Duration::minutes(IMPERSONATION_TTL_MINUTES);
+
+    let claims = Claims {
+        sub: user.id.to_string(),
+        email: user.email.clone(),
+        name: user.name.clone(),
+        is_admin: user.is_admin(),
+        role: user.role,
+        exp: expiration.timestamp() as usize,
+        iat: now.timestamp() as usize,
+        typ: TOKEN_TYPE_ACCESS.to_string(),
+        jti: Some(uuid::Uuid::new_v4().to_string()),
+        iss: config.issuer.clone(),
+        aud: config.audience.clone(),
+        ver: user.token_version,
+        sid: None,
+        rem: false,
+        impersonated_by: Some(admin_id),
+    };
+
+    let token = sign(&claims)?;
+
+    Ok((token, claims))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::errors::ErrorKind;
+    use crate::models::user::Role;
+
+    // Claves RSA de prueba (generadas con openssl, solo para tests)
+    fn fixture(name: &str) -> String {
+        format!("{}/tests/fixtures/jwt/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    fn config_from(vars: &[(&str, &str)]) -> JwtConfig {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        JwtConfig::from_vars(|name| vars.get(name).cloned()).expect("configuración válida")
+    }
+
+    fn rs256_config() -> JwtConfig {
+        config_from(&[
+            ("JWT_ALGORITHM", "RS256"),
+            ("JWT_KEY_ID", "current"),
+            ("JWT_PRIVATE_KEY_PATH", &fixture("current-private.pem")),
+            ("JWT_PUBLIC_KEY_PATH", &fixture("current-public.pem")),
+        ])
+    }
+
+    fn user(role: Role) -> User {
+        User {
+            id: 42,
+            name: "Ana".to_string(),
+            email: "ana@example.com".to_string(),
+            password_hash: None,
+            role,
+            is_active: true,
+            token_version: 3,
+            username: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    // Claims de acceso válidos para `config`, con vencimiento a `expires_in` segundos
+    fn access_claims(config: &JwtConfig, expires_in: i64) -> Claims {
+        let now = Utc::now();
+        Claims {
+            sub: "42".to_string(),
+            email: "ana@example.com".to_string(),
+            name: "Ana".to_string(),
+            is_admin: false,
+            role: Role::User,
+            exp: (now + Duration::seconds(expires_in)).timestamp() as usize,
+            iat: now.timestamp() as usize,
+            typ: TOKEN_TYPE_ACCESS.to_string(),
+            jti: Some(uuid::Uuid::new_v4().to_string()),
+            iss: config.issuer.clone(),
+            aud: config.audience.clone(),
+            ver: 0,
+            sid: None,
+            rem: false,
+            impersonated_by: None,
+        }
+    }
+
+    fn error_kind(result: Result<Claims, jsonwebtoken::errors::Error>) -> ErrorKind {
+        result.expect_err("el token debía rechazarse").into_kind()
+    }
+
+    #[test]
+    fn rs256_round_trip() {
+        let config = rs256_config();
+        let claims = access_claims(&config, 3600);
+        let token = sign_with(&config, &claims).unwrap();
+
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.alg, Algorithm::RS256);
+        assert_eq!(header.kid.as_deref(), Some("current"));
+
+        let decoded = decode_with(&config, &token).unwrap();
+        assert_eq!(decoded.sub, claims.sub);
+        assert_eq!(decoded.jti, claims.jti);
+    }
+
+    #[test]
+    fn rs256_rejects_token_signed_with_another_key() {
+        let config = rs256_config();
+        // Mismo kid pero firmado con una clave privada que no corresponde
+        let forger = config_from(&[
+            ("JWT_ALGORITHM", "RS256"),
+            ("JWT_KEY_ID", "current"),
+            ("JWT_PRIVATE_KEY_PATH", &fixture("other-private.pem")),
+            ("JWT_PUBLIC_KEY_PATH", &fixture("other-public.pem")),
+        ]);
+        let token = sign_with(&forger, &access_claims(&config, 3600)).unwrap();
+
+        assert_eq!(error_kind(decode_with(&config, &token)), ErrorKind::InvalidSignature);
+    }
+
+    #[test]
+    fn rs256_requires_key_paths() {
+        let missing = JwtConfig::from_vars(|name| (name == "JWT_ALGORITHM").then(|| "RS256".to_string()));
+        assert!(missing.err().unwrap().contains("JWT_PRIVATE_KEY_PATH"));
+    }
+
+    #[test]
+    fn hs256_round_trip_is_the_default() {
+        let config = config_from(&[("JWT_SECRET", "un-secreto-de-prueba-de-al-menos-32-bytes")]);
+        assert_eq!(config.algorithm, Algorithm::HS256);
+
+        let claims = access_claims(&config, 3600);
+        let token = sign_with(&config, &claims).unwrap();
+        assert_eq!(decode_header(&token).unwrap().alg, Algorithm::HS256);
+        assert_eq!(decode_with(&config, &token).unwrap().sub, claims.sub);
+    }
+
+    #[test]
+    fn rejects_algorithm_confusion() {
+        let rs256 = rs256_config();
+
+        // HS256 firmado con la clave pública RSA como secreto (ataque clásico de confusión)
+        let public_pem = std::fs::read_to_string(fixture("current-public.pem")).unwrap();
+        let confused = config_from(&[("JWT_SECRET", &public_pem), ("JWT_KEY_ID", "current")]);
+        let token = sign_with(&confused, &access_claims(&rs256, 3600)).unwrap();
+        assert_eq!(error_kind(decode_with(&rs256, &token)), ErrorKind::InvalidAlgorithm);
+
+        // Y al revés: un token RS256 no sirve donde se espera HS256
+        let hs256 = config_from(&[("JWT_SECRET", "un-secreto-de-prueba-de-al-menos-32-bytes"), ("JWT_KEY_ID", "current")]);
+        let token = sign_with(&rs256, &access_claims(&hs256, 3600)).unwrap();
+        assert_eq!(error_kind(decode_with(&hs256, &token)), ErrorKind::InvalidAlgorithm);
+    }
+
+    #[test]
+    fn jwks_publishes_rs256_public_keys() {
+        let config = config_from(&[
+            ("JWT_ALGORITHM", "RS256"),
+            ("JWT_KEY_ID", "current"),
+            ("JWT_PRIVATE_KEY_PATH", &fixture("current-private.pem")),
+            ("JWT_PUBLIC_KEY_PATH", &fixture("current-public.pem")),
+            ("JWT_PREVIOUS_KEYS", &format!("retired:{}", fixture("retired-public.pem"))),
+        ]);
+        let jwks = config.jwks().expect("RS256 publica sus claves");
+
+        let kids: Vec<_> = jwks.keys.iter().map(|k| k.common.key_id.as_deref()).collect();
+        assert_eq!(kids, vec![Some("current"), Some("retired")]);
+
+        let json = serde_json::to_value(jwks).unwrap();
+        let key = &json["keys"][0];
+        assert_eq!(key["kty"], "RSA");
+        assert_eq!(key["use"], "sig");
+        assert_eq!(key["alg"], "RS256");
+        assert_eq!(key["e"], "AQAB");
+        assert!(!json.to_string().contains("PRIVATE"));
+
+        // Un servicio externo verifica solo con la JWK publicada
+        let token = sign_with(&config, &access_claims(&config, 3600)).unwrap();
+        let decoding_key = DecodingKey::from_jwk(jwks.find("current").unwrap()).unwrap();
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&[&config.audience]);
+        assert!(decode::<Claims>(&token, &decoding_key, &validation).is_ok());
+    }
+
+    #[test]
+    fn jwks_is_empty_with_hs256() {
+        let config = config_from(&[("JWT_SECRET", "un-secreto-de-prueba-de-al-menos-32-bytes")]);
+        assert!(config.jwks().is_none());
+    }
+
+    #[test]
+    fn rejects_wrong_audience() {
+        let production = config_from(&[("JWT_SECRET", "secreto-compartido-entre-entornos-32b"), ("JWT_AUDIENCE", "venta-libre")]);
+        let staging = config_from(&[("JWT_SECRET", "secreto-compartido-entre-entornos-32b"), ("JWT_AUDIENCE", "venta-libre-staging")]);
+
+        let token = sign_with(&staging, &access_claims(&staging, 3600)).unwrap();
+        assert_eq!(error_kind(decode_with(&production, &token)), ErrorKind::InvalidAudience);
+        assert!(decode_with(&staging, &token).is_ok());
+    }
+
+    #[test]
+    fn rejects_wrong_issuer() {
+        let ours = config_from(&[("JWT_SECRET", "secreto-compartido-entre-entornos-32b")]);
+        let theirs = config_from(&[("JWT_SECRET", "secreto-compartido-entre-entornos-32b"), ("JWT_ISSUER", "otro-servicio")]);
+
+        let token = sign_with(&theirs, &access_claims(&theirs, 3600)).unwrap();
+        assert_eq!(error_kind(decode_with(&ours, &token)), ErrorKind::InvalidIssuer);
+    }
+
+    fn rotated_config() -> JwtConfig {
+        config_from(&[
+            ("JWT_SECRET", "secreto-actual-de-al-menos-32-bytes!"),
+            ("JWT_KEY_ID", "2026-10"),
+            ("JWT_PREVIOUS_KEYS", "2026-04:secreto-retirado-de-al-menos-32-bytes"),
+        ])
+    }
+
+    fn retired_config() -> JwtConfig {
+        config_from(&[
+            ("JWT_SECRET", "secreto-retirado-de-al-menos-32-bytes"),
+            ("JWT_KEY_ID", "2026-04"),
+        ])
+    }
+
+    #[test]
+    fn signs_with_active_key_and_accepts_it() {
+        let config = rotated_config();
+        assert_eq!(config.previous_key_count(), 1);
+
+        let token = sign_with(&config, &access_claims(&config, 3600)).unwrap();
+        assert_eq!(decode_header(&token).unwrap().kid.as_deref(), Some("2026-10"));
+        assert!(decode_with(&config, &token).is_ok());
+    }
+
+    #[test]
+    fn retired_key_validates_until_expiry() {
+        let config = rotated_config();
+        let retired = retired_config();
+
+        let token = sign_with(&retired, &access_claims(&config, 3600)).unwrap();
+        assert!(decode_with(&config, &token).is_ok());
+
+        let expired = sign_with(&retired, &access_claims(&config, -3600)).unwrap();
+        assert_eq!(error_kind(decode_with(&config, &expired)), ErrorKind::ExpiredSignature);
+    }
+
+    #[test]
+    fn rejects_unknown_kid() {
+        let config = rotated_config();
+        let unknown = config_from(&[
+            ("JWT_SECRET", "secreto-actual-de-al-menos-32-bytes!"),
+            ("JWT_KEY_ID", "2025-01"),
+        ]);
+
+        // Aunque la firma coincida con la clave actual, el kid no está registrado
+        let token = sign_with(&unknown, &access_claims(&config, 3600)).unwrap();
+        assert_eq!(error_kind(decode_with(&config, &token)), ErrorKind::InvalidToken);
+    }
+
+    #[test]
+    fn retired_kid_signed_with_wrong_secret_is_rejected() {
+        let config = rotated_config();
+        let forged = config_from(&[
+            ("JWT_SECRET", "otro-secreto-cualquiera-de-32-bytes!!"),
+            ("JWT_KEY_ID", "2026-04"),
+        ]);
+
+        let token = sign_with(&forged, &access_claims(&config, 3600)).unwrap();
+        assert_eq!(error_kind(decode_with(&config, &token)), ErrorKind::InvalidSignature);
+    }
+
+    #[test]
+    fn rejects_active_kid_listed_as_retired() {
+        let result = JwtConfig::from_vars(|name| match name {
+            "JWT_KEY_ID" => Some("2026-10".to_string()),
+            "JWT_PREVIOUS_KEYS" => Some("2026-10:otro-secreto".to_string()),
+            _ => None,
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn expired_token_reports_token_expired() {
+        let config = config_from(&[("JWT_SECRET", "un-secreto-de-prueba-de-al-menos-32-bytes")]);
+        let token = sign_with(&config, &access_claims(&config, -3600)).unwrap();
+
+        let error = decode_with(&config, &token).expect_err("token vencido");
+        assert_eq!(error.kind(), &ErrorKind::ExpiredSignature);
+        assert_eq!(crate::models::auth::AuthError::from_jwt_error(&error).error, "token_expired");
+
+        let malformed = decode_with(&config, "no.es.un-jwt").expect_err("token inválido");
+        assert_eq!(crate::models::auth::AuthError::from_jwt_error(&malformed).error, "invalid_token");
+    }
+
+    #[test]
+    fn expiry_allows_configured_leeway() {
+        let config = config_from(&[
+            ("JWT_SECRET", "un-secreto-de-prueba-de-al-menos-32-bytes"),
+            ("JWT_LEEWAY_SECS", "30"),
+        ]);
+
+        let just_expired = sign_with(&config, &access_claims(&config, -10)).unwrap();
+        assert!(decode_with(&config, &just_expired).is_ok());
+
+        let expired = sign_with(&config, &access_claims(&config, -60)).unwrap();
+        assert_eq!(error_kind(decode_with(&config, &expired)), ErrorKind::ExpiredSignature);
+    }
+
+    #[test]
+    fn validate_rejects_default_and_short_secrets() {
+        assert!(config_from(&[]).validate().is_err());
+        assert!(config_from(&[("JWT_SECRET", DEFAULT_SECRET)]).uses_default_secret());
+
+        let short = config_from(&[("JWT_SECRET", "corto")]).validate().unwrap_err();
+        assert!(short.contains("demasiado corto"));
+
+        assert!(config_from(&[("JWT_SECRET", &"x".repeat(MIN_SECRET_BYTES))]).validate().is_ok());
+        // Con RS256 el secreto no se usa
+        assert!(rs256_config().validate().is_ok());
+    }
+
+    #[test]
+    fn config_reads_each_variable_once() {
+        let reads = std::cell::RefCell::new(Vec::new());
+        let config = JwtConfig::from_vars(|name| {
+            reads.borrow_mut().push(name.to_string());
+            (name == "JWT_SECRET").then(|| "un-secreto-de-prueba-de-al-menos-32-bytes".to_string())
+        })
+        .unwrap();
+
+        let reads = reads.into_inner();
+        assert_eq!(reads.iter().filter(|name| *name == "JWT_SECRET").count(), 1);
+        let distinct: std::collections::HashSet<_> = reads.iter().collect();
+        assert_eq!(distinct.len(), reads.len());
+
+        // Firmar y verificar usan las claves ya cargadas, sin volver a leer variables
+        let token = sign_with(&config, &access_claims(&config, 3600)).unwrap();
+        assert!(decode_with(&config, &token).is_ok());
+
+        // La instancia global se construye una sola vez
+        assert!(std::ptr::eq(jwt_config(), jwt_config()));
+        assert!(std::ptr::eq(init_jwt_config().unwrap(), jwt_config()));
+    }
+
+    #[test]
+    fn admin_token_requires_admin() {
+        for role in [Role::User, Role::Moderator] {
+            let error = generate_admin_token(&user(role), 24, None).expect_err("solo admins");
+            assert_eq!(error.kind(), &ErrorKind::InvalidToken);
+        }
+    }
+
+    #[test]
+    fn admin_token_ttl_is_capped() {
+        let admin = user(Role::Admin);
+        let ttl_hours = |claims: &Claims| (claims.exp - claims.iat) as i64 / 3600;
+
+        let (_, claims) = generate_admin_token(&admin, 1000, None).unwrap();
+        assert_eq!(ttl_hours(&claims), ADMIN_TOKEN_MAX_TTL_HOURS);
+
+        let (_, claims) = generate_admin_token(&admin, 0, None).unwrap();
+        assert_eq!(ttl_hours(&claims), 1);
+
+        let (token, claims) = generate_admin_token(&admin, 24, Some(7)).unwrap();
+        assert_eq!(ttl_hours(&claims), 24);
+        assert!(claims.is_admin);
+        assert!(!claims.rem);
+
+        let verified = verify_token(&token).unwrap();
+        assert_eq!(verified.sid, Some(7));
+    }
+
+    #[test]
+    fn impersonation_token_carries_admin_and_expires_quickly() {
+        let (token, claims) = generate_impersonation_token(&user(Role::User), 7).unwrap();
+        assert_eq!(claims.impersonated_by, Some(7));
+        assert_eq!((claims.exp - claims.iat) as i64, IMPERSONATION_TTL_MINUTES * 60);
+        assert_eq!(claims.sid, None);
+        assert!(!claims.is_admin);
+
+        // El claim sobrevive la verificación, que es de donde lo toma AuthUser
+        let verified = verify_token(&token).unwrap();
+        assert_eq!(verified.impersonated_by, Some(7));
+        assert_eq!(verified.sub, "42");
+        assert_eq!(verified.ver, 3);
+    }
+
+    #[test]
+    fn remember_me_extends_lifetime_and_is_marked() {
+        let config = jwt_config();
+        let user = user(Role::User);
+
+        let (_, regular) = generate_token(&user, Some(1), false).unwrap();
+        assert!(!regular.rem);
+        assert_eq!((regular.exp - regular.iat) as i64, config.expiration_hours * 3600);
+
+        let (token, remembered) = generate_token(&user, Some(1), true).unwrap();
+        assert!(remembered.rem);
+        assert_eq!((remembered.exp - remembered.iat) as i64, config.remember_expiration_days * 86400);
+        assert!(verify_token(&token).unwrap().rem);
+
+        // Los refresh tokens conservan el modo de la sesión
+        let (_, refresh) = generate_refresh_token(&user, Some(1), true).unwrap();
+        assert!(refresh.rem);
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+// Máximo de emails rastreados antes de purgar entradas vencidas
+const MAX_TRACKED_EMAILS: usize = 10_000;
+
+// Bloqueo de cuentas tras intentos de login fallidos
+// (LOGIN_LOCKOUT_THRESHOLD fallos dentro de LOGIN_LOCKOUT_WINDOW_MINUTES)
+pub struct LoginAttempts {
+    threshold: u32,
+    window: Duration,
+    attempts: RwLock<HashMap<String, (u32, Instant)>>,
+}
+
+impl LoginAttempts {
+    pub fn from_env() -> Self {
+        let threshold = std::env::var("LOGIN_LOCKOUT_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5u32)
+            .max(1);
+        let window_minutes = std::env::var("LOGIN_LOCKOUT_WINDOW_MINUTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(15u64);
+
+        Self::new(threshold, Duration::from_secs(window_minutes * 60))
+    }
+
+    pub fn new(threshold: u32, window: Duration) -> Self {
+        Self {
+            threshold,
+            window,
+            attempts: RwLock::new(HashMap::new()),
+        }
+    }
+
+    // Tiempo restante de bloqueo para el email, si está bloqueado
+    pub fn locked_for(&self, email: &str) -> Option<Duration> {
+        let attempts = self.attempts.read().unwrap_or_else(|e| e.into_inner());
+        let (failures, first_failure) = attempts.get(email)?;
+        let elapsed = first_failure.elapsed();
+
+        if *failures >= self.threshold && elapsed < self.window {
+            Some(self.window - elapsed)
+        } else {
+            None
+        }
+    }
+
+    // Registrar un intento fallido; devuelve true si este intento provoca el bloqueo
+    pub fn record_failure(&self, email: &str) -> bool {
+        let mut attempts = self.attempts.write().unwrap_or_else(|e| e.into_inner());
+
+        if attempts.len() >= MAX_TRACKED_EMAILS {
+            let window = self.window;
+            attempts.retain(|_, (_, first_failure)| first_failure.elapsed() < window);
+        }
+
+        let entry = attempts
+            .entry(email.to_string())
+            .or_insert((0, Instant::now()));
+
+        // Ventana vencida: se empieza a contar de nuevo
+        if entry.1.elapsed() >= self.window {
+            *entry = (0, Instant::now());
+        }
+
+        entry.0 += 1;
+        entry.0 == self.threshold
+    }
+
+    // Login exitoso: se reinicia el contador
+    pub fn reset(&self, email: &str) {
+        self.attempts
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(email);
+    }
+}
+
+// Instancia global compartida por los handlers de auth
+pub fn login_attempts() -> &'static LoginAttempts {
+    static ATTEMPTS: OnceLock<LoginAttempts> = OnceLock::new();
+    ATTEMPTS.get_or_init(LoginAttempts::from_env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locks_after_threshold_failures() {
+        let attempts = LoginAttempts::new(5, Duration::from_secs(900));
+
+        for _ in 0..4 {
+            assert!(!attempts.record_failure("ana@example.com"));
+            assert!(attempts.locked_for("ana@example.com").is_none());
+        }
+
+        // El quinto fallo es el que bloquea (y se informa una sola vez)
+        assert!(attempts.record_failure("ana@example.com"));
+        let remaining = attempts.locked_for("ana@example.com").expect("cuenta bloqueada");
+        assert!(remaining <= Duration::from_secs(900));
+        assert!(!attempts.record_failure("ana@example.com"));
+
+        // Otro email no se ve afectado
+        assert!(attempts.locked_for("otro@example.com").is_none());
+    }
+
+    #[test]
+    fn lock_expires_after_window() {
+        let attempts = LoginAttempts::new(2, Duration::from_millis(50));
+        attempts.record_failure("ana@example.com");
+        attempts.record_failure("ana@example.com");
+        assert!(attempts.locked_for("ana@example.com").is_some());
+
+        std::thread::sleep(Duration::from_millis(80));
+        assert!(attempts.locked_for("ana@example.com").is_none());
+
+        // Tras la ventana el contador empieza de nuevo
+        assert!(!attempts.record_failure("ana@example.com"));
+        assert!(attempts.locked_for("ana@example.com").is_none());
+    }
+
+    #[test]
+    fn reset_clears_failures() {
+        let attempts = LoginAttempts::new(3, Duration::from_secs(900));
+        attempts.record_failure("ana@example.com");
+        attempts.record_failure("ana@example.com");
+        attempts.reset("ana@example.com");
+
+        assert!(!attempts.record_failure("ana@example.com"));
+        assert!(!attempts.record_failure("ana@example.com"));
+        assert!(attempts.locked_for("ana@example.com").is_none());
+    }
+}
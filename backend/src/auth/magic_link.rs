@@ -0,0 +1,150 @@
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::sync::OnceLock;
+
+// Acceso sin contraseña por enlace de un solo uso
+//   MAGIC_LINK_SECRET            clave de firma propia (sin ella la función queda desactivada)
+//   MAGIC_LINK_TTL_MINUTES=15    vigencia del enlace
+//   MAGIC_LINK_URL               página del frontend que recibe el token
+struct MagicLinkConfig {
+    secret: Option<Vec<u8>>,
+    ttl: Duration,
+    url: String,
+}
+
+fn config() -> &'static MagicLinkConfig {
+    static CONFIG: OnceLock<MagicLinkConfig> = OnceLock::new();
+    CONFIG.get_or_init(|| MagicLinkConfig {
+        secret: std::env::var("MAGIC_LINK_SECRET")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .map(String::into_bytes),
+        ttl: Duration::minutes(
+            std::env::var("MAGIC_LINK_TTL_MINUTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(15),
+        ),
+        url: std::env::var("MAGIC_LINK_URL")
+            .unwrap_or_else(|_| "http://localhost:5173/magic-link".to_string()),
+    })
+}
+
+pub fn is_enabled() -> bool {
+    config().secret.is_some()
+}
+
+pub fn link_url(token: &str) -> String {
+    format!("{}?token={}", config().url, token)
+}
+
+// Resultado de canjear un enlace
+pub enum Redemption {
+    Valid(i32),
+    Expired,
+    AlreadyUsed,
+    Unknown,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn signature(secret: &[u8], nonce: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC acepta claves de cualquier tamaño");
+    mac.update(nonce.as_bytes());
+    hex(&mac.finalize().into_bytes())
+}
+
+// Token "<nonce>.<firma>": la firma descarta tokens inventados sin ir a la BD
+fn has_valid_signature(token: &str) -> bool {
+    let (Some(secret), Some((nonce, sig))) = (config().secret.as_deref(), token.split_once('.')) else {
+        return false;
+    };
+    let Some(sig) = from_hex(sig) else {
+        return false;
+    };
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC acepta claves de cualquier tamaño");
+    mac.update(nonce.as_bytes());
+    mac.verify_slice(&sig).is_ok()
+}
+
+// En BD nunca se guarda el token en claro
+fn hash_token(token: &str) -> String {
+    hex(&Sha256::digest(token.as_bytes()))
+}
+
+// Crear un enlace para el usuario; devuelve el token y su vencimiento
+pub async fn create(pool: &PgPool, user_id: i32) -> Result<Option<(String, DateTime<Utc>)>, sqlx::Error> {
+    let Some(secret) = config().secret.as_deref() else {
+        return Ok(None);
+    };
+
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let nonce = hex(&bytes);
+    let token = format!("{}.{}", nonce, signature(secret, &nonce));
+    let expires_at = Utc::now() + config().ttl;
+
+    sqlx::query("INSERT INTO magic_links (user_id, token_hash, expires_at) VALUES ($1, $2, $3)")
+        .bind(user_id)
+        .bind(hash_token(&token))
+        .bind(expires_at)
+        .execute(pool)
+        .await?;
+
+    Ok(Some((token, expires_at)))
+}
+
+// Canjear un enlace. Marcarlo como usado en el mismo UPDATE garantiza un solo uso,
+// incluso con requests concurrentes
+pub async fn redeem(pool: &PgPool, token: &str) -> Result<Redemption, sqlx::Error> {
+    if !has_valid_signature(token) {
+        return Ok(Redemption::Unknown);
+    }
+    let token_hash = hash_token(token);
+
+    let redeemed: Option<(i32, DateTime<Utc>)> = sqlx::query_as(
+        "UPDATE magic_links SET used_at = NOW()
+         WHERE token_hash = $1 AND used_at IS NULL
+         RETURNING user_id, expires_at"
+    )
+    .bind(&token_hash)
+    .fetch_optional(pool)
+    .await?;
+
+    match redeemed {
+        Some((user_id, expires_at)) if expires_at > Utc::now() => Ok(Redemption::Valid(user_id)),
+        Some(_) => Ok(Redemption::Expired),
+        None => {
+            let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM magic_links WHERE token_hash = $1)")
+                .bind(&token_hash)
+                .fetch_one(pool)
+                .await?;
+            Ok(if exists { Redemption::AlreadyUsed } else { Redemption::Unknown })
+        }
+    }
+}
+
+// Borrar enlaces vencidos hace más de un día (tarea de limpieza)
+pub async fn purge_expired(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM magic_links WHERE expires_at < NOW() - INTERVAL '1 day'")
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
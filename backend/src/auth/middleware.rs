@@ -1,55 +1,305 @@
 use axum::{
-    extract::{Request, State},
-    http::{HeaderMap, StatusCode},
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
     Json,
 };
 use sqlx::PgPool;
-use crate::auth::jwt::{verify_token, extract_token_from_header};
-use crate::models::auth::{AuthError, Claims};
-use crate::models::user::User;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use crate::auth::cookie::request_token;
+use crate::auth::jwt::verify_token;
+use crate::database::query_log::{self, DEBUG_QUERIES_HEADER};
+use crate::logging::timing::{self, RequestTiming, PHASE_AUTH};
+use crate::logging::{middleware::get_client_ip, Logger, RequestId};
+use crate::models::auth::{AuthError, Claims, TOKEN_TYPE_ACCESS};
+use crate::models::user::{Role, User};
 
 // Extension para agregar el usuario autenticado al request
 #[derive(Clone)]
 pub struct AuthUser {
     pub user: User,
     pub claims: Claims,
+    // Scopes de la API key usada; None = sesión JWT con acceso completo
+    pub scopes: Option<Vec<String>>,
+    // Admin que suplanta al usuario (token de POST /admin/impersonate/:user_id)
+    pub impersonated_by: Option<i32>,
 }
 
-// Middleware para verificar autenticación
+impl AuthUser {
+    pub fn is_impersonated(&self) -> bool {
+        self.impersonated_by.is_some()
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes
+            .as_ref()
+            .is_none_or(|scopes| scopes.iter().any(|s| s == scope))
+    }
+}
+
+fn wants_debug_queries(headers: &HeaderMap) -> bool {
+    headers
+        .get(DEBUG_QUERIES_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+        .unwrap_or(false)
+}
+
+// Scope que exige un grupo de rutas a las API keys (ver required_scope_middleware)
+#[derive(Clone, Copy)]
+pub struct RequiredScope(pub &'static str);
+
+// Declara el scope requerido; debe aplicarse como capa externa a auth_middleware.
+// Las API keys solo se aceptan en rutas que declaran un scope
+pub async fn required_scope_middleware(
+    State(scope): State<&'static str>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    request.extensions_mut().insert(RequiredScope(scope));
+    next.run(request).await
+}
+
+fn database_error() -> (StatusCode, Json<AuthError>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(AuthError::new("database_error", "Error de base de datos")),
+    )
+}
+
+// Convertir un rechazo de autenticación en respuesta; los 401 llevan WWW-Authenticate
+// (RFC 6750) para que los clientes estándar sepan que deben (re)autenticarse
+fn auth_rejection((status, Json(error)): (StatusCode, Json<AuthError>)) -> Response {
+    let challenge = (status == StatusCode::UNAUTHORIZED).then(|| {
+        if error.error == "missing_token" {
+            // Sin credenciales no se informa código de error
+            HeaderValue::from_static("Bearer realm=\"venta-libre-api\"")
+        } else {
+            HeaderValue::from_static("Bearer realm=\"venta-libre-api\", error=\"invalid_token\"")
+        }
+    });
+
+    let mut response = (status, Json(error)).into_response();
+    if let Some(challenge) = challenge {
+        response.headers_mut().insert(header::WWW_AUTHENTICATE, challenge);
+    }
+    response
+}
+
+// Middleware para verificar autenticación (Bearer JWT o header X-Api-Key)
 pub async fn auth_middleware(
+    state: State<PgPool>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Response {
+    authenticate(state, headers, request, next)
+        .await
+        .unwrap_or_else(auth_rejection)
+}
+
+async fn authenticate(
     State(pool): State<PgPool>,
     headers: HeaderMap,
     mut request: Request,
     next: Next,
 ) -> Result<Response, (StatusCode, Json<AuthError>)> {
-    // Extraer token del header Authorization
-    let auth_header = headers
-        .get("authorization")
-        .and_then(|h| h.to_str().ok())
+    // Verificación del token y lectura del usuario: fase "auth" de Server-Timing
+    let started = std::time::Instant::now();
+    let timing = request.extensions().get::<RequestTiming>().cloned();
+    let authenticated = match headers.get("x-api-key").and_then(|h| h.to_str().ok()) {
+        Some(raw_key) => authenticate_api_key(&pool, raw_key).await,
+        None => authenticate_jwt(&pool, &headers)
+            .await
+            .map(|(user, claims)| (user, claims, None)),
+    };
+    timing::record_phase(timing.as_ref(), PHASE_AUTH, started);
+    let (user, claims, scopes) = authenticated?;
+    let impersonated_by = claims.impersonated_by;
+    let auth_user = AuthUser { user, claims, scopes, impersonated_by };
+
+    // Las API keys solo se aceptan en rutas que declaran un scope que la key tenga
+    if auth_user.scopes.is_some() {
+        let required = request.extensions().get::<RequiredScope>().map(|r| r.0);
+        if !required.is_some_and(|scope| auth_user.has_scope(scope)) {
+            let scope = required.unwrap_or("none");
+            tracing::warn!(
+                event = "api_key_scope_denied",
+                user_id = auth_user.user.id,
+                required_scope = %scope,
+                "🔑 API key sin el scope requerido"
+            );
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(AuthError::insufficient_scope(scope)),
+            ));
+        }
+    }
+
+    // Verificar que el usuario esté activo
+    if !auth_user.user.is_active {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(AuthError::new("user_inactive", "Usuario inactivo")),
+        ));
+    }
+
+    // Cada request bajo suplantación queda registrado a nombre del admin original
+    if let Some(admin_id) = auth_user.impersonated_by {
+        let client_ip = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| get_client_ip(&headers, addr));
+        let request_id = request
+            .extensions()
+            .get::<RequestId>()
+            .map(|id| id.0.clone())
+            .unwrap_or_default();
+        Logger::log_auth_event(
+            "impersonated_request",
+            Some(admin_id),
+            Some(&auth_user.user.email),
+            client_ip.as_deref(),
+            true,
+            &request_id,
+        );
+    }
+
+    // Cupo de requests simultáneos por usuario (los admins quedan exentos);
+    // el permiso se libera al terminar el request
+    let limiter = crate::auth::concurrency::user_concurrency();
+    let _permit = if auth_user.user.is_admin() {
+        None
+    } else {
+        match limiter.try_acquire(auth_user.user.id) {
+            Some(permit) => Some(permit),
+            None => {
+                tracing::warn!(
+                    event = "concurrency_limit",
+                    user_id = auth_user.user.id,
+                    max = limiter.max(),
+                    "🚦 Límite de requests simultáneos alcanzado"
+                );
+                return Err((
+                    StatusCode::TOO_MANY_REQUESTS,
+                    Json(AuthError::concurrency_limit(limiter.max())),
+                ));
+            }
+        }
+    };
+
+    // Solo los admins pueden pedir el detalle de queries del request
+    let debug_queries = auth_user.user.is_admin() && wants_debug_queries(request.headers());
+
+    // Agregar usuario autenticado al request
+    request.extensions_mut().insert(auth_user.clone());
+
+    // También en la respuesta, para que logging y métricas registren el user_id
+    let mut response = if debug_queries {
+        let (mut response, query_debug) = query_log::capture(next.run(request)).await;
+        tracing::info!(
+            event = "debug_queries",
+            user_id = auth_user.user.id,
+            total_queries = query_debug.total_queries,
+            total_duration_ms = query_debug.total_duration_ms,
+            truncated = query_debug.truncated,
+            queries = ?query_debug.queries,
+            "🔍 Queries del request"
+        );
+        response.extensions_mut().insert(query_debug);
+        response
+    } else {
+        next.run(request).await
+    };
+    if response.status().is_success() {
+        add_token_expiry_headers(&mut response, &auth_user.claims);
+    }
+    response.extensions_mut().insert(auth_user);
+
+    Ok(response)
+}
+
+// Ventana antes del vencimiento en la que se recomienda renovar
+// (TOKEN_REFRESH_WARNING_SECS, por defecto 300)
+fn refresh_warning_secs() -> i64 {
+    static WARNING: OnceLock<i64> = OnceLock::new();
+    *WARNING.get_or_init(|| {
+        std::env::var("TOKEN_REFRESH_WARNING_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300)
+    })
+}
+
+// X-Token-Expires-In (segundos restantes) y X-Token-Refresh-Recommended dentro de la ventana;
+// solo para tokens JWT de acceso (las API keys no se renuevan)
+fn add_token_expiry_headers(response: &mut Response, claims: &Claims) {
+    if claims.typ != TOKEN_TYPE_ACCESS {
+        return;
+    }
+
+    let expires_in = (claims.exp as i64 - chrono::Utc::now().timestamp()).max(0);
+    let headers = response.headers_mut();
+    headers.insert("x-token-expires-in", HeaderValue::from(expires_in));
+    if expires_in <= refresh_warning_secs() {
+        headers.insert("x-token-refresh-recommended", HeaderValue::from_static("true"));
+    }
+}
+
+// Autenticación con API key (servidor a servidor)
+async fn authenticate_api_key(
+    pool: &PgPool,
+    raw_key: &str,
+) -> Result<(User, Claims, Option<Vec<String>>), (StatusCode, Json<AuthError>)> {
+    let (user, api_key) = crate::auth::api_keys::authenticate(pool, raw_key.trim())
+        .await
+        .map_err(|_| database_error())?
         .ok_or_else(|| {
             (
                 StatusCode::UNAUTHORIZED,
-                Json(AuthError::new("missing_token", "Token de autorización requerido")),
+                Json(AuthError::new("invalid_api_key", "API key inválida, vencida o revocada")),
             )
         })?;
 
-    let token = extract_token_from_header(auth_header).ok_or_else(|| {
+    let claims = crate::auth::api_keys::claims_for(&user, &api_key);
+    Ok((user, claims, Some(api_key.scopes)))
+}
+
+// Autenticación con token JWT de acceso (header Authorization: Bearer)
+async fn authenticate_jwt(
+    pool: &PgPool,
+    headers: &HeaderMap,
+) -> Result<(User, Claims), (StatusCode, Json<AuthError>)> {
+    // Extraer token del header Authorization (o de la cookie de sesión)
+    let token = request_token(headers)?;
+
+    // Verificar token
+    let claims = verify_token(token).map_err(|e| {
         (
             StatusCode::UNAUTHORIZED,
-            Json(AuthError::new("invalid_format", "Formato de token inválido")),
+            Json(AuthError::from_jwt_error(&e)),
         )
     })?;
 
-    // Verificar token
-    let claims = verify_token(token).map_err(|_| {
+    // Rechazar tokens revocados (logout)
+    let revoked = crate::auth::revocation::is_revoked(pool, &claims).await.map_err(|_| {
         (
-            StatusCode::UNAUTHORIZED,
-            Json(AuthError::invalid_token()),
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
         )
     })?;
 
+    if revoked {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(AuthError::token_revoked()),
+        ));
+    }
+
     // Buscar usuario en base de datos
     let user_id: i32 = claims.sub.parse().map_err(|_| {
         (
@@ -59,11 +309,11 @@ pub async fn auth_middleware(
     })?;
 
     let user = sqlx::query_as::<_, User>(
-        "SELECT id, name, email, password_hash, is_admin, is_active, created_at, updated_at 
+        "SELECT id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at 
          FROM users WHERE id = $1 AND is_active = true"
     )
     .bind(user_id)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await
     .map_err(|_| {
         (
@@ -78,22 +328,46 @@ pub async fn auth_middleware(
         )
     })?;
 
-    // Verificar que el usuario esté activo
-    if !user.is_active {
+    // Tokens emitidos antes de una invalidación global (token_version)
+    if crate::auth::jwt::is_token_stale(&claims, &user) {
         return Err((
             StatusCode::UNAUTHORIZED,
-            Json(AuthError::new("user_inactive", "Usuario inactivo")),
+            Json(AuthError::token_revoked()),
         ));
     }
 
-    // Agregar usuario autenticado al request
-    request.extensions_mut().insert(AuthUser { user, claims });
+    // La sesión del token debe seguir vigente (se puede revocar desde /auth/sessions)
+    if let Some(session_id) = claims.sid {
+        let active = crate::auth::sessions::touch(pool, session_id, user.id, None)
+            .await
+            .map_err(|_| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(AuthError::new("database_error", "Error de base de datos")),
+                )
+            })?;
+        if !active {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                Json(AuthError::token_revoked()),
+            ));
+        }
+    }
 
-    Ok(next.run(request).await)
+    Ok((user, claims))
+}
+
+// Futuro devuelto por el middleware de require_role
+pub type RoleCheck = Pin<Box<dyn Future<Output = Result<Response, (StatusCode, Json<AuthError>)>> + Send>>;
+
+// Middleware que exige un rol mínimo; debe aplicarse como capa interna a auth_middleware:
+// `.route_layer(middleware::from_fn(require_role(Role::Admin)))`
+pub fn require_role(role: Role) -> impl Fn(Request, Next) -> RoleCheck + Clone + Send + Sync + 'static {
+    move |request, next| Box::pin(check_role(role, request, next))
 }
 
-// Middleware para verificar que el usuario sea admin
-pub async fn admin_middleware(
+async fn check_role(
+    role: Role,
     request: Request,
     next: Next,
 ) -> Result<Response, (StatusCode, Json<AuthError>)> {
@@ -108,8 +382,15 @@ pub async fn admin_middleware(
             )
         })?;
 
-    // Verificar que sea admin
-    if !auth_user.user.is_admin() {
+    // Un token de suplantación nunca da acceso de admin, aunque el usuario lo sea
+    if !auth_user.user.has_role(role) || (role == Role::Admin && auth_user.is_impersonated()) {
+        tracing::warn!(
+            event = "role_denied",
+            user_id = auth_user.user.id,
+            user_role = auth_user.user.role.as_str(),
+            required_role = role.as_str(),
+            "⛔ Rol insuficiente para la ruta"
+        );
         return Err((
             StatusCode::FORBIDDEN,
             Json(AuthError::forbidden()),
@@ -119,13 +400,38 @@ pub async fn admin_middleware(
     Ok(next.run(request).await)
 }
 
+// Middleware para rutas que tocan las credenciales o sesiones del usuario: un admin que lo
+// suplanta no puede usarlas. Capa interna a auth_middleware, como require_role
+pub async fn deny_impersonation(
+    request: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, Json<AuthError>)> {
+    if let Some(auth_user) = request.extensions().get::<AuthUser>() {
+        if auth_user.is_impersonated() {
+            tracing::warn!(
+                event = "impersonation_denied",
+                user_id = auth_user.user.id,
+                admin_id = auth_user.impersonated_by,
+                path = %request.uri().path(),
+                "⛔ Ruta no disponible con un token de suplantación"
+            );
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(AuthError::impersonation_not_allowed()),
+            ));
+        }
+    }
+
+    Ok(next.run(request).await)
+}
+
 // Extractor para obtener el usuario autenticado fácilmente
 #[axum::async_trait]
 impl<S> axum::extract::FromRequestParts<S> for AuthUser
 where
     S: Send + Sync,
 {
-    type Rejection = (StatusCode, Json<AuthError>);
+    type Rejection = Response;
 
     async fn from_request_parts(
         parts: &mut axum::http::request::Parts,
@@ -136,10 +442,40 @@ where
             .get::<AuthUser>()
             .cloned()
             .ok_or_else(|| {
-                (
+                auth_rejection((
                     StatusCode::UNAUTHORIZED,
                     Json(AuthError::unauthorized()),
-                )
+                ))
             })
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use axum::http::{header, Method, StatusCode};
+    use crate::test_support::{api, lazy_pool, send};
+
+    // Los rechazos por token ausente o ilegible ocurren antes de tocar la BD
+    #[tokio::test]
+    async fn missing_token_gets_bearer_challenge() {
+        let app = api(lazy_pool());
+
+        let (status, headers, body) = send(&app, Method::POST, "/api/v1/auth/logout-all", None, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "missing_token");
+        assert_eq!(headers[header::WWW_AUTHENTICATE], "Bearer realm=\"venta-libre-api\"");
+    }
+
+    #[tokio::test]
+    async fn invalid_token_challenge_reports_invalid_token() {
+        let app = api(lazy_pool());
+
+        let (status, headers, body) =
+            send(&app, Method::GET, "/api/v1/auth/sessions", Some("no-es-un-jwt"), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "invalid_token");
+        assert_eq!(
+            headers[header::WWW_AUTHENTICATE],
+            "Bearer realm=\"venta-libre-api\", error=\"invalid_token\""
+        );
+    }
+}
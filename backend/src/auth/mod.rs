@@ -1,5 +1,19 @@
+pub mod account_deletion;
+pub mod api_keys;
+pub mod concurrency;
+pub mod cookie;
+pub mod devices;
 pub mod jwt;
+pub mod lockout;
+pub mod magic_link;
 pub mod middleware;
+pub mod oauth;
+pub mod password;
+pub mod rate_limit;
+pub mod revocation;
+pub mod sessions;
+pub mod totp;
 
 pub use jwt::*;
-pub use middleware::*;
\ No newline at end of file
+pub use middleware::*;
+pub use password::{hash_password, verify_password};
\ No newline at end of file
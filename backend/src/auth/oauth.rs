@@ -0,0 +1,227 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use crate::outbound::{self, http_client};
+
+const GOOGLE_AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const GOOGLE_USERINFO_URL: &str = "https://openidconnect.googleapis.com/v1/userinfo";
+
+// Destino en el registro de llamadas salientes
+const DESTINATION: &str = "google_oauth";
+
+// Nombre de la cookie que ata el `state` al navegador que inició el flujo
+pub const STATE_COOKIE: &str = "oauth_state";
+
+// Máximo de flujos pendientes antes de purgar los vencidos
+const MAX_PENDING_STATES: usize = 10_000;
+
+// Credenciales de Google (GOOGLE_CLIENT_ID, GOOGLE_CLIENT_SECRET, GOOGLE_REDIRECT_URL);
+// si falta alguna el login con Google queda deshabilitado
+pub struct GoogleOAuthConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_url: String,
+}
+
+impl GoogleOAuthConfig {
+    fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+
+        Some(Self {
+            client_id: var("GOOGLE_CLIENT_ID")?,
+            client_secret: var("GOOGLE_CLIENT_SECRET")?,
+            redirect_url: var("GOOGLE_REDIRECT_URL")?,
+        })
+    }
+
+    // URL de autorización con state y desafío PKCE (S256)
+    pub fn authorization_url(&self, state: &str, code_challenge: &str) -> String {
+        reqwest::Url::parse_with_params(
+            GOOGLE_AUTH_URL,
+            &[
+                ("client_id", self.client_id.as_str()),
+                ("redirect_uri", self.redirect_url.as_str()),
+                ("response_type", "code"),
+                ("scope", "openid email profile"),
+                ("state", state),
+                ("code_challenge", code_challenge),
+                ("code_challenge_method", "S256"),
+                ("prompt", "select_account"),
+            ],
+        )
+        .map(String::from)
+        .unwrap_or_else(|_| GOOGLE_AUTH_URL.to_string())
+    }
+}
+
+pub fn google_config() -> Option<&'static GoogleOAuthConfig> {
+    static CONFIG: OnceLock<Option<GoogleOAuthConfig>> = OnceLock::new();
+    CONFIG.get_or_init(GoogleOAuthConfig::from_env).as_ref()
+}
+
+#[derive(Debug)]
+pub struct OAuthError(String);
+
+impl std::fmt::Display for OAuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error de OAuth: {}", self.0)
+    }
+}
+
+impl std::error::Error for OAuthError {}
+
+impl From<reqwest::Error> for OAuthError {
+    fn from(e: reqwest::Error) -> Self {
+        Self(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for OAuthError {
+    fn from(e: serde_json::Error) -> Self {
+        Self(e.to_string())
+    }
+}
+
+// Flujo iniciado y todavía no completado
+pub struct PendingAuthorization {
+    pub code_verifier: String,
+    // Usuario autenticado que pidió vincular su cuenta (None = login normal)
+    pub link_user_id: Option<i32>,
+    created_at: Instant,
+}
+
+// States emitidos, de un solo uso y con vencimiento (OAUTH_STATE_TTL_SECS, por defecto 600)
+pub struct OAuthStates {
+    ttl: Duration,
+    pending: Mutex<HashMap<String, PendingAuthorization>>,
+}
+
+impl OAuthStates {
+    fn from_env() -> Self {
+        let ttl_secs = std::env::var("OAUTH_STATE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(600u64);
+
+        Self {
+            ttl: Duration::from_secs(ttl_secs),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    // Registrar un flujo nuevo; devuelve (state, code_challenge)
+    pub fn begin(&self, link_user_id: Option<i32>) -> (String, String) {
+        let state = random_token();
+        let code_verifier = random_token();
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        if pending.len() >= MAX_PENDING_STATES {
+            let ttl = self.ttl;
+            pending.retain(|_, flow| flow.created_at.elapsed() < ttl);
+        }
+        pending.insert(
+            state.clone(),
+            PendingAuthorization {
+                code_verifier,
+                link_user_id,
+                created_at: Instant::now(),
+            },
+        );
+
+        (state, code_challenge)
+    }
+
+    // Consumir un state: desconocido, ya usado o vencido devuelve None
+    pub fn take(&self, state: &str) -> Option<PendingAuthorization> {
+        let flow = self
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(state)?;
+
+        (flow.created_at.elapsed() < self.ttl).then_some(flow)
+    }
+}
+
+pub fn oauth_states() -> &'static OAuthStates {
+    static STATES: OnceLock<OAuthStates> = OnceLock::new();
+    STATES.get_or_init(OAuthStates::from_env)
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// Perfil devuelto por Google (OpenID Connect userinfo)
+#[derive(Debug, Deserialize)]
+pub struct GoogleProfile {
+    pub sub: String,
+    pub email: String,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+// Canjear el código de autorización y obtener el perfil del usuario
+pub async fn fetch_google_profile(
+    config: &GoogleOAuthConfig,
+    code: &str,
+    code_verifier: &str,
+) -> Result<GoogleProfile, OAuthError> {
+    let client = http_client();
+
+    let response = outbound::send(
+        DESTINATION,
+        client.post(GOOGLE_TOKEN_URL).form(&[
+            ("code", code),
+            ("client_id", config.client_id.as_str()),
+            ("client_secret", config.client_secret.as_str()),
+            ("redirect_uri", config.redirect_url.as_str()),
+            ("grant_type", "authorization_code"),
+            ("code_verifier", code_verifier),
+        ]),
+    )
+    .await?;
+
+    if !response.is_success() {
+        return Err(OAuthError(format!(
+            "canje de código rechazado ({})",
+            response.status
+        )));
+    }
+
+    let token: TokenResponse = response.json()?;
+
+    let response = outbound::send(
+        DESTINATION,
+        client.get(GOOGLE_USERINFO_URL).bearer_auth(&token.access_token),
+    )
+    .await?;
+
+    if !response.is_success() {
+        return Err(OAuthError(format!(
+            "perfil de Google rechazado ({})",
+            response.status
+        )));
+    }
+
+    Ok(response.json()?)
+}
@@ -0,0 +1,190 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use bcrypt::DEFAULT_COST;
+use rand::RngCore;
+use std::sync::OnceLock;
+
+// Costo mínimo recomendado para producción
+const RECOMMENDED_MIN_COST: u32 = 10;
+
+// Algoritmo para hashes nuevos (PASSWORD_HASH_ALGO=argon2|bcrypt, por defecto Argon2id)
+// La verificación decide por el prefijo del hash guardado, así ambos conviven
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PasswordAlgo {
+    Bcrypt,
+    Argon2,
+}
+
+impl PasswordAlgo {
+    fn from_env() -> Self {
+        match std::env::var("PASSWORD_HASH_ALGO")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "bcrypt" => Self::Bcrypt,
+            _ => Self::Argon2,
+        }
+    }
+
+    fn of_hash(stored: &str) -> Option<Self> {
+        if stored.starts_with("$argon2") {
+            Some(Self::Argon2)
+        } else if stored.starts_with("$2") {
+            Some(Self::Bcrypt)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PasswordError(String);
+
+impl std::fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error de hash de contraseña: {}", self.0)
+    }
+}
+
+impl std::error::Error for PasswordError {}
+
+pub fn configured_algo() -> PasswordAlgo {
+    static ALGO: OnceLock<PasswordAlgo> = OnceLock::new();
+    *ALGO.get_or_init(PasswordAlgo::from_env)
+}
+
+// Costo de bcrypt (BCRYPT_COST, limitado a 4..=31; por defecto DEFAULT_COST)
+pub fn bcrypt_cost() -> u32 {
+    static COST: OnceLock<u32> = OnceLock::new();
+    *COST.get_or_init(|| parse_bcrypt_cost(std::env::var("BCRYPT_COST").ok().as_deref()))
+}
+
+fn parse_bcrypt_cost(value: Option<&str>) -> u32 {
+    value
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .map(|cost| cost.clamp(4, 31))
+        .unwrap_or(DEFAULT_COST)
+}
+
+// Parámetros de Argon2id: ARGON2_MEMORY_KIB (por defecto 19456), ARGON2_ITERATIONS
+// (por defecto 2) y ARGON2_PARALLELISM (por defecto 1); valores inválidos usan los por defecto
+pub fn argon2_params() -> &'static Params {
+    static PARAMS: OnceLock<Params> = OnceLock::new();
+    PARAMS.get_or_init(|| {
+        let var = |name: &str, default: u32| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Params::new(
+            var("ARGON2_MEMORY_KIB", Params::DEFAULT_M_COST),
+            var("ARGON2_ITERATIONS", Params::DEFAULT_T_COST),
+            var("ARGON2_PARALLELISM", Params::DEFAULT_P_COST),
+            None,
+        )
+        .unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "⚠️ Parámetros de Argon2 inválidos, se usan los valores por defecto");
+            Params::default()
+        })
+    })
+}
+
+fn argon2() -> Argon2<'static> {
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, argon2_params().clone())
+}
+
+// Avisar al iniciar si el costo configurado es bajo
+pub fn warn_if_weak_cost() {
+    let cost = bcrypt_cost();
+    if configured_algo() == PasswordAlgo::Bcrypt && cost < RECOMMENDED_MIN_COST {
+        tracing::warn!(
+            bcrypt_cost = cost,
+            recommended_min = RECOMMENDED_MIN_COST,
+            "⚠️ BCRYPT_COST por debajo del mínimo recomendado"
+        );
+    }
+}
+
+pub fn hash_password(password: &str) -> Result<String, PasswordError> {
+    match configured_algo() {
+        PasswordAlgo::Bcrypt => {
+            bcrypt::hash(password, bcrypt_cost()).map_err(|e| PasswordError(e.to_string()))
+        }
+        PasswordAlgo::Argon2 => {
+            let mut salt = [0u8; 16];
+            rand::thread_rng().fill_bytes(&mut salt);
+            let salt = SaltString::encode_b64(&salt).map_err(|e| PasswordError(e.to_string()))?;
+
+            argon2()
+                .hash_password(password.as_bytes(), &salt)
+                .map(|hash| hash.to_string())
+                .map_err(|e| PasswordError(e.to_string()))
+        }
+    }
+}
+
+pub fn verify_password(password: &str, stored: &str) -> Result<bool, PasswordError> {
+    match PasswordAlgo::of_hash(stored) {
+        Some(PasswordAlgo::Bcrypt) => {
+            bcrypt::verify(password, stored).map_err(|e| PasswordError(e.to_string()))
+        }
+        Some(PasswordAlgo::Argon2) => {
+            let parsed = PasswordHash::new(stored).map_err(|e| PasswordError(e.to_string()))?;
+            // Los parámetros salen del propio hash, así verifican también los anteriores
+            Ok(argon2()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok())
+        }
+        None => Err(PasswordError("formato de hash desconocido".to_string())),
+    }
+}
+
+// El hash guardado usa otro algoritmo, o Argon2 con otros parámetros, que el configurado
+// (PASSWORD_REHASH_ON_LOGIN=false desactiva la migración en el login)
+pub fn needs_rehash(stored: &str) -> bool {
+    let enabled = std::env::var("PASSWORD_REHASH_ON_LOGIN")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true);
+
+    if !enabled {
+        return false;
+    }
+
+    match (PasswordAlgo::of_hash(stored), configured_algo()) {
+        (Some(PasswordAlgo::Argon2), PasswordAlgo::Argon2) => {
+            let configured = argon2_params();
+            PasswordHash::new(stored)
+                .ok()
+                .filter(|hash| hash.algorithm == Algorithm::Argon2id.ident())
+                .and_then(|hash| Params::try_from(&hash).ok())
+                .is_none_or(|params| {
+                    params.m_cost() != configured.m_cost()
+                        || params.t_cost() != configured.t_cost()
+                        || params.p_cost() != configured.p_cost()
+                })
+        }
+        (algo, configured) => algo != Some(configured),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bcrypt_cost_is_clamped() {
+        assert_eq!(parse_bcrypt_cost(None), DEFAULT_COST);
+        assert_eq!(parse_bcrypt_cost(Some("12")), 12);
+        assert_eq!(parse_bcrypt_cost(Some(" 10 ")), 10);
+        assert_eq!(parse_bcrypt_cost(Some("0")), 4);
+        assert_eq!(parse_bcrypt_cost(Some("3")), 4);
+        assert_eq!(parse_bcrypt_cost(Some("32")), 31);
+        assert_eq!(parse_bcrypt_cost(Some("1000")), 31);
+        assert_eq!(parse_bcrypt_cost(Some("alto")), DEFAULT_COST);
+        assert_eq!(parse_bcrypt_cost(Some("-5")), DEFAULT_COST);
+    }
+}
@@ -0,0 +1,251 @@
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use crate::config::app_config;
+use crate::logging::middleware::get_client_ip;
+use crate::models::auth::AuthError;
+
+// Máximo de claves (ruta, IP) antes de purgar las inactivas
+const MAX_TRACKED_KEYS: usize = 50_000;
+
+// Límite por ruta: `max` requests por IP dentro de `window`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub max: usize,
+    pub window: Duration,
+}
+
+impl RateLimit {
+    // Formato "max/segundos", p. ej. RATE_LIMIT_LOGIN=10/60
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        let (max, secs) = value.trim().split_once('/')?;
+        Some(Self {
+            max: max.trim().parse().ok()?,
+            window: Duration::from_secs(secs.trim().parse().ok()?),
+        })
+    }
+
+    // Límite de una ruta: el configurado (RATE_LIMIT_<RUTA> o CONFIG_FILE, recargable)
+    // o el valor por defecto de la ruta
+    fn for_route(route: &str) -> Self {
+        if let Some(limit) = app_config().rate_limits.get(&route_key(route)) {
+            return *limit;
+        }
+        let default = match route {
+            "login" => "10/60",
+            "register" | "forgot-password" | "magic-link" => "5/3600",
+            "magic-link-email" => "3/900",
+            "contact" => "3/86400",
+            "introspect" => "120/60",
+            _ => "30/60",
+        };
+
+        Self::parse(default).expect("límite por defecto válido")
+    }
+}
+
+// Nombre de la ruta tal como aparece en RATE_LIMIT_<RUTA>, p. ej. "forgot-password" → "FORGOT_PASSWORD"
+pub(crate) fn route_key(route: &str) -> String {
+    route.trim().to_uppercase().replace(['-', '/'], "_")
+}
+
+// Ventana deslizante por (ruta, IP). Los límites se leen de la configuración vigente en
+// cada request, así una recarga aplica sin perder los contadores
+pub struct RateLimiter {
+    hits: Mutex<HashMap<(String, String), VecDeque<Instant>>>,
+}
+
+impl RateLimiter {
+    fn new() -> Self {
+        Self {
+            hits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Registrar un intento; si se excede el límite devuelve cuánto esperar
+    pub fn check(&self, route: &str, ip: &str) -> Result<(), Duration> {
+        let limit = RateLimit::for_route(route);
+        let now = Instant::now();
+        let mut hits = self.hits.lock().unwrap_or_else(|e| e.into_inner());
+
+        if hits.len() >= MAX_TRACKED_KEYS {
+            Self::retain_active(&mut hits, now);
+        }
+
+        let times = hits.entry((route.to_string(), ip.to_string())).or_default();
+        while times.front().is_some_and(|t| now.duration_since(*t) >= limit.window) {
+            times.pop_front();
+        }
+
+        if times.len() >= limit.max {
+            let oldest = times.front().copied().unwrap_or(now);
+            return Err(limit.window.saturating_sub(now.duration_since(oldest)));
+        }
+
+        times.push_back(now);
+        Ok(())
+    }
+
+    // Purgar claves cuya ventana (la de su propia ruta) ya no tiene requests
+    fn retain_active(hits: &mut HashMap<(String, String), VecDeque<Instant>>, now: Instant) {
+        let mut windows: HashMap<String, Duration> = HashMap::new();
+        hits.retain(|(route, _), times| {
+            let window = *windows
+                .entry(route.clone())
+                .or_insert_with(|| RateLimit::for_route(route).window);
+            times.back().is_some_and(|t| now.duration_since(*t) < window)
+        });
+    }
+
+    // Limpieza periódica (tarea de mantenimiento); devuelve cuántas claves se eliminaron
+    pub fn purge_idle(&self) -> usize {
+        let mut hits = self.hits.lock().unwrap_or_else(|e| e.into_inner());
+        let before = hits.len();
+        Self::retain_active(&mut hits, Instant::now());
+        before - hits.len()
+    }
+}
+
+pub fn rate_limiter() -> &'static RateLimiter {
+    static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+    LIMITER.get_or_init(RateLimiter::new)
+}
+
+// Respuesta 429 con Retry-After (segundos, al menos 1) para un límite excedido
+pub fn rate_limited_response(route: &str, client_ip: &str, retry_after: Duration) -> Response {
+    let retry_after_secs = retry_after.as_secs().max(1);
+
+    tracing::warn!(
+        event = "rate_limited",
+        route = %route,
+        client_ip = %client_ip,
+        retry_after_secs = retry_after_secs,
+        "🚦 Límite de requests excedido"
+    );
+
+    let mut response = (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(AuthError::new(
+            "rate_limited",
+            "Demasiadas solicitudes. Intenta de nuevo más tarde",
+        )),
+    )
+        .into_response();
+    response.headers_mut().insert(
+        header::RETRY_AFTER,
+        HeaderValue::from(retry_after_secs),
+    );
+    response
+}
+
+// Middleware de rate limiting por IP para las rutas de auth
+pub async fn auth_rate_limit_middleware(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    matched_path: Option<MatchedPath>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = matched_path
+        .as_ref()
+        .map(|p| p.as_str())
+        .unwrap_or_else(|| request.uri().path());
+    let route = path
+        .rsplit_once("/auth/")
+        .map(|(_, route)| route)
+        .unwrap_or(path)
+        .trim_matches('/')
+        .to_string();
+    let client_ip = get_client_ip(&headers, &addr);
+
+    if let Err(retry_after) = rate_limiter().check(&route, &client_ip) {
+        return rate_limited_response(&route, &client_ip, retry_after);
+    }
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::post, Router};
+    use tower::ServiceExt;
+
+    async fn login_from(app: &Router, peer: &str, forwarded_for: &str) -> Response {
+        let mut request = axum::http::Request::post("/auth/login")
+            .header("x-forwarded-for", forwarded_for)
+            .body(Body::empty())
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 40000)));
+        app.clone().oneshot(request).await.unwrap()
+    }
+
+    fn login_app() -> Router {
+        Router::new()
+            .route("/auth/login", post(|| async { StatusCode::OK }))
+            .route_layer(axum::middleware::from_fn(auth_rate_limit_middleware))
+    }
+
+    #[test]
+    fn parses_limits() {
+        assert_eq!(
+            RateLimit::parse(" 10 / 60 "),
+            Some(RateLimit { max: 10, window: Duration::from_secs(60) })
+        );
+        assert_eq!(RateLimit::parse("10"), None);
+        assert_eq!(RateLimit::parse("diez/60"), None);
+        assert_eq!(route_key("forgot-password"), "FORGOT_PASSWORD");
+    }
+
+    #[test]
+    fn rejects_eleventh_login_in_window() {
+        let limiter = RateLimiter::new();
+        for _ in 0..10 {
+            assert!(limiter.check("login", "192.0.2.1").is_ok());
+        }
+
+        let retry_after = limiter.check("login", "192.0.2.1").expect_err("el request 11 se rechaza");
+        assert!(retry_after > Duration::ZERO && retry_after <= Duration::from_secs(60));
+
+        // Otra IP y otra ruta llevan su propio contador
+        assert!(limiter.check("login", "192.0.2.2").is_ok());
+        assert!(limiter.check("refresh", "192.0.2.1").is_ok());
+    }
+
+    #[tokio::test]
+    async fn middleware_answers_429_with_retry_after() {
+        let app = login_app();
+        for _ in 0..10 {
+            assert_eq!(login_from(&app, "192.0.2.10", "192.0.2.10").await.status(), StatusCode::OK);
+        }
+
+        let response = login_from(&app, "192.0.2.10", "192.0.2.10").await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response.headers()[header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+        assert!((1..=60).contains(&retry_after));
+    }
+
+    #[tokio::test]
+    async fn spoofed_forwarded_for_does_not_bypass_limit() {
+        // El peer no está en TRUSTED_PROXIES: cambiar X-Forwarded-For en cada request no
+        // genera claves nuevas, todo cuenta para la IP de la conexión
+        let app = login_app();
+        for i in 0..10 {
+            let spoofed = format!("203.0.113.{}", i);
+            assert_eq!(login_from(&app, "192.0.2.20", &spoofed).await.status(), StatusCode::OK);
+        }
+
+        let response = login_from(&app, "192.0.2.20", "203.0.113.250").await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+}
@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, PgPool, Postgres};
+use crate::models::auth::Claims;
+
+// Revocar un token de acceso hasta su expiración natural
+pub async fn revoke_token<'c, E>(executor: E, claims: &Claims) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    // Tokens emitidos antes de existir el jti no se pueden revocar; expiran solos
+    let Some(jti) = claims.jti.as_deref() else {
+        return Ok(());
+    };
+
+    let user_id: i32 = claims.sub.parse().unwrap_or_default();
+    let expires_at = DateTime::<Utc>::from_timestamp(claims.exp as i64, 0).unwrap_or_else(Utc::now);
+
+    sqlx::query(
+        "INSERT INTO revoked_tokens (jti, user_id, expires_at) VALUES ($1, $2, $3)
+         ON CONFLICT (jti) DO NOTHING"
+    )
+    .bind(jti)
+    .bind(user_id)
+    .bind(expires_at)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+// Comprobar si el token fue revocado
+pub async fn is_revoked(pool: &PgPool, claims: &Claims) -> Result<bool, sqlx::Error> {
+    let Some(jti) = claims.jti.as_deref() else {
+        return Ok(false);
+    };
+
+    let revoked: Option<i32> = sqlx::query_scalar("SELECT 1 FROM revoked_tokens WHERE jti = $1")
+        .bind(jti)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(revoked.is_some())
+}
+
+// Purgar revocaciones de tokens ya expirados (no pueden volver a usarse)
+pub async fn purge_expired(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM revoked_tokens WHERE expires_at < NOW()")
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,146 @@
+use axum::http::{header, HeaderMap};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Executor, FromRow, PgPool, Postgres};
+use std::net::SocketAddr;
+use crate::logging::middleware::get_client_ip;
+
+// Datos del cliente que inicia una sesión
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip: String,
+}
+
+impl ClientInfo {
+    pub fn from_request(headers: &HeaderMap, addr: &SocketAddr) -> Self {
+        Self {
+            user_agent: headers
+                .get(header::USER_AGENT)
+                .and_then(|h| h.to_str().ok())
+                .map(|ua| ua.chars().take(512).collect()),
+            ip: get_client_ip(headers, addr),
+        }
+    }
+}
+
+// Sesión tal como se lista al usuario. Cada login abre una; los refresh la mantienen
+// (todos los tokens de la cadena llevan su id en el claim `sid`)
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Session {
+    pub id: i32,
+    pub user_agent: Option<String>,
+    pub ip_address: String,
+    #[serde(with = "crate::models::timestamp")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::models::timestamp")]
+    pub last_seen_at: DateTime<Utc>,
+    // Se completa al listar: la sesión del token usado en el request
+    #[sqlx(skip)]
+    pub current: bool,
+}
+
+pub async fn create<'c, E>(executor: E, user_id: i32, client: &ClientInfo) -> Result<i32, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        "INSERT INTO sessions (user_id, user_agent, ip_address) VALUES ($1, $2, $3) RETURNING id"
+    )
+    .bind(user_id)
+    .bind(&client.user_agent)
+    .bind(&client.ip)
+    .fetch_one(executor)
+    .await
+}
+
+// Registrar actividad de una sesión vigente; false si fue revocada o no es del usuario.
+// `jti` se pasa al emitir tokens nuevos para recordar el token de acceso actual
+pub async fn touch<'c, E>(executor: E, session_id: i32, user_id: i32, jti: Option<&str>) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let result = sqlx::query(
+        "UPDATE sessions SET last_seen_at = NOW(), jti = COALESCE($3, jti)
+         WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .bind(jti)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn list(pool: &PgPool, user_id: i32) -> Result<Vec<Session>, sqlx::Error> {
+    sqlx::query_as::<_, Session>(
+        "SELECT id, user_agent, ip_address, created_at, last_seen_at
+         FROM sessions WHERE user_id = $1 AND revoked_at IS NULL
+         ORDER BY last_seen_at DESC"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn revoke<'c, E>(executor: E, user_id: i32, session_id: i32) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let result = sqlx::query(
+        "UPDATE sessions SET revoked_at = NOW() WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Revocar los refresh tokens vigentes de una sesión
+pub async fn revoke_refresh_tokens<'c, E>(executor: E, user_id: i32, session_id: i32) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let result = sqlx::query(
+        "UPDATE refresh_tokens SET revoked_at = NOW()
+         WHERE session_id = $1 AND user_id = $2 AND revoked_at IS NULL"
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// Revocar todas las sesiones del usuario, salvo opcionalmente una
+pub async fn revoke_all<'c, E>(executor: E, user_id: i32, except: Option<i32>) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let result = sqlx::query(
+        "UPDATE sessions SET revoked_at = NOW()
+         WHERE user_id = $1 AND revoked_at IS NULL AND ($2::INTEGER IS NULL OR id <> $2)"
+    )
+    .bind(user_id)
+    .bind(except)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// Borrar sesiones revocadas o sin actividad por más de `idle_days` (ya no pueden refrescarse)
+pub async fn purge_stale(pool: &PgPool, idle_days: i64) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "DELETE FROM sessions
+         WHERE revoked_at IS NOT NULL OR last_seen_at < NOW() - make_interval(days => $1::INTEGER)"
+    )
+    .bind(idle_days)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
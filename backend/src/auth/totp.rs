@@ -0,0 +1,182 @@
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+
+// TOTP según RFC 6238 (HMAC-SHA1, 6 dígitos, pasos de 30 segundos)
+const STEP_SECS: i64 = 30;
+const DIGITS: u32 = 6;
+// Pasos aceptados antes y después del actual (tolerancia de reloj)
+const WINDOW: i64 = 1;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+// Secreto aleatorio de 160 bits
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; 20];
+    rand::thread_rng().fill_bytes(&mut secret);
+    secret
+}
+
+// Base32 sin relleno (formato de las apps autenticadoras)
+pub fn base32_encode(data: &[u8]) -> String {
+    let mut output = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            output.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+
+    if bits > 0 {
+        output.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+
+    output
+}
+
+pub fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for c in encoded.trim_end_matches('=').bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a == c.to_ascii_uppercase())? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+        }
+    }
+
+    Some(output)
+}
+
+pub fn current_step() -> i64 {
+    chrono::Utc::now().timestamp() / STEP_SECS
+}
+
+fn code_at(secret: &[u8], step: i64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC acepta claves de cualquier tamaño");
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+
+    binary % 10u32.pow(DIGITS)
+}
+
+// Verificar un código; devuelve el paso que coincidió para impedir su reutilización
+pub fn verify_code(secret: &[u8], code: &str, last_used_step: Option<i64>) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let now = current_step();
+
+    (now - WINDOW..=now + WINDOW)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| code_at(secret, *step) == code)
+}
+
+// URI otpauth:// para registrar la cuenta en la app autenticadora (código QR)
+pub fn otpauth_uri(secret_base32: &str, account: &str) -> String {
+    let issuer = std::env::var("TOTP_ISSUER").unwrap_or_else(|_| "Venta Libre".to_string());
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        percent_encode(&issuer),
+        percent_encode(account),
+        secret_base32,
+        percent_encode(&issuer),
+        DIGITS,
+        STEP_SECS
+    )
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"12345678901234567890";
+
+    fn code(step: i64) -> String {
+        format!("{:06}", code_at(SECRET, step))
+    }
+
+    #[test]
+    fn matches_rfc6238_vectors() {
+        // RFC 6238, apéndice B (SHA1), truncado a 6 dígitos
+        assert_eq!(code(59 / STEP_SECS), "287082");
+        assert_eq!(code(1111111109 / STEP_SECS), "081804");
+        assert_eq!(code(1234567890 / STEP_SECS), "005924");
+    }
+
+    #[test]
+    fn accepts_current_code_and_adjacent_steps() {
+        let now = current_step();
+        assert!(verify_code(SECRET, &code(now), None).is_some());
+        assert!(verify_code(SECRET, &code(now - 1), None).is_some());
+        assert!(verify_code(SECRET, &code(now + 1), None).is_some());
+    }
+
+    #[test]
+    fn rejects_invalid_code() {
+        let now = current_step();
+        let valid: Vec<u32> = (now - 2..=now + 2).map(|step| code_at(SECRET, step)).collect();
+        let wrong = (0..).find(|c| !valid.contains(c)).unwrap();
+
+        assert!(verify_code(SECRET, &format!("{:06}", wrong), None).is_none());
+        assert!(verify_code(SECRET, "12345", None).is_none());
+        assert!(verify_code(SECRET, "abcdef", None).is_none());
+    }
+
+    #[test]
+    fn rejects_expired_step() {
+        let now = current_step();
+        assert!(verify_code(SECRET, &code(now - 3), None).is_none());
+        assert!(verify_code(SECRET, &code(now + 3), None).is_none());
+    }
+
+    #[test]
+    fn rejects_replayed_code() {
+        let now = current_step();
+        let step = verify_code(SECRET, &code(now), None).expect("código válido");
+        assert!(verify_code(SECRET, &code(now), Some(step)).is_none());
+        // Un código anterior al último usado tampoco sirve
+        assert!(verify_code(SECRET, &code(now - 1), Some(step)).is_none());
+    }
+
+    #[test]
+    fn base32_round_trip() {
+        let secret = generate_secret();
+        assert_eq!(base32_decode(&base32_encode(&secret)), Some(secret));
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert!(base32_decode("no-es-base32!").is_none());
+    }
+}
@@ -0,0 +1,130 @@
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use rand::Rng;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Configuración de inyección de fallos (solo para pruebas de resiliencia)
+//   CHAOS_MODE=true          habilita el modo (ignorado en producción)
+//   CHAOS_LATENCY_MS=500     latencia aleatoria entre 0 y N ms
+//   CHAOS_ERROR_RATE=0.1     probabilidad de responder 500
+//   CHAOS_DROP_RATE=0.05     probabilidad de no responder (el request termina por timeout)
+//   CHAOS_ROUTES=/api/v1/auth,/api/v1/users   prefijos afectados (todos si está vacío)
+#[derive(Debug, Clone, Default)]
+pub struct ChaosConfig {
+    pub enabled: bool,
+    pub latency_ms: u64,
+    pub error_rate: f64,
+    pub drop_rate: f64,
+    pub routes: Vec<String>,
+}
+
+impl ChaosConfig {
+    pub fn from_env(environment: &str) -> Self {
+        let requested = env::var("CHAOS_MODE")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        if requested && environment == "production" {
+            tracing::warn!("⚠️ CHAOS_MODE ignorado: nunca se habilita en producción");
+            return Self::default();
+        }
+
+        if !requested {
+            return Self::default();
+        }
+
+        let rate = |name: &str| {
+            env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<f64>().ok())
+                .unwrap_or(0.0)
+                .clamp(0.0, 1.0)
+        };
+
+        let config = Self {
+            enabled: true,
+            latency_ms: env::var("CHAOS_LATENCY_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            error_rate: rate("CHAOS_ERROR_RATE"),
+            drop_rate: rate("CHAOS_DROP_RATE"),
+            routes: env::var("CHAOS_ROUTES")
+                .unwrap_or_default()
+                .split(',')
+                .map(|r| r.trim().to_string())
+                .filter(|r| !r.is_empty())
+                .collect(),
+        };
+
+        tracing::warn!(
+            latency_ms = config.latency_ms,
+            error_rate = config.error_rate,
+            drop_rate = config.drop_rate,
+            routes = ?config.routes,
+            "🐒 Modo chaos habilitado"
+        );
+
+        config
+    }
+
+    // Los health checks nunca se ven afectados para no romper los probes
+    fn applies_to(&self, path: &str) -> bool {
+        if path.starts_with("/health") {
+            return false;
+        }
+        self.routes.is_empty() || self.routes.iter().any(|prefix| path.starts_with(prefix.as_str()))
+    }
+}
+
+// Middleware de inyección de latencia, errores y respuestas perdidas
+pub async fn chaos_middleware(
+    State(config): State<Arc<ChaosConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !config.enabled || !config.applies_to(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let path = request.uri().path().to_string();
+    let (delay_ms, fail, drop) = {
+        let mut rng = rand::thread_rng();
+        (
+            if config.latency_ms > 0 { rng.gen_range(0..=config.latency_ms) } else { 0 },
+            rng.gen_bool(config.error_rate),
+            rng.gen_bool(config.drop_rate),
+        )
+    };
+
+    if delay_ms > 0 {
+        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+    }
+
+    if drop {
+        tracing::debug!(event = "chaos_drop", path = %path, "🐒 Respuesta descartada por chaos");
+        // No responder nunca: el TimeoutLayer global cierra el request
+        std::future::pending::<()>().await;
+    }
+
+    if fail {
+        tracing::debug!(event = "chaos_error", path = %path, "🐒 Error inyectado por chaos");
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": "chaos_injected",
+                "message": "Error inyectado por el modo chaos"
+            })),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
@@ -0,0 +1,3 @@
+pub mod middleware;
+
+pub use middleware::{chaos_middleware, ChaosConfig};
@@ -0,0 +1,341 @@
+use arc_swap::{ArcSwap, Guard};
+use axum::http::HeaderValue;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use crate::auth::rate_limit::{route_key, RateLimit};
+use crate::health::watchdog::WatchdogConfig;
+
+// Configuración recargable sin reiniciar (POST /api/v1/admin/config/reload).
+// Se arma desde las variables de entorno (al recargar, con .env por encima) y, si
+// CONFIG_FILE apunta a un archivo TOML, sus valores tienen prioridad:
+//
+//   [rate_limits]
+//   login = "10/60"
+//   forgot-password = "5/3600"
+//
+//   [cors]
+//   allowed_origins = ["https://ventalibre.bo"]
+//
+//   [watchdog]
+//   rss_warn_mb = 1024
+//   rss_limit_mb = 2048
+//   fds_warn = 4096
+//   fds_limit = 8192
+//   interval_secs = 30
+//   drain_secs = 10
+//   self_restart = true
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    // Clave: ruta normalizada (ver rate_limit::route_key)
+    pub rate_limits: HashMap<String, RateLimit>,
+    pub cors_origins: Vec<HeaderValue>,
+    pub watchdog: WatchdogConfig,
+}
+
+// Secciones que se aplican en caliente
+const SECTION_RATE_LIMITS: &str = "rate_limits";
+const SECTION_CORS: &str = "cors";
+const SECTION_WATCHDOG: &str = "watchdog";
+
+// Variables que solo se leen al arrancar: si cambian, hace falta reiniciar
+const RESTART_ONLY_VARS: &[&str] = &[
+    "HOST",
+    "PORT",
+    "DATABASE_URL",
+    "ENVIRONMENT",
+    "JWT_SECRET",
+    "JWT_ALGORITHM",
+    "AUTH_TRANSPORT",
+];
+
+const DEFAULT_CORS_ORIGIN: &str = "http://localhost:5173";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    rate_limits: HashMap<String, String>,
+    cors: CorsSection,
+    watchdog: WatchdogSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CorsSection {
+    allowed_origins: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct WatchdogSection {
+    interval_secs: Option<u64>,
+    rss_warn_mb: Option<u64>,
+    rss_limit_mb: Option<u64>,
+    fds_warn: Option<u64>,
+    fds_limit: Option<u64>,
+    self_restart: Option<bool>,
+    drain_secs: Option<u64>,
+}
+
+// Resultado de una recarga aplicada
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadReport {
+    pub applied: Vec<&'static str>,
+    pub changed: Vec<&'static str>,
+    pub requires_restart: Vec<&'static str>,
+    pub config_file: Option<String>,
+}
+
+// Variables con las que se arma la configuración: las del proceso con los valores de
+// `overrides` por encima. El entorno del proceso nunca se modifica
+#[derive(Debug, Default)]
+struct Vars {
+    overrides: HashMap<String, String>,
+}
+
+impl Vars {
+    // Solo el entorno del proceso (al arrancar, main ya cargó .env en él)
+    fn process() -> Self {
+        Self::default()
+    }
+
+    // El entorno con el contenido actual de .env por encima; sin .env queda solo el entorno.
+    // dotenv_iter está deprecado pero no tiene reemplazo para leer sin aplicar
+    #[allow(deprecated)]
+    fn with_env_file() -> Result<Self, String> {
+        let mut overrides = HashMap::new();
+        if let Ok(entries) = dotenv::dotenv_iter() {
+            for entry in entries {
+                let (name, value) = entry.map_err(|e| format!(".env: {}", e))?;
+                overrides.insert(name, value);
+            }
+        }
+        Ok(Self { overrides })
+    }
+
+    fn get(&self, name: &str) -> Option<String> {
+        self.overrides.get(name).cloned().or_else(|| std::env::var(name).ok())
+    }
+
+    // Todas las variables cuyo nombre empieza con `prefix`
+    fn with_prefix(&self, prefix: &str) -> HashMap<String, String> {
+        let mut vars: HashMap<String, String> =
+            std::env::vars().filter(|(name, _)| name.starts_with(prefix)).collect();
+        vars.extend(
+            self.overrides
+                .iter()
+                .filter(|(name, _)| name.starts_with(prefix))
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+        vars
+    }
+}
+
+fn config_file_path(vars: &Vars) -> Option<String> {
+    vars.get("CONFIG_FILE").filter(|p| !p.trim().is_empty())
+}
+
+fn read_config_file(vars: &Vars, errors: &mut Vec<String>) -> FileConfig {
+    let Some(path) = config_file_path(vars) else {
+        return FileConfig::default();
+    };
+
+    match std::fs::read_to_string(&path) {
+        Ok(content) => toml::from_str(&content).unwrap_or_else(|e| {
+            errors.push(format!("{}: {}", path, e));
+            FileConfig::default()
+        }),
+        Err(e) => {
+            errors.push(format!("{}: no se pudo leer ({})", path, e));
+            FileConfig::default()
+        }
+    }
+}
+
+fn parse_rate_limit(source: &str, value: &str, errors: &mut Vec<String>) -> Option<RateLimit> {
+    match RateLimit::parse(value) {
+        Some(limit) if limit.max > 0 && !limit.window.is_zero() => Some(limit),
+        _ => {
+            errors.push(format!("{}: límite inválido '{}' (formato max/segundos)", source, value));
+            None
+        }
+    }
+}
+
+fn parse_origin(origin: &str, errors: &mut Vec<String>) -> Option<HeaderValue> {
+    let origin = origin.trim().trim_end_matches('/');
+    if !(origin.starts_with("http://") || origin.starts_with("https://")) {
+        errors.push(format!("origen CORS inválido '{}' (debe empezar con http:// o https://)", origin));
+        return None;
+    }
+    origin.parse().map_err(|_| errors.push(format!("origen CORS inválido '{}'", origin))).ok()
+}
+
+impl AppConfig {
+    // Armar la configuración; los valores inválidos se reemplazan por el de defecto y se
+    // devuelven como errores
+    fn build(vars: &Vars) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let file = read_config_file(vars, &mut errors);
+
+        // Límites: RATE_LIMIT_<RUTA> y luego [rate_limits] del archivo
+        let mut rate_limits = HashMap::new();
+        for (name, value) in vars.with_prefix("RATE_LIMIT_") {
+            if let Some(route) = name.strip_prefix("RATE_LIMIT_") {
+                if let Some(limit) = parse_rate_limit(&name, &value, &mut errors) {
+                    rate_limits.insert(route.to_string(), limit);
+                }
+            }
+        }
+        for (route, value) in &file.rate_limits {
+            let source = format!("[rate_limits] {}", route);
+            if let Some(limit) = parse_rate_limit(&source, value, &mut errors) {
+                rate_limits.insert(route_key(route), limit);
+            }
+        }
+
+        // Orígenes CORS: CORS_ALLOWED_ORIGINS (separados por coma) o [cors] allowed_origins
+        let origins = file.cors.allowed_origins.unwrap_or_else(|| {
+            vars.get("CORS_ALLOWED_ORIGINS")
+                .unwrap_or_else(|| DEFAULT_CORS_ORIGIN.to_string())
+                .split(',')
+                .map(str::to_string)
+                .filter(|o| !o.trim().is_empty())
+                .collect()
+        });
+        let mut cors_origins: Vec<HeaderValue> = origins
+            .iter()
+            .filter_map(|origin| parse_origin(origin, &mut errors))
+            .collect();
+        if origins.is_empty() {
+            errors.push("no hay orígenes CORS configurados".to_string());
+        }
+        if cors_origins.is_empty() {
+            cors_origins.push(HeaderValue::from_static(DEFAULT_CORS_ORIGIN));
+        }
+
+        // Umbrales del watchdog de recursos
+        errors.extend(WatchdogConfig::var_errors(|name| vars.get(name)));
+        let mut watchdog = WatchdogConfig::from_vars(|name| vars.get(name));
+        let section = file.watchdog;
+        if let Some(secs) = section.interval_secs {
+            watchdog.interval = Duration::from_secs(secs.max(1));
+        }
+        if let Some(secs) = section.drain_secs {
+            watchdog.drain = Duration::from_secs(secs);
+        }
+        watchdog.rss_warn_mb = section.rss_warn_mb.unwrap_or(watchdog.rss_warn_mb);
+        watchdog.rss_limit_mb = section.rss_limit_mb.unwrap_or(watchdog.rss_limit_mb);
+        watchdog.fds_warn = section.fds_warn.unwrap_or(watchdog.fds_warn);
+        watchdog.fds_limit = section.fds_limit.unwrap_or(watchdog.fds_limit);
+        watchdog.self_restart = section.self_restart.unwrap_or(watchdog.self_restart);
+        errors.extend(watchdog.validate());
+
+        (Self { rate_limits, cors_origins, watchdog }, errors)
+    }
+
+    // Configuración completa y válida, o la lista de problemas encontrados
+    fn load(vars: &Vars) -> Result<Self, Vec<String>> {
+        match Self::build(vars) {
+            (config, errors) if errors.is_empty() => Ok(config),
+            (_, errors) => Err(errors),
+        }
+    }
+
+    fn changed_sections(&self, other: &Self) -> Vec<&'static str> {
+        [
+            (SECTION_RATE_LIMITS, self.rate_limits != other.rate_limits),
+            (SECTION_CORS, self.cors_origins != other.cors_origins),
+            (SECTION_WATCHDOG, self.watchdog != other.watchdog),
+        ]
+        .into_iter()
+        .filter_map(|(section, changed)| changed.then_some(section))
+        .collect()
+    }
+}
+
+fn store() -> &'static ArcSwap<AppConfig> {
+    static CONFIG: OnceLock<ArcSwap<AppConfig>> = OnceLock::new();
+    CONFIG.get_or_init(|| ArcSwap::from_pointee(AppConfig::build(&Vars::process()).0))
+}
+
+// Valores de las variables de solo-arranque tal como se leyeron al iniciar
+fn startup_values() -> &'static Vec<Option<String>> {
+    static VALUES: OnceLock<Vec<Option<String>>> = OnceLock::new();
+    VALUES.get_or_init(|| restart_only_values(&Vars::process()))
+}
+
+fn restart_only_values(vars: &Vars) -> Vec<Option<String>> {
+    RESTART_ONLY_VARS.iter().map(|name| vars.get(name)).collect()
+}
+
+// Configuración vigente (lectura sin bloqueo)
+pub fn app_config() -> Guard<Arc<AppConfig>> {
+    store().load()
+}
+
+// Cargar y validar la configuración al arrancar
+pub fn init() -> Result<(), Vec<String>> {
+    startup_values();
+    let config = AppConfig::load(&Vars::process())?;
+    store().store(Arc::new(config));
+    Ok(())
+}
+
+// Preparar una recarga: la configuración nueva (con el .env actual) ya validada y qué
+// cambiaría. No aplica nada ni toca el entorno; si falla la configuración vigente se
+// mantiene intacta
+pub fn prepare_reload() -> Result<(AppConfig, ReloadReport), Vec<String>> {
+    let vars = Vars::with_env_file().map_err(|e| vec![e])?;
+    let config = AppConfig::load(&vars)?;
+
+    let current_values = restart_only_values(&vars);
+    let requires_restart = RESTART_ONLY_VARS
+        .iter()
+        .zip(startup_values().iter().zip(current_values.iter()))
+        .filter(|(_, (at_startup, now))| at_startup != now)
+        .map(|(name, _)| *name)
+        .collect();
+
+    let report = ReloadReport {
+        applied: vec![SECTION_RATE_LIMITS, SECTION_CORS, SECTION_WATCHDOG],
+        changed: app_config().changed_sections(&config),
+        requires_restart,
+        config_file: config_file_path(&vars),
+    };
+
+    Ok((config, report))
+}
+
+// Reemplazar la configuración vigente de forma atómica
+pub fn apply(config: AppConfig) {
+    store().store(Arc::new(config));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(overrides: &[(&str, &str)]) -> Vars {
+        Vars {
+            overrides: overrides.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        }
+    }
+
+    #[test]
+    fn overrides_are_validated_without_touching_the_environment() {
+        let (config, errors) = AppConfig::build(&vars(&[
+            ("RATE_LIMIT_PRUEBA_RECARGA", "5/60"),
+            ("CORS_ALLOWED_ORIGINS", "https://ventalibre.bo"),
+        ]));
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(config.rate_limits["PRUEBA_RECARGA"].max, 5);
+        assert_eq!(config.cors_origins, vec![HeaderValue::from_static("https://ventalibre.bo")]);
+
+        let errors = AppConfig::load(&vars(&[("RATE_LIMIT_PRUEBA_RECARGA", "cinco")])).unwrap_err();
+        assert!(errors.iter().any(|e| e.contains("RATE_LIMIT_PRUEBA_RECARGA")), "{:?}", errors);
+
+        assert!(std::env::var("RATE_LIMIT_PRUEBA_RECARGA").is_err());
+    }
+}
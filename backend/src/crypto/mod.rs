@@ -0,0 +1,3 @@
+pub mod secrets;
+
+pub use secrets::{backfill_encrypted_columns, decrypt, encrypt, verify_encryption_config};
@@ -0,0 +1,283 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rand::RngCore;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use sqlx::PgPool;
+use std::sync::OnceLock;
+
+// Prefijo de los valores cifrados: enc:v1:<key_id>:<base64(nonce || ciphertext || tag)>
+const PREFIX: &str = "enc:v1:";
+
+// Columnas con secretos cifrados en reposo (tabla, columna); la tabla debe tener `id`
+pub const ENCRYPTED_COLUMNS: &[(&str, &str)] = &[("users", "totp_secret")];
+
+#[derive(Debug)]
+pub enum CryptoError {
+    MissingKey,
+    InvalidKey(String),
+    UnknownKeyId(String),
+    Malformed,
+    Failed,
+}
+
+impl std::fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingKey => write!(f, "APP_ENCRYPTION_KEY no está configurada"),
+            Self::InvalidKey(id) => write!(f, "clave de cifrado inválida: {}", id),
+            Self::UnknownKeyId(id) => write!(f, "no hay clave para el key id '{}'", id),
+            Self::Malformed => write!(f, "valor cifrado con formato inválido"),
+            Self::Failed => write!(f, "no se pudo cifrar/descifrar el valor"),
+        }
+    }
+}
+
+impl std::error::Error for CryptoError {}
+
+// Claves de cifrado de la aplicación (AES-256-GCM)
+// APP_ENCRYPTION_KEY acepta una clave base64 de 32 bytes (key id "k1") o varias
+// "id:base64" separadas por coma; la primera cifra y todas descifran (rotación)
+pub struct SecretCipher {
+    keys: Vec<(String, LessSafeKey)>,
+}
+
+impl SecretCipher {
+    pub fn from_env() -> Result<Option<Self>, CryptoError> {
+        Self::from_spec(&std::env::var("APP_ENCRYPTION_KEY").unwrap_or_default())
+    }
+
+    fn from_spec(raw: &str) -> Result<Option<Self>, CryptoError> {
+        if raw.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let mut keys = Vec::new();
+        for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (id, encoded) = entry.split_once(':').unwrap_or(("k1", entry));
+            let bytes = STANDARD
+                .decode(encoded)
+                .map_err(|_| CryptoError::InvalidKey(id.to_string()))?;
+            let key = UnboundKey::new(&AES_256_GCM, &bytes)
+                .map_err(|_| CryptoError::InvalidKey(id.to_string()))?;
+            keys.push((id.to_string(), LessSafeKey::new(key)));
+        }
+
+        Ok(Some(Self { keys }))
+    }
+
+    pub fn active_key_id(&self) -> &str {
+        &self.keys[0].0
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, CryptoError> {
+        let (id, key) = &self.keys[0];
+
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce_bytes);
+
+        let mut in_out = plaintext.as_bytes().to_vec();
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce_bytes),
+            Aad::from(id.as_bytes()),
+            &mut in_out,
+        )
+        .map_err(|_| CryptoError::Failed)?;
+
+        let mut payload = nonce_bytes.to_vec();
+        payload.extend_from_slice(&in_out);
+
+        Ok(format!("{}{}:{}", PREFIX, id, STANDARD.encode(payload)))
+    }
+
+    pub fn decrypt(&self, value: &str) -> Result<String, CryptoError> {
+        let (id, encoded) = value
+            .strip_prefix(PREFIX)
+            .and_then(|rest| rest.split_once(':'))
+            .ok_or(CryptoError::Malformed)?;
+
+        let (_, key) = self
+            .keys
+            .iter()
+            .find(|(key_id, _)| key_id == id)
+            .ok_or_else(|| CryptoError::UnknownKeyId(id.to_string()))?;
+
+        let payload = STANDARD.decode(encoded).map_err(|_| CryptoError::Malformed)?;
+        if payload.len() < NONCE_LEN {
+            return Err(CryptoError::Malformed);
+        }
+
+        let (nonce_bytes, ciphertext) = payload.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce_bytes).map_err(|_| CryptoError::Malformed)?;
+
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = key
+            .open_in_place(nonce, Aad::from(id.as_bytes()), &mut in_out)
+            .map_err(|_| CryptoError::Failed)?;
+
+        String::from_utf8(plaintext.to_vec()).map_err(|_| CryptoError::Malformed)
+    }
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+// Key id con el que se cifró un valor
+fn key_id(value: &str) -> Option<&str> {
+    value.strip_prefix(PREFIX)?.split(':').next()
+}
+
+// Instancia global; una configuración inválida se trata como ausente (y se avisa)
+pub fn cipher() -> Option<&'static SecretCipher> {
+    static CIPHER: OnceLock<Option<SecretCipher>> = OnceLock::new();
+    CIPHER
+        .get_or_init(|| {
+            SecretCipher::from_env().unwrap_or_else(|e| {
+                tracing::error!(error = %e, "🚨 APP_ENCRYPTION_KEY inválida");
+                None
+            })
+        })
+        .as_ref()
+}
+
+// Sin clave solo se admite guardar en claro fuera de producción
+fn plaintext_fallback_allowed() -> bool {
+    std::env::var("ENVIRONMENT").map_or(true, |env| env != "production")
+}
+
+// Cifrar si hay clave configurada. Sin clave, en desarrollo se guarda en claro y
+// `encrypt-secrets` lo cifra cuando se configure; en producción es MissingKey
+pub fn encrypt(plaintext: &str) -> Result<String, CryptoError> {
+    match cipher() {
+        Some(cipher) => cipher.encrypt(plaintext),
+        None if plaintext_fallback_allowed() => {
+            tracing::warn!("⚠️ APP_ENCRYPTION_KEY no configurada: secreto guardado sin cifrar");
+            Ok(plaintext.to_string())
+        }
+        None => Err(CryptoError::MissingKey),
+    }
+}
+
+// Descifrado transparente: los valores aún no cifrados se devuelven tal cual
+pub fn decrypt(value: &str) -> Result<String, CryptoError> {
+    if !is_encrypted(value) {
+        return Ok(value.to_string());
+    }
+    cipher().ok_or(CryptoError::MissingKey)?.decrypt(value)
+}
+
+// Al iniciar: la clave es obligatoria en producción y, si hay datos cifrados, debe estar
+// configurada y conocer sus key ids
+pub async fn verify_encryption_config(pool: &PgPool) -> Result<(), Box<dyn std::error::Error>> {
+    if SecretCipher::from_env()?.is_none() && !plaintext_fallback_allowed() {
+        return Err(Box::new(CryptoError::MissingKey));
+    }
+
+    for (table, column) in ENCRYPTED_COLUMNS {
+        let key_ids: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT DISTINCT split_part({column}, ':', 3) FROM {table} WHERE {column} LIKE 'enc:v1:%'"
+        ))
+        .fetch_all(pool)
+        .await?;
+
+        if key_ids.is_empty() {
+            continue;
+        }
+
+        let cipher = cipher().ok_or(CryptoError::MissingKey)?;
+        if let Some(missing) = key_ids
+            .iter()
+            .find(|id| !cipher.keys.iter().any(|(key_id, _)| key_id == *id))
+        {
+            return Err(Box::new(CryptoError::UnknownKeyId(missing.clone())));
+        }
+    }
+
+    Ok(())
+}
+
+// Cifrar valores en claro y recifrar con la clave activa los cifrados con claves anteriores
+pub async fn backfill_encrypted_columns(pool: &PgPool) -> Result<u64, Box<dyn std::error::Error>> {
+    let cipher = cipher().ok_or(CryptoError::MissingKey)?;
+    let mut updated = 0;
+
+    for (table, column) in ENCRYPTED_COLUMNS {
+        let rows: Vec<(i32, String)> = sqlx::query_as(&format!(
+            "SELECT id, {column} FROM {table} WHERE {column} IS NOT NULL"
+        ))
+        .fetch_all(pool)
+        .await?;
+
+        for (id, value) in rows {
+            if key_id(&value) == Some(cipher.active_key_id()) {
+                continue;
+            }
+
+            let plaintext = decrypt(&value)?;
+            sqlx::query(&format!("UPDATE {table} SET {column} = $1 WHERE id = $2"))
+                .bind(cipher.encrypt(&plaintext)?)
+                .bind(id)
+                .execute(pool)
+                .await?;
+            updated += 1;
+        }
+
+        tracing::info!(table = %table, column = %column, "🔐 Columna cifrada con la clave activa");
+    }
+
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> String {
+        STANDARD.encode([byte; 32])
+    }
+
+    #[test]
+    fn rotation_encrypts_with_new_key_and_decrypts_old_values() {
+        let old = SecretCipher::from_spec(&format!("k1:{}", key(1))).unwrap().unwrap();
+        let legacy = old.encrypt("JBSWY3DPEHPK3PXP").unwrap();
+        assert_eq!(key_id(&legacy), Some("k1"));
+
+        // La clave nueva va primero; la anterior queda solo para descifrar
+        let rotated = SecretCipher::from_spec(&format!("k2:{}, k1:{}", key(2), key(1)))
+            .unwrap()
+            .unwrap();
+        assert_eq!(rotated.active_key_id(), "k2");
+        assert_eq!(rotated.decrypt(&legacy).unwrap(), "JBSWY3DPEHPK3PXP");
+
+        let fresh = rotated.encrypt("JBSWY3DPEHPK3PXP").unwrap();
+        assert_eq!(key_id(&fresh), Some("k2"));
+        assert_eq!(rotated.decrypt(&fresh).unwrap(), "JBSWY3DPEHPK3PXP");
+
+        // Sin la clave nueva los valores re-cifrados ya no se pueden leer
+        assert!(matches!(old.decrypt(&fresh), Err(CryptoError::UnknownKeyId(id)) if id == "k2"));
+    }
+
+    #[test]
+    fn single_key_without_id_uses_k1() {
+        let cipher = SecretCipher::from_spec(&key(3)).unwrap().unwrap();
+        assert_eq!(cipher.active_key_id(), "k1");
+        assert!(SecretCipher::from_spec("  ").unwrap().is_none());
+        assert!(matches!(
+            SecretCipher::from_spec("k1:no-es-base64"),
+            Err(CryptoError::InvalidKey(id)) if id == "k1"
+        ));
+    }
+
+    #[test]
+    fn tampered_values_are_rejected() {
+        let cipher = SecretCipher::from_spec(&key(4)).unwrap().unwrap();
+        let value = cipher.encrypt("secreto").unwrap();
+
+        // Cambiar el key id rompe la autenticación (va como AAD)
+        let other = SecretCipher::from_spec(&format!("k1:{}, k9:{}", key(4), key(4))).unwrap().unwrap();
+        let relabelled = value.replacen("enc:v1:k1:", "enc:v1:k9:", 1);
+        assert!(matches!(other.decrypt(&relabelled), Err(CryptoError::Failed)));
+
+        assert!(matches!(cipher.decrypt("enc:v1:k1:"), Err(CryptoError::Malformed)));
+        assert!(matches!(cipher.decrypt("texto plano"), Err(CryptoError::Malformed)));
+    }
+}
@@ -1,3 +1,8 @@
 pub mod connection;
+pub mod query_log;
+pub mod schema_check;
+pub mod tx;
 
-pub use connection::create_pool;
\ No newline at end of file
+pub use connection::create_pool;
+pub use schema_check::{check_schema_compatibility, verify_schema, SchemaCompatibility};
+pub use tx::{transaction_middleware, Tx};
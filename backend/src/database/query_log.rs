@@ -0,0 +1,126 @@
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing::field::{Field, Visit};
+use tracing::subscriber::Interest;
+use tracing::{Event, Metadata, Subscriber};
+use tracing_subscriber::filter::dynamic_filter_fn;
+use tracing_subscriber::layer::{Context, Layer};
+
+// Header con el que un admin pide el detalle de queries del request
+pub const DEBUG_QUERIES_HEADER: &str = "x-debug-queries";
+
+// sqlx emite un evento con este target por cada query ejecutada
+const SQLX_QUERY_TARGET: &str = "sqlx::query";
+
+// Máximo de queries guardadas por request (DEBUG_QUERIES_MAX, por defecto 200)
+fn max_queries() -> usize {
+    static MAX: OnceLock<usize> = OnceLock::new();
+    *MAX.get_or_init(|| {
+        std::env::var("DEBUG_QUERIES_MAX")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(200)
+    })
+}
+
+// Query ejecutada durante el request
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QueryRecord {
+    // Resumen de sqlx (primeras palabras del SQL, sin parámetros)
+    pub query: String,
+    pub duration_ms: f64,
+    pub rows_returned: u64,
+    pub rows_affected: u64,
+}
+
+// Resultado de la captura; va en las extensions de la respuesta
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QueryDebug {
+    pub queries: Vec<QueryRecord>,
+    pub total_queries: usize,
+    pub total_duration_ms: f64,
+    // Queries que no se guardaron por superar DEBUG_QUERIES_MAX
+    pub truncated: usize,
+}
+
+tokio::task_local! {
+    static QUERY_LOG: Arc<Mutex<QueryDebug>>;
+}
+
+// Ejecutar `future` registrando las queries que haga; solo las de esta tarea
+pub async fn capture<F: Future>(future: F) -> (F::Output, QueryDebug) {
+    let log = Arc::new(Mutex::new(QueryDebug::default()));
+    let output = QUERY_LOG.scope(log.clone(), future).await;
+    let debug = std::mem::take(&mut *log.lock().unwrap_or_else(|e| e.into_inner()));
+    (output, debug)
+}
+
+fn capturing(metadata: &Metadata<'_>) -> bool {
+    metadata.target() == SQLX_QUERY_TARGET && QUERY_LOG.try_with(|_| ()).is_ok()
+}
+
+// Layer que copia los eventos de sqlx al registro del request en curso.
+// El filtro dinámico hace que sqlx ni siquiera arme el evento si no hay captura activa
+pub fn capture_layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    let filter = dynamic_filter_fn(|metadata, _| capturing(metadata)).with_callsite_filter(|metadata| {
+        if metadata.target() == SQLX_QUERY_TARGET {
+            Interest::sometimes()
+        } else {
+            Interest::never()
+        }
+    });
+    QueryCaptureLayer.with_filter(filter)
+}
+
+pub struct QueryCaptureLayer;
+
+impl<S: Subscriber> Layer<S> for QueryCaptureLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = QueryVisitor::default();
+        event.record(&mut visitor);
+
+        let _ = QUERY_LOG.try_with(|log| {
+            let mut log = log.lock().unwrap_or_else(|e| e.into_inner());
+            log.total_queries += 1;
+            log.total_duration_ms += visitor.record.duration_ms;
+            if log.queries.len() < max_queries() {
+                log.queries.push(visitor.record);
+            } else {
+                log.truncated += 1;
+            }
+        });
+    }
+}
+
+#[derive(Default)]
+struct QueryVisitor {
+    record: QueryRecord,
+}
+
+impl Visit for QueryVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "summary" {
+            self.record.query = value.to_string();
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "rows_returned" => self.record.rows_returned = value,
+            "rows_affected" => self.record.rows_affected = value,
+            _ => {}
+        }
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        if field.name() == "elapsed_secs" {
+            self.record.duration_ms = value * 1000.0;
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
@@ -0,0 +1,445 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::env;
+
+// Índices y restricciones críticas que deben existir en la base de datos
+enum Requirement {
+    UniqueIndex { table: &'static str, column: &'static str },
+    Index { table: &'static str, name: &'static str },
+    ForeignKey { table: &'static str, column: &'static str },
+}
+
+const REQUIREMENTS: &[Requirement] = &[
+    Requirement::UniqueIndex { table: "users", column: "email" },
+    Requirement::ForeignKey { table: "users", column: "merged_into" },
+    Requirement::ForeignKey { table: "admin_audit_log", column: "admin_id" },
+    Requirement::Index { table: "admin_audit_log", name: "idx_admin_audit_log_target_user" },
+    Requirement::Index { table: "admin_audit_log", name: "idx_admin_audit_log_created_at" },
+    Requirement::Index { table: "health_transitions", name: "idx_health_transitions_created_at" },
+];
+
+impl Requirement {
+    fn describe(&self) -> String {
+        match self {
+            Requirement::UniqueIndex { table, column } => format!("índice único en {}({})", table, column),
+            Requirement::Index { table, name } => format!("índice {} en {}", name, table),
+            Requirement::ForeignKey { table, column } => format!("foreign key en {}({})", table, column),
+        }
+    }
+
+    async fn exists(&self, pool: &PgPool) -> Result<bool, sqlx::Error> {
+        match self {
+            Requirement::UniqueIndex { table, column } => {
+                sqlx::query_scalar::<_, bool>(
+                    "SELECT EXISTS (
+                        SELECT 1 FROM pg_indexes
+                        WHERE schemaname = current_schema()
+                          AND tablename = $1
+                          AND indexdef ILIKE 'CREATE UNIQUE INDEX%'
+                          AND indexdef ILIKE '%' || $2 || '%'
+                    )"
+                )
+                .bind(table)
+                .bind(column)
+                .fetch_one(pool)
+                .await
+            }
+            Requirement::Index { table, name } => {
+                sqlx::query_scalar::<_, bool>(
+                    "SELECT EXISTS (
+                        SELECT 1 FROM pg_indexes
+                        WHERE schemaname = current_schema()
+                          AND tablename = $1
+                          AND indexname = $2
+                    )"
+                )
+                .bind(table)
+                .bind(name)
+                .fetch_one(pool)
+                .await
+            }
+            Requirement::ForeignKey { table, column } => {
+                sqlx::query_scalar::<_, bool>(
+                    "SELECT EXISTS (
+                        SELECT 1
+                        FROM information_schema.table_constraints tc
+                        JOIN information_schema.key_column_usage kcu
+                          ON tc.constraint_name = kcu.constraint_name
+                         AND tc.table_schema = kcu.table_schema
+                        WHERE tc.constraint_type = 'FOREIGN KEY'
+                          AND tc.table_schema = current_schema()
+                          AND tc.table_name = $1
+                          AND kcu.column_name = $2
+                    )"
+                )
+                .bind(table)
+                .bind(column)
+                .fetch_one(pool)
+                .await
+            }
+        }
+    }
+}
+
+// Modo de verificación: SCHEMA_CHECK_MODE=off|warn|fail
+// Por defecto falla en producción y solo advierte en otros entornos
+fn check_mode() -> String {
+    env::var("SCHEMA_CHECK_MODE").unwrap_or_else(|_| {
+        match env::var("ENVIRONMENT").as_deref() {
+            Ok("production") => "fail".to_string(),
+            _ => "warn".to_string(),
+        }
+    })
+}
+
+// Verificar índices y restricciones críticas al iniciar
+pub async fn verify_schema(pool: &PgPool) -> Result<(), Box<dyn std::error::Error>> {
+    let mode = check_mode();
+    if mode == "off" {
+        tracing::info!("⏭️ Verificación de esquema deshabilitada");
+        return Ok(());
+    }
+
+    let mut missing = Vec::new();
+    for requirement in REQUIREMENTS {
+        if !requirement.exists(pool).await? {
+            missing.push(requirement.describe());
+        }
+    }
+
+    if missing.is_empty() {
+        tracing::info!(checked = REQUIREMENTS.len(), "✅ Índices y restricciones críticas verificadas");
+        return Ok(());
+    }
+
+    for item in &missing {
+        tracing::warn!(event = "schema_check_missing", missing = %item, "⚠️ Falta {}", item);
+    }
+
+    if mode == "fail" {
+        return Err(format!(
+            "Faltan {} índices/restricciones críticas: {}",
+            missing.len(),
+            missing.join(", ")
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+// Tipos de columna que espera el código (familias compatibles de information_schema)
+#[derive(Debug, Clone, Copy)]
+enum ColumnType {
+    Integer,
+    BigInt,
+    Text,
+    Boolean,
+    TimestampTz,
+    Jsonb,
+    TextArray,
+}
+
+impl ColumnType {
+    fn matches(&self, data_type: &str) -> bool {
+        match self {
+            ColumnType::Integer => data_type == "integer",
+            ColumnType::BigInt => data_type == "bigint",
+            ColumnType::Text => matches!(data_type, "text" | "character varying"),
+            ColumnType::Boolean => data_type == "boolean",
+            ColumnType::TimestampTz => data_type == "timestamp with time zone",
+            ColumnType::Jsonb => data_type == "jsonb",
+            ColumnType::TextArray => data_type == "ARRAY",
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ColumnType::Integer => "integer",
+            ColumnType::BigInt => "bigint",
+            ColumnType::Text => "text/varchar",
+            ColumnType::Boolean => "boolean",
+            ColumnType::TimestampTz => "timestamptz",
+            ColumnType::Jsonb => "jsonb",
+            ColumnType::TextArray => "text[]",
+        }
+    }
+}
+
+// Columnas que usa esta versión del código, por tabla
+struct TableManifest {
+    table: &'static str,
+    columns: &'static [(&'static str, ColumnType)],
+}
+
+const MANIFEST: &[TableManifest] = &[
+    TableManifest {
+        table: "users",
+        columns: &[
+            ("id", ColumnType::Integer),
+            ("name", ColumnType::Text),
+            ("email", ColumnType::Text),
+            ("password_hash", ColumnType::Text),
+            ("is_admin", ColumnType::Boolean),
+            ("role", ColumnType::Text),
+            ("is_active", ColumnType::Boolean),
+            ("created_at", ColumnType::TimestampTz),
+            ("updated_at", ColumnType::TimestampTz),
+            ("merged_into", ColumnType::Integer),
+            ("totp_secret", ColumnType::Text),
+            ("totp_enabled", ColumnType::Boolean),
+            ("totp_last_step", ColumnType::BigInt),
+            ("token_version", ColumnType::Integer),
+            ("deleted_at", ColumnType::TimestampTz),
+            ("anonymized_at", ColumnType::TimestampTz),
+            ("username", ColumnType::Text),
+        ],
+    },
+    TableManifest {
+        table: "admin_audit_log",
+        columns: &[
+            ("id", ColumnType::Integer),
+            ("admin_id", ColumnType::Integer),
+            ("action", ColumnType::Text),
+            ("target_user_id", ColumnType::Integer),
+            ("details", ColumnType::Jsonb),
+            ("created_at", ColumnType::TimestampTz),
+        ],
+    },
+    TableManifest {
+        table: "health_transitions",
+        columns: &[
+            ("id", ColumnType::Integer),
+            ("status", ColumnType::Text),
+            ("created_at", ColumnType::TimestampTz),
+        ],
+    },
+    TableManifest {
+        table: "refresh_tokens",
+        columns: &[
+            ("id", ColumnType::Integer),
+            ("user_id", ColumnType::Integer),
+            ("jti", ColumnType::Text),
+            ("expires_at", ColumnType::TimestampTz),
+            ("revoked_at", ColumnType::TimestampTz),
+            ("created_at", ColumnType::TimestampTz),
+            ("session_id", ColumnType::Integer),
+        ],
+    },
+    TableManifest {
+        table: "revoked_tokens",
+        columns: &[
+            ("jti", ColumnType::Text),
+            ("user_id", ColumnType::Integer),
+            ("expires_at", ColumnType::TimestampTz),
+        ],
+    },
+    TableManifest {
+        table: "password_resets",
+        columns: &[
+            ("user_id", ColumnType::Integer),
+            ("token_hash", ColumnType::Text),
+            ("expires_at", ColumnType::TimestampTz),
+        ],
+    },
+    TableManifest {
+        table: "user_identities",
+        columns: &[
+            ("user_id", ColumnType::Integer),
+            ("provider", ColumnType::Text),
+            ("subject", ColumnType::Text),
+            ("email", ColumnType::Text),
+        ],
+    },
+    TableManifest {
+        table: "sessions",
+        columns: &[
+            ("id", ColumnType::Integer),
+            ("user_id", ColumnType::Integer),
+            ("jti", ColumnType::Text),
+            ("user_agent", ColumnType::Text),
+            ("ip_address", ColumnType::Text),
+            ("created_at", ColumnType::TimestampTz),
+            ("last_seen_at", ColumnType::TimestampTz),
+            ("revoked_at", ColumnType::TimestampTz),
+        ],
+    },
+    TableManifest {
+        table: "known_devices",
+        columns: &[
+            ("id", ColumnType::Integer),
+            ("user_id", ColumnType::Integer),
+            ("fingerprint", ColumnType::Text),
+            ("browser", ColumnType::Text),
+            ("os", ColumnType::Text),
+            ("network", ColumnType::Text),
+            ("location", ColumnType::Text),
+            ("first_seen_at", ColumnType::TimestampTz),
+            ("last_seen_at", ColumnType::TimestampTz),
+        ],
+    },
+    TableManifest {
+        table: "api_keys",
+        columns: &[
+            ("id", ColumnType::Integer),
+            ("user_id", ColumnType::Integer),
+            ("name", ColumnType::Text),
+            ("key_prefix", ColumnType::Text),
+            ("key_hash", ColumnType::Text),
+            ("scopes", ColumnType::TextArray),
+            ("expires_at", ColumnType::TimestampTz),
+            ("last_used_at", ColumnType::TimestampTz),
+            ("revoked_at", ColumnType::TimestampTz),
+            ("created_at", ColumnType::TimestampTz),
+        ],
+    },
+    TableManifest {
+        table: "contact_messages",
+        columns: &[
+            ("id", ColumnType::Integer),
+            ("name", ColumnType::Text),
+            ("email", ColumnType::Text),
+            ("subject", ColumnType::Text),
+            ("message", ColumnType::Text),
+            ("ip_address", ColumnType::Text),
+            ("status", ColumnType::Text),
+            ("assigned_to", ColumnType::Integer),
+            ("handled_by", ColumnType::Integer),
+            ("handled_at", ColumnType::TimestampTz),
+            ("created_at", ColumnType::TimestampTz),
+        ],
+    },
+    TableManifest {
+        table: "domain_events",
+        columns: &[
+            ("sequence", ColumnType::BigInt),
+            ("event_type", ColumnType::Text),
+            ("aggregate_type", ColumnType::Text),
+            ("aggregate_id", ColumnType::BigInt),
+            ("payload", ColumnType::Jsonb),
+            ("occurred_at", ColumnType::TimestampTz),
+        ],
+    },
+    TableManifest {
+        table: "magic_links",
+        columns: &[
+            ("user_id", ColumnType::Integer),
+            ("token_hash", ColumnType::Text),
+            ("expires_at", ColumnType::TimestampTz),
+            ("used_at", ColumnType::TimestampTz),
+        ],
+    },
+    TableManifest {
+        table: "outbound_calls",
+        columns: &[
+            ("destination", ColumnType::Text),
+            ("method", ColumnType::Text),
+            ("url", ColumnType::Text),
+            ("status", ColumnType::Integer),
+            ("success", ColumnType::Boolean),
+            ("duration_ms", ColumnType::BigInt),
+            ("error", ColumnType::Text),
+            ("response_body", ColumnType::Text),
+            ("created_at", ColumnType::TimestampTz),
+        ],
+    },
+];
+
+// Resultado de la verificación, expuesto en /health bajo `schema`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaCompatibility {
+    pub status: String, // "compatible" | "incompatible" | "unknown"
+    pub strict: bool,
+    pub checked_columns: usize,
+    pub mismatches: Vec<String>,
+    #[serde(with = "crate::models::timestamp")]
+    pub checked_at: DateTime<Utc>,
+}
+
+impl SchemaCompatibility {
+    pub fn is_compatible(&self) -> bool {
+        self.status == "compatible"
+    }
+
+    // En modo estricto un esquema incompatible (o no verificable) impide estar ready
+    pub fn blocks_readiness(&self) -> bool {
+        self.strict && !self.is_compatible()
+    }
+}
+
+// STRICT_SCHEMA=true|false; por defecto estricto solo en producción
+fn strict_mode() -> bool {
+    env::var("STRICT_SCHEMA")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_else(|| env::var("ENVIRONMENT").as_deref() == Ok("production"))
+}
+
+// Comparar las columnas esperadas con las de la base de datos conectada
+pub async fn check_schema_compatibility(pool: &PgPool) -> SchemaCompatibility {
+    let strict = strict_mode();
+    let checked_columns = MANIFEST.iter().map(|t| t.columns.len()).sum();
+
+    let rows = sqlx::query_as::<_, (String, String, String)>(
+        "SELECT table_name, column_name, data_type
+         FROM information_schema.columns
+         WHERE table_schema = current_schema()"
+    )
+    .fetch_all(pool)
+    .await;
+
+    let mismatches: Vec<String> = match rows {
+        Ok(rows) => {
+            let actual: HashMap<(String, String), String> = rows
+                .into_iter()
+                .map(|(table, column, data_type)| ((table, column), data_type))
+                .collect();
+
+            MANIFEST
+                .iter()
+                .flat_map(|manifest| {
+                    manifest.columns.iter().filter_map(|(column, expected)| {
+                        match actual.get(&(manifest.table.to_string(), column.to_string())) {
+                            None => Some(format!("falta la columna {}.{} ({})", manifest.table, column, expected.name())),
+                            Some(data_type) if !expected.matches(data_type) => Some(format!(
+                                "{}.{} es {} (se esperaba {})",
+                                manifest.table, column, data_type, expected.name()
+                            )),
+                            Some(_) => None,
+                        }
+                    })
+                })
+                .collect()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "🚨 No se pudo verificar la compatibilidad del esquema");
+            return SchemaCompatibility {
+                status: "unknown".to_string(),
+                strict,
+                checked_columns,
+                mismatches: vec![format!("no se pudo leer information_schema: {}", e)],
+                checked_at: Utc::now(),
+            };
+        }
+    };
+
+    let result = SchemaCompatibility {
+        status: if mismatches.is_empty() { "compatible" } else { "incompatible" }.to_string(),
+        strict,
+        checked_columns,
+        mismatches,
+        checked_at: Utc::now(),
+    };
+
+    if result.is_compatible() {
+        tracing::info!(checked_columns = checked_columns, "✅ Esquema compatible con esta versión");
+    } else {
+        for mismatch in &result.mismatches {
+            tracing::warn!(event = "schema_incompatible", mismatch = %mismatch, strict = strict, "⚠️ Esquema incompatible: {}", mismatch);
+        }
+    }
+
+    result
+}
@@ -0,0 +1,163 @@
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts, Request},
+    http::{request::Parts, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use tokio::sync::{Mutex, OwnedMappedMutexGuard, OwnedMutexGuard};
+use crate::models::auth::AuthError;
+
+type TxSlot = Arc<Mutex<Option<Transaction<'static, Postgres>>>>;
+
+// Espacio para la transacción del request, creado por transaction_middleware
+#[derive(Clone, Default)]
+struct TxHandle(TxSlot);
+
+// Transacción con alcance de request
+// Se confirma si el handler responde con éxito y se revierte si falla o hace panic
+pub struct Tx(TxSlot);
+
+impl Tx {
+    // Conexión de la transacción; usar como `&mut **conn` en las queries
+    pub async fn conn(&self) -> OwnedMappedMutexGuard<Option<Transaction<'static, Postgres>>, Transaction<'static, Postgres>> {
+        OwnedMutexGuard::map(self.0.clone().lock_owned().await, |tx| {
+            tx.as_mut().expect("la transacción se finaliza después del handler")
+        })
+    }
+
+    // Confirmar antes de responder, para actuar solo sobre cambios ya persistidos
+    // (logs, notificaciones). Soltar antes los guards de conn(); el middleware ya no hace nada
+    pub async fn commit(self) -> Result<(), sqlx::Error> {
+        match self.0.lock().await.take() {
+            Some(tx) => tx.commit().await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Tx
+where
+    PgPool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, Json<AuthError>);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let handle = parts.extensions.get::<TxHandle>().cloned().ok_or_else(|| {
+            tracing::error!("🚨 Extractor Tx usado en una ruta sin transaction_middleware");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(AuthError::new("transaction_error", "Error interno del servidor")),
+            )
+        })?;
+
+        let tx = PgPool::from_ref(state).begin().await.map_err(|e| {
+            tracing::error!(error = %e, "🚨 Error al iniciar transacción");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(AuthError::new("database_error", "Error de base de datos")),
+            )
+        })?;
+
+        *handle.0.lock().await = Some(tx);
+        Ok(Tx(handle.0))
+    }
+}
+
+// Middleware que confirma o revierte la transacción abierta por el extractor Tx
+// Si el handler hace panic, la transacción se descarta y sqlx la revierte
+pub async fn transaction_middleware(mut request: Request, next: Next) -> Response {
+    let handle = TxHandle::default();
+    request.extensions_mut().insert(handle.clone());
+
+    let response = next.run(request).await;
+
+    let tx = match handle.0.lock().await.take() {
+        Some(tx) => tx,
+        None => return response,
+    };
+
+    let status = response.status();
+    if status.is_success() || status.is_redirection() {
+        if let Err(e) = tx.commit().await {
+            tracing::error!(error = %e, "🚨 Error al confirmar transacción");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(AuthError::new("database_error", "Error de base de datos")),
+            )
+                .into_response();
+        }
+    } else if let Err(e) = tx.rollback().await {
+        tracing::warn!(error = %e, "⚠️ Error al revertir transacción");
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::Method, middleware, routing::post, Router};
+    use crate::test_support::{send, TestApp};
+
+    // Escribe en la transacción y luego responde según la ruta: éxito, error o panic
+    async fn write_then(tx: Tx, axum::extract::Path(outcome): axum::extract::Path<String>) -> Response {
+        {
+            let mut conn = tx.conn().await;
+            sqlx::query("INSERT INTO users (name, email) VALUES ('Parcial', $1)")
+                .bind(format!("{}@ventalibre.bo", outcome))
+                .execute(&mut **conn)
+                .await
+                .unwrap();
+        }
+
+        match outcome.as_str() {
+            "ok" => StatusCode::OK.into_response(),
+            "error" => (
+                StatusCode::CONFLICT,
+                Json(AuthError::new("invalid_merge", "falla después de escribir")),
+            )
+                .into_response(),
+            _ => panic!("panic después de escribir"),
+        }
+    }
+
+    async fn exists(app: &TestApp, outcome: &str) -> bool {
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE email = $1)")
+            .bind(format!("{}@ventalibre.bo", outcome))
+            .fetch_one(&app.pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn failed_handlers_leave_no_partial_writes() {
+        let app = TestApp::spawn().await;
+        let router = Router::new()
+            .route("/:outcome", post(write_then).layer(middleware::from_fn(transaction_middleware)))
+            .with_state(app.pool.clone());
+
+        let (status, _, _) = send(&router, Method::POST, "/ok", None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(exists(&app, "ok").await);
+
+        let (status, _, body) = send(&router, Method::POST, "/error", None, None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error"], "invalid_merge");
+        assert!(!exists(&app, "error").await);
+
+        // El panic descarta la transacción sin confirmarla
+        let panicking = router.clone();
+        let result = tokio::spawn(async move { send(&panicking, Method::POST, "/panic", None, None).await }).await;
+        assert!(result.is_err());
+        assert!(!exists(&app, "panic").await);
+
+        app.finish().await;
+    }
+}
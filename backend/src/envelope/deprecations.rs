@@ -0,0 +1,54 @@
+use axum::response::{IntoResponseParts, ResponseParts};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Mutex, OnceLock};
+use crate::envelope::sunset;
+
+// Avisos de deprecación registrados por los handlers como extension de la respuesta
+// Uso: Ok((Deprecations::new(["el campo token se eliminará en v2"]), Json(body)))
+#[derive(Debug, Clone, Default)]
+pub struct Deprecations(pub Vec<String>);
+
+impl Deprecations {
+    pub fn new<I, S>(notices: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self(notices.into_iter().map(Into::into).collect())
+    }
+}
+
+impl IntoResponseParts for Deprecations {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        // Acumular con avisos registrados previamente
+        match res.extensions_mut().get_mut::<Deprecations>() {
+            Some(existing) => existing.0.extend(self.0),
+            None => {
+                res.extensions_mut().insert(self);
+            }
+        }
+        Ok(res)
+    }
+}
+
+fn usage() -> &'static Mutex<HashMap<String, u64>> {
+    static USAGE: OnceLock<Mutex<HashMap<String, u64>>> = OnceLock::new();
+    USAGE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Contar un request que todavía dispara los avisos dados. Los avisos de rutas
+// deprecadas no se cuentan aquí: ya los cuenta el colector de métricas, por ruta
+pub fn record_usage(deprecations: &Deprecations) {
+    let mut usage = usage().lock().unwrap_or_else(|e| e.into_inner());
+    for notice in deprecations.0.iter().filter(|notice| !sunset::is_route_notice(notice)) {
+        *usage.entry(notice.clone()).or_insert(0) += 1;
+    }
+}
+
+// Conteo de requests por aviso de deprecación desde el inicio del proceso
+pub fn usage_snapshot() -> HashMap<String, u64> {
+    usage().lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
@@ -0,0 +1,87 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use crate::database::query_log::QueryDebug;
+use crate::envelope::Deprecations;
+use crate::logging::timing::{self, RequestTiming, PHASE_SERIALIZATION};
+use crate::logging::RequestId;
+
+// Header con el que el cliente pide el envelope
+pub const ENVELOPE_HEADER: &str = "x-api-envelope";
+
+// Envolver respuestas JSON exitosas en {data, meta} cuando el cliente lo pide
+pub async fn envelope_middleware(request: Request, next: Next) -> Response {
+    let wants_envelope = request
+        .headers()
+        .get(ENVELOPE_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+        .unwrap_or(false);
+
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .map(|r| r.0.clone());
+    let request_timing = request.extensions().get::<RequestTiming>().cloned();
+
+    let response = next.run(request).await;
+
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|h| h.to_str().ok())
+        .map(|ct| ct.starts_with("application/json"))
+        .unwrap_or(false);
+
+    if !wants_envelope || !response.status().is_success() || !is_json {
+        return response;
+    }
+
+    // Releer y volver a serializar el cuerpo cuenta como serialización en Server-Timing
+    let started = std::time::Instant::now();
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!(error = %e, "🚨 Error al leer la respuesta para el envelope");
+            return axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let data: serde_json::Value = match serde_json::from_slice(&bytes) {
+        Ok(data) => data,
+        // Si el cuerpo no es JSON válido se devuelve tal cual
+        Err(_) => {
+            timing::record_phase(request_timing.as_ref(), PHASE_SERIALIZATION, started);
+            return Response::from_parts(parts, Body::from(bytes));
+        }
+    };
+
+    let deprecations = parts
+        .extensions
+        .get::<Deprecations>()
+        .map(|d| d.0.clone())
+        .unwrap_or_default();
+
+    let mut enveloped = serde_json::json!({
+        "data": data,
+        "meta": {
+            "request_id": request_id,
+            "deprecations": deprecations
+        }
+    });
+
+    // Detalle de queries pedido por un admin con X-Debug-Queries
+    if let Some(debug) = parts.extensions.get::<QueryDebug>() {
+        enveloped["debug"] = serde_json::json!(debug);
+    }
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let body = enveloped.to_string();
+    timing::record_phase(request_timing.as_ref(), PHASE_SERIALIZATION, started);
+    Response::from_parts(parts, Body::from(body))
+}
@@ -0,0 +1,7 @@
+pub mod deprecations;
+pub mod middleware;
+pub mod sunset;
+
+pub use deprecations::Deprecations;
+pub use middleware::envelope_middleware;
+pub use sunset::route_deprecation_middleware;
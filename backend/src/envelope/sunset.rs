@@ -0,0 +1,165 @@
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::HashSet;
+use std::sync::{Arc, OnceLock};
+use crate::envelope::Deprecations;
+use crate::metrics::MetricsCollector;
+
+// Ruta deprecada con fecha de retiro
+pub struct DeprecatedRoute {
+    pub method: &'static str,
+    // Patrón de ruta; los segmentos ":param" aceptan cualquier valor
+    pub path: &'static str,
+    pub deprecated_since: &'static str,
+    pub sunset: &'static str,
+    // Reemplazo recomendado (header Link)
+    pub successor: &'static str,
+    pub note: &'static str,
+}
+
+// Registro de rutas deprecadas
+pub const DEPRECATED_ROUTES: &[DeprecatedRoute] = &[
+    DeprecatedRoute {
+        method: "POST",
+        path: "/api/v1/users",
+        deprecated_since: "2026-10-16",
+        sunset: "2027-01-31",
+        successor: "/api/v1/auth/register",
+        note: "POST /api/v1/users no persiste usuarios; usar POST /api/v1/auth/register",
+    },
+];
+
+fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc())
+}
+
+// Rutas con kill switch activo: DEPRECATION_KILL_SWITCH="POST /api/v1/users,GET /api/v1/x"
+fn kill_switches() -> &'static HashSet<String> {
+    static KILL_SWITCHES: OnceLock<HashSet<String>> = OnceLock::new();
+    KILL_SWITCHES.get_or_init(|| {
+        std::env::var("DEPRECATION_KILL_SWITCH")
+            .unwrap_or_default()
+            .split(',')
+            .map(|k| k.trim().to_string())
+            .filter(|k| !k.is_empty())
+            .collect()
+    })
+}
+
+impl DeprecatedRoute {
+    pub fn key(&self) -> String {
+        format!("{} {}", self.method, self.path)
+    }
+
+    pub fn matches(&self, method: &str, path: &str) -> bool {
+        if !self.method.eq_ignore_ascii_case(method) {
+            return false;
+        }
+
+        let pattern: Vec<&str> = self.path.trim_end_matches('/').split('/').collect();
+        let actual: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+
+        pattern.len() == actual.len()
+            && pattern
+                .iter()
+                .zip(actual.iter())
+                .all(|(p, a)| p.starts_with(':') || p == a)
+    }
+
+    pub fn sunset_at(&self) -> Option<DateTime<Utc>> {
+        parse_date(self.sunset)
+    }
+
+    pub fn deprecated_at(&self) -> Option<DateTime<Utc>> {
+        parse_date(self.deprecated_since)
+    }
+
+    // Después del sunset responde 410 solo si el kill switch de la ruta está activo
+    pub fn is_removed(&self) -> bool {
+        let past_sunset = self.sunset_at().map(|s| Utc::now() >= s).unwrap_or(false);
+        past_sunset && kill_switches().contains(&self.key())
+    }
+
+    pub fn kill_switch_enabled(&self) -> bool {
+        kill_switches().contains(&self.key())
+    }
+}
+
+// Aviso de una ruta del registro (su uso se cuenta en el colector, no en deprecations)
+pub fn is_route_notice(notice: &str) -> bool {
+    DEPRECATED_ROUTES.iter().any(|route| route.note == notice)
+}
+
+pub fn find(method: &str, path: &str) -> Option<&'static DeprecatedRoute> {
+    DEPRECATED_ROUTES.iter().find(|route| route.matches(method, path))
+}
+
+// Middleware que anuncia rutas deprecadas (headers Deprecation/Sunset/Link),
+// cuenta su uso y las retira tras el sunset si el kill switch está activo
+pub async fn route_deprecation_middleware(
+    State(metrics_collector): State<Arc<MetricsCollector>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = match find(request.method().as_str(), request.uri().path()) {
+        Some(route) => route,
+        None => return next.run(request).await,
+    };
+
+    metrics_collector.record_deprecated_call(&route.key());
+
+    let mut response = if route.is_removed() {
+        tracing::info!(
+            event = "deprecated_route_removed",
+            route = %route.key(),
+            "⛔ Ruta retirada tras su fecha de sunset"
+        );
+        (
+            StatusCode::GONE,
+            Json(serde_json::json!({
+                "error": "gone",
+                "message": format!("Esta ruta fue retirada. {}", route.note),
+                "successor": route.successor
+            })),
+        )
+            .into_response()
+    } else {
+        next.run(request).await
+    };
+
+    let headers = response.headers_mut();
+    let deprecation = route
+        .deprecated_at()
+        .map(|d| format!("@{}", d.timestamp()))
+        .unwrap_or_else(|| "true".to_string());
+    if let Ok(value) = HeaderValue::from_str(&deprecation) {
+        headers.insert("deprecation", value);
+    }
+    if let Some(sunset) = route.sunset_at() {
+        if let Ok(value) = HeaderValue::from_str(&sunset.format("%a, %d %b %Y %H:%M:%S GMT").to_string()) {
+            headers.insert("sunset", value);
+        }
+    }
+    if let Ok(value) = HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", route.successor)) {
+        headers.insert("link", value);
+    }
+
+    // Aviso visible en el envelope; el uso ya quedó contado arriba, por ruta
+    match response.extensions_mut().get_mut::<Deprecations>() {
+        Some(existing) => existing.0.push(route.note.to_string()),
+        None => {
+            response.extensions_mut().insert(Deprecations::new([route.note]));
+        }
+    }
+
+    response
+}
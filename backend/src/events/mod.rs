@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgConnection, PgPool};
+use std::sync::OnceLock;
+
+// Tipos de evento y de agregado publicados
+pub const EVENT_USER_REGISTERED: &str = "user_registered";
+pub const EVENT_USER_DELETED: &str = "user_deleted";
+pub const EVENT_USER_RESTORED: &str = "user_restored";
+pub const AGGREGATE_USER: &str = "user";
+
+// Clave del advisory lock que serializa las inserciones (ver `record`)
+const SEQUENCE_LOCK_KEY: i64 = 0x7665_6e74_6173; // "ventas"
+
+// Evento tal como lo leen los consumidores
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DomainEvent {
+    pub sequence: i64,
+    pub event_type: String,
+    pub aggregate_type: String,
+    pub aggregate_id: i64,
+    pub payload: serde_json::Value,
+    #[serde(with = "crate::models::timestamp")]
+    pub occurred_at: DateTime<Utc>,
+}
+
+// Retención (DOMAIN_EVENTS_RETENTION_DAYS, por defecto 365; 0 = conservar todo).
+// Los consumidores que se atrasen más que esto pierden eventos
+pub fn retention_days() -> u32 {
+    static DAYS: OnceLock<u32> = OnceLock::new();
+    *DAYS.get_or_init(|| {
+        std::env::var("DOMAIN_EVENTS_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(365)
+    })
+}
+
+// Registrar un evento dentro de la transacción de la operación que lo produce: si la
+// operación se revierte, el evento no existe.
+// Un BIGSERIAL no garantiza que los eventos se confirmen en orden de sequence; sin el lock
+// un consumidor que lee `after_sequence` podría saltarse uno confirmado tarde. El advisory
+// lock vive hasta el fin de la transacción y ordena las confirmaciones
+pub async fn record(
+    conn: &mut PgConnection,
+    event_type: &str,
+    aggregate_type: &str,
+    aggregate_id: i64,
+    payload: serde_json::Value,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(SEQUENCE_LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+
+    let sequence: i64 = sqlx::query_scalar(
+        "INSERT INTO domain_events (event_type, aggregate_type, aggregate_id, payload)
+         VALUES ($1, $2, $3, $4)
+         RETURNING sequence"
+    )
+    .bind(event_type)
+    .bind(aggregate_type)
+    .bind(aggregate_id)
+    .bind(payload)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    tracing::debug!(
+        event = "domain_event_recorded",
+        event_type = %event_type,
+        aggregate_type = %aggregate_type,
+        aggregate_id = aggregate_id,
+        sequence = sequence,
+        "📬 Evento de dominio registrado"
+    );
+
+    Ok(sequence)
+}
+
+// Eventos posteriores a `after_sequence`, en orden
+pub async fn list_after(pool: &PgPool, after_sequence: i64, limit: i64) -> Result<Vec<DomainEvent>, sqlx::Error> {
+    sqlx::query_as::<_, DomainEvent>(
+        "SELECT sequence, event_type, aggregate_type, aggregate_id, payload, occurred_at
+         FROM domain_events
+         WHERE sequence > $1
+         ORDER BY sequence
+         LIMIT $2"
+    )
+    .bind(after_sequence)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+// Borrar eventos más antiguos que la retención (tarea de limpieza)
+pub async fn purge_expired(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let days = retention_days();
+    if days == 0 {
+        return Ok(0);
+    }
+
+    let result = sqlx::query("DELETE FROM domain_events WHERE occurred_at < NOW() - make_interval(days => $1)")
+        .bind(days as i32)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,161 @@
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::env;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use crate::health::HealthChecker;
+use crate::models::user::User;
+
+pub mod proto {
+    tonic::include_proto!("ventalibre.internal.v1");
+}
+
+use proto::internal_service_server::{InternalService, InternalServiceServer};
+
+// Longitud mínima del token compartido con los servicios internos
+const MIN_TOKEN_BYTES: usize = 32;
+
+// Servidor gRPC interno (proto/internal.proto), en su propio puerto
+//   GRPC_ENABLED=false          levantar el servidor
+//   GRPC_PORT=50051             puerto (mismo HOST que la API HTTP)
+//   GRPC_INTERNAL_TOKEN         token estático que deben enviar los servicios (obligatorio si está activo)
+#[derive(Debug, Clone)]
+pub struct GrpcConfig {
+    pub enabled: bool,
+    pub port: u16,
+    token: Option<String>,
+}
+
+impl GrpcConfig {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: env::var("GRPC_ENABLED")
+                .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+                .unwrap_or(false),
+            port: env::var("GRPC_PORT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(50051),
+            token: env::var("GRPC_INTERNAL_TOKEN").ok().filter(|t| !t.trim().is_empty()),
+        }
+    }
+
+    // Activo sin un token suficientemente largo no se puede arrancar
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.token {
+            _ if !self.enabled => Ok(()),
+            None => Err("GRPC_INTERNAL_TOKEN es obligatorio con GRPC_ENABLED=true".to_string()),
+            Some(token) if token.len() < MIN_TOKEN_BYTES => Err(format!(
+                "GRPC_INTERNAL_TOKEN debe tener al menos {} bytes",
+                MIN_TOKEN_BYTES
+            )),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+// Se comparan los digests para no filtrar por tiempo cuántos bytes coinciden
+fn token_digest(token: &str) -> [u8; 32] {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+// Interceptor: exige "authorization: Bearer <GRPC_INTERNAL_TOKEN>"
+// (tonic fija el tipo de error de los interceptores en Status)
+#[allow(clippy::result_large_err)]
+fn internal_token_interceptor(expected: [u8; 32]) -> impl Fn(Request<()>) -> Result<Request<()>, Status> + Clone {
+    move |request: Request<()>| {
+        let provided = request
+            .metadata()
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(crate::auth::jwt::extract_token_from_header);
+
+        match provided {
+            Some(token) if token_digest(token) == expected => Ok(request),
+            _ => {
+                tracing::warn!(event = "grpc_unauthenticated", "🔒 Llamada gRPC sin token interno válido");
+                Err(Status::unauthenticated("token interno inválido"))
+            }
+        }
+    }
+}
+
+struct InternalApi {
+    pool: PgPool,
+    health_checker: Arc<HealthChecker>,
+}
+
+impl From<User> for proto::User {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            role: user.role.as_str().to_string(),
+            is_active: user.is_active,
+            created_at: user.created_at.timestamp(),
+            updated_at: user.updated_at.timestamp(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl InternalService for InternalApi {
+    async fn get_user(&self, request: Request<proto::GetUserRequest>) -> Result<Response<proto::User>, Status> {
+        let id = request.into_inner().id;
+
+        let user = sqlx::query_as::<_, User>(
+            "SELECT id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at
+             FROM users WHERE id = $1"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, user_id = id, "🚨 Error de base de datos en GetUser (gRPC)");
+            Status::internal("error de base de datos")
+        })?
+        .ok_or_else(|| Status::not_found("usuario no encontrado"))?;
+
+        Ok(Response::new(user.into()))
+    }
+
+    async fn health_check(
+        &self,
+        _request: Request<proto::HealthCheckRequest>,
+    ) -> Result<Response<proto::HealthCheckResponse>, Status> {
+        let health = self.health_checker.check_health().await;
+
+        Ok(Response::new(proto::HealthCheckResponse {
+            status: health.status,
+            version: health.version,
+            uptime_seconds: health.uptime_seconds,
+        }))
+    }
+}
+
+// Servir la API interna hasta que se resuelva `shutdown` (el mismo apagado que HTTP)
+pub async fn serve(
+    config: GrpcConfig,
+    address: SocketAddr,
+    pool: PgPool,
+    health_checker: Arc<HealthChecker>,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), tonic::transport::Error> {
+    let expected = token_digest(config.token.as_deref().unwrap_or_default());
+    let service = InternalServiceServer::with_interceptor(
+        InternalApi { pool, health_checker },
+        internal_token_interceptor(expected),
+    );
+
+    tracing::info!(address = %address, "🛰️ API gRPC interna escuchando");
+    tonic::transport::Server::builder()
+        .add_service(service)
+        .serve_with_shutdown(address, shutdown)
+        .await?;
+    tracing::info!("🛑 API gRPC interna detenida");
+
+    Ok(())
+}
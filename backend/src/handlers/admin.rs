@@ -0,0 +1,1018 @@
+use axum::{
+    extract::{ConnectInfo, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use crate::audit;
+use crate::config;
+use crate::auth::account_deletion::{self, Restoration};
+use crate::auth::api_keys::{self, KNOWN_SCOPES};
+use crate::envelope::{deprecations, sunset};
+use crate::events;
+use crate::metrics::{privacy, MetricsCollector};
+use crate::auth::jwt::{generate_impersonation_token, IMPERSONATION_TTL_MINUTES};
+use crate::auth::middleware::AuthUser;
+use crate::database::Tx;
+use crate::handlers::ApiError;
+use crate::logging::{middleware::get_client_ip, Logger, RequestId};
+use crate::models::auth::{AuthError, AuthResponse, CreateApiKeyRequest};
+use crate::models::contact::{AssignContactRequest, ContactMessage};
+use crate::models::display::{self, DisplayTimezone};
+use crate::models::timestamp;
+use crate::models::user::{MergeUsersRequest, User};
+use crate::outbound::outbound_log;
+use crate::validation::disposable_email;
+
+fn database_error(e: sqlx::Error) -> (StatusCode, Json<AuthError>) {
+    tracing::error!(error = %e, "🚨 Error de base de datos en endpoint de admin");
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(AuthError::new("database_error", "Error de base de datos")),
+    )
+}
+
+// POST /api/v1/admin/disposable-domains/reload
+pub async fn reload_disposable_domains(
+    auth_user: AuthUser,
+) -> Result<Json<Value>, (StatusCode, Json<AuthError>)> {
+    let domains = disposable_email::denylist().reload().map_err(|e| {
+        tracing::error!(
+            error = %e,
+            user_id = auth_user.user.id,
+            "🚨 Error al recargar dominios desechables"
+        );
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("reload_error", "No se pudo recargar la lista de dominios")),
+        )
+    })?;
+
+    tracing::info!(
+        event = "disposable_domains_reload",
+        user_id = auth_user.user.id,
+        domains = domains,
+        "📧 Dominios desechables recargados por admin"
+    );
+
+    Ok(Json(json!({
+        "message": "Lista de dominios desechables recargada",
+        "domains": domains,
+        "timestamp": crate::models::timestamp::now()
+    })))
+}
+
+// POST /api/v1/admin/config/reload
+// Vuelve a leer .env y CONFIG_FILE; si algo es inválido se mantiene la configuración vigente
+pub async fn reload_config(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+) -> Result<Json<Value>, ApiError> {
+    let prepared = config::prepare_reload();
+
+    let details = match &prepared {
+        Ok((_, report)) => json!({ "status": "applied", "report": report }),
+        Err(errors) => json!({ "status": "rejected", "errors": errors }),
+    };
+    // Toda recarga queda en la auditoría; sin registro no se aplica
+    audit::record(&pool, auth_user.user.id, "config_reload", None, details)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "🚨 Error de base de datos en endpoint de admin");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "database_error", "message": "Error de base de datos" })),
+            )
+        })?;
+
+    let (new_config, report) = prepared.map_err(|errors| {
+        tracing::warn!(
+            event = "config_reload_rejected",
+            user_id = auth_user.user.id,
+            errors = ?errors,
+            "⚠️ Recarga de configuración rechazada, se mantiene la vigente"
+        );
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "error": "invalid_config",
+                "message": "La configuración nueva es inválida; se mantiene la vigente",
+                "errors": errors
+            })),
+        )
+    })?;
+
+    config::apply(new_config);
+
+    tracing::info!(
+        event = "config_reload",
+        user_id = auth_user.user.id,
+        changed = ?report.changed,
+        requires_restart = ?report.requires_restart,
+        "🔄 Configuración recargada por admin"
+    );
+
+    Ok(Json(json!({
+        "message": "Configuración recargada",
+        "applied": report.applied,
+        "changed": report.changed,
+        "requires_restart": report.requires_restart,
+        "config_file": report.config_file,
+        "timestamp": crate::models::timestamp::now()
+    })))
+}
+
+
+// POST /api/v1/admin/impersonate/:user_id (también POST /api/v1/users/:id/impersonate)
+// Token de acceso corto para ver la app como el usuario; no se pueden suplantar admins
+pub async fn impersonate_user(
+    Path(user_id): Path<i32>,
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request_id: Option<Extension<RequestId>>,
+    headers: HeaderMap,
+    auth_user: AuthUser,
+) -> Result<Json<AuthResponse>, (StatusCode, Json<AuthError>)> {
+    let admin = &auth_user.user;
+    let client_ip = get_client_ip(&headers, &addr);
+    let request_id = request_id.map(|Extension(id)| id.0).unwrap_or_default();
+
+    // Un token de suplantación no puede encadenar otra
+    if auth_user.is_impersonated() {
+        return Err((StatusCode::FORBIDDEN, Json(AuthError::impersonation_not_allowed())));
+    }
+
+    if admin.id == user_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(AuthError::new("invalid_target", "No puedes suplantarte a ti mismo")),
+        ));
+    }
+
+    let target = sqlx::query_as::<_, User>(
+        "SELECT id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at
+         FROM users WHERE id = $1 AND is_active = true"
+    )
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, Json(AuthError::user_not_found())))?;
+
+    if target.is_admin() {
+        Logger::log_auth_event("impersonation_denied", Some(admin.id), Some(&admin.email), Some(&client_ip), false, &request_id);
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(AuthError::new("invalid_target", "No se puede suplantar a otro admin")),
+        ));
+    }
+
+    let (token, claims) = generate_impersonation_token(&target, admin.id).map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("token_error", "Error al generar token")),
+        )
+    })?;
+
+    audit::record(
+        &pool,
+        admin.id,
+        "impersonation_start",
+        Some(target.id),
+        json!({
+            "ip": client_ip,
+            "jti": claims.jti,
+            "expires_at": claims.exp,
+        }),
+    )
+    .await
+    .map_err(database_error)?;
+
+    Logger::log_auth_event("impersonation_token_issued", Some(admin.id), Some(&admin.email), Some(&client_ip), true, &request_id);
+    tracing::warn!(
+        event = "impersonation_start",
+        admin_id = admin.id,
+        target_user_id = target.id,
+        expires_in_minutes = IMPERSONATION_TTL_MINUTES,
+        "🎭 Admin suplantando a un usuario"
+    );
+
+    Ok(Json(AuthResponse {
+        token,
+        refresh_token: String::new(),
+        user: target.to_public(),
+        expires_at: claims.exp as i64,
+    }))
+}
+
+// POST /api/v1/admin/users/:id/restore
+// Reactivar una cuenta dada de baja por el usuario, dentro del plazo de restauración
+pub async fn restore_user(
+    Path(user_id): Path<i32>,
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+) -> Result<Json<Value>, (StatusCode, Json<AuthError>)> {
+    let user = match account_deletion::restore(&pool, user_id).await.map_err(database_error)? {
+        Restoration::Restored(user) => user,
+        Restoration::NotFound => {
+            return Err((StatusCode::NOT_FOUND, Json(AuthError::user_not_found())));
+        }
+        Restoration::NotDeleted => {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(AuthError::new("account_not_deleted", "La cuenta no está dada de baja")),
+            ));
+        }
+        Restoration::Anonymized => {
+            return Err((
+                StatusCode::GONE,
+                Json(AuthError::new(
+                    "account_anonymized",
+                    "Terminó el plazo de restauración: los datos de la cuenta ya fueron anonimizados",
+                )),
+            ));
+        }
+        Restoration::EmailTaken => {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(AuthError::new(
+                    "email_taken",
+                    "Otra cuenta activa usa el email de esta cuenta desde su baja; cambia el email antes de restaurarla",
+                )),
+            ));
+        }
+        Restoration::UsernameTaken => {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(AuthError::new(
+                    "username_taken",
+                    "Otra cuenta activa usa el nombre de usuario de esta cuenta desde su baja",
+                )),
+            ));
+        }
+    };
+
+    audit::record(&pool, auth_user.user.id, "user_restore", Some(user.id), json!({}))
+        .await
+        .map_err(database_error)?;
+
+    tracing::warn!(
+        event = "user_restored",
+        admin_id = auth_user.user.id,
+        target_user_id = user.id,
+        "♻️ Cuenta dada de baja restaurada"
+    );
+
+    Ok(Json(json!({
+        "user": user.to_public(),
+        "timestamp": crate::models::timestamp::now()
+    })))
+}
+
+// GET /api/v1/admin/deprecations
+pub async fn get_deprecation_usage() -> Json<Value> {
+    Json(json!({
+        "deprecations": deprecations::usage_snapshot(),
+        "timestamp": crate::models::timestamp::now()
+    }))
+}
+
+// POST /api/v1/admin/users/merge
+pub async fn merge_users(
+    tx: Tx,
+    auth_user: AuthUser,
+    display_timezone: DisplayTimezone,
+    Json(request): Json<MergeUsersRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<AuthError>)> {
+    if request.source_id == request.target_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(AuthError::new("invalid_merge", "La cuenta origen y destino deben ser distintas")),
+        ));
+    }
+
+    // Transacción del request: se confirma al responder con éxito
+    let mut conn = tx.conn().await;
+
+    // Bloquear ambas cuentas en orden de id para evitar deadlocks
+    let users = sqlx::query_as::<_, User>(
+        "SELECT id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at
+         FROM users WHERE id = ANY($1) ORDER BY id FOR UPDATE"
+    )
+    .bind(vec![request.source_id, request.target_id])
+    .fetch_all(&mut **conn)
+    .await
+    .map_err(database_error)?;
+
+    let source = users.iter().find(|u| u.id == request.source_id).cloned();
+    let target = users.iter().find(|u| u.id == request.target_id).cloned();
+    let (source, target) = match (source, target) {
+        (Some(source), Some(target)) => (source, target),
+        _ => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(AuthError::user_not_found()),
+            ))
+        }
+    };
+
+    let already_merged: Option<i32> = sqlx::query_scalar(
+        "SELECT merged_into FROM users WHERE id = $1"
+    )
+    .bind(source.id)
+    .fetch_one(&mut **conn)
+    .await
+    .map_err(database_error)?;
+
+    if already_merged.is_some() || !target.is_active {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(AuthError::new("invalid_merge", "La cuenta origen ya fue fusionada o la cuenta destino está inactiva")),
+        ));
+    }
+
+    // Conservar la fecha de creación más antigua
+    let created_at = source.created_at.min(target.created_at);
+
+    let target = sqlx::query_as::<_, User>(
+        "UPDATE users SET created_at = $1 WHERE id = $2
+         RETURNING id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at"
+    )
+    .bind(created_at)
+    .bind(target.id)
+    .fetch_one(&mut **conn)
+    .await
+    .map_err(database_error)?;
+
+    let source = sqlx::query_as::<_, User>(
+        "UPDATE users SET is_active = false, merged_into = $1 WHERE id = $2
+         RETURNING id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at"
+    )
+    .bind(target.id)
+    .bind(source.id)
+    .fetch_one(&mut **conn)
+    .await
+    .map_err(database_error)?;
+
+    audit::record(
+        &mut **conn,
+        auth_user.user.id,
+        "users_merge",
+        Some(target.id),
+        json!({
+            "source_id": source.id,
+            "source_email": source.email,
+            "target_id": target.id,
+            "target_email": target.email,
+            "created_at_kept": crate::models::timestamp::format(&created_at),
+        }),
+    )
+    .await
+    .map_err(database_error)?;
+
+    drop(conn);
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(
+        event = "users_merged",
+        admin_id = auth_user.user.id,
+        source_id = source.id,
+        target_id = target.id,
+        "🔀 Cuentas de usuario fusionadas"
+    );
+
+    Ok(Json(json!({
+        "message": "Cuentas fusionadas exitosamente",
+        "source": source.to_public().with_display(display_timezone),
+        "target": target.to_public().with_display(display_timezone),
+        "merged_into": target.id,
+    })))
+}
+
+// GET /api/v1/admin/users/:id/activity
+pub async fn get_user_activity(
+    State(metrics_collector): State<Arc<MetricsCollector>>,
+    Path(user_id): Path<i32>,
+    Query(params): Query<HashMap<String, String>>,
+    auth_user: AuthUser,
+) -> Json<Value> {
+    let limit: usize = params
+        .get("limit")
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(50)
+        .min(500); // Máximo 500
+
+    let activity = metrics_collector.get_user_activity(user_id, limit);
+    let raw_ids = privacy::can_view_user_ids(&auth_user.user);
+
+    tracing::info!(
+        event = "user_activity_accessed",
+        admin_id = auth_user.user.id,
+        user_id = user_id,
+        results = activity.len(),
+        raw_user_ids = raw_ids,
+        "🔍 Actividad de usuario consultada por admin"
+    );
+
+    Json(json!({
+        "user_id": privacy::user_id_value(Some(user_id), raw_ids),
+        "activity": privacy::redact_request_metrics(&activity, raw_ids),
+        "limit": limit,
+        "timestamp": crate::models::timestamp::now()
+    }))
+}
+
+// GET /api/v1/admin/deprecated-routes
+pub async fn get_deprecated_routes(
+    State(metrics_collector): State<Arc<MetricsCollector>>,
+) -> Json<Value> {
+    let last_24h = chrono::Utc::now() - chrono::Duration::hours(24);
+
+    let routes: Vec<Value> = sunset::DEPRECATED_ROUTES
+        .iter()
+        .map(|route| {
+            let usage = metrics_collector.deprecated_route_usage(&route.key());
+            json!({
+                "method": route.method,
+                "path": route.path,
+                "deprecated_since": route.deprecated_since,
+                "sunset": route.sunset,
+                "successor": route.successor,
+                "note": route.note,
+                "kill_switch": route.kill_switch_enabled(),
+                "removed": route.is_removed(),
+                "total_calls": usage.as_ref().map(|u| u.total_calls).unwrap_or(0),
+                "last_called_at": usage.map(|u| crate::models::timestamp::format(&u.last_called_at)),
+                "calls_last_24h": metrics_collector
+                    .count_requests_since(last_24h, |m| route.matches(&m.method, &m.path)),
+            })
+        })
+        .collect();
+
+    Json(json!({
+        "deprecated_routes": routes,
+        "timestamp": crate::models::timestamp::now()
+    }))
+}
+
+// GET /api/v1/admin/inspect/user/:id
+// Vista de solo lectura para soporte: usuario + registros relacionados, sin campos sensibles.
+// Sin seudónimos a propósito: se consulta por id y devuelve el email para contactar al
+// usuario, así que ocultar ids no protege nada (la redacción de METRICS_EXPOSE_USER_IDS
+// aplica a las vistas de métricas, incluida /admin/users/:id/activity). El acceso se audita
+pub async fn inspect_user(
+    State((pool, metrics_collector)): State<(PgPool, Arc<MetricsCollector>)>,
+    Path(user_id): Path<i32>,
+    auth_user: AuthUser,
+    display_timezone: DisplayTimezone,
+) -> Result<Json<Value>, (StatusCode, Json<AuthError>)> {
+    let user = sqlx::query_as::<_, User>(
+        "SELECT id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at
+         FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(database_error)?;
+
+    // El acceso se audita siempre, exista o no el usuario
+    audit::record(
+        &pool,
+        auth_user.user.id,
+        "inspect_user",
+        user.as_ref().map(|u| u.id),
+        json!({ "user_id": user_id, "found": user.is_some() }),
+    )
+    .await
+    .map_err(database_error)?;
+
+    let user = user.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(AuthError::user_not_found()),
+        )
+    })?;
+
+    let merged_into: Option<i32> = sqlx::query_scalar("SELECT merged_into FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(database_error)?;
+
+    let merged_accounts: Vec<i32> = sqlx::query_scalar(
+        "SELECT id FROM users WHERE merged_into = $1 ORDER BY id"
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(database_error)?;
+
+    // Sesiones: solo metadatos, nunca el identificador del token
+    let sessions: Vec<Value> = sqlx::query_as::<_, (i32, DateTime<Utc>, DateTime<Utc>, Option<DateTime<Utc>>)>(
+        "SELECT id, created_at, expires_at, revoked_at FROM refresh_tokens
+         WHERE user_id = $1 ORDER BY created_at DESC LIMIT 50"
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(database_error)?
+    .into_iter()
+    .map(|(id, created_at, expires_at, revoked_at)| {
+        json!({
+            "id": id,
+            "created_at": timestamp::format(&created_at),
+            "expires_at": timestamp::format(&expires_at),
+            "revoked_at": revoked_at.as_ref().map(timestamp::format),
+        })
+    })
+    .collect();
+
+    let audit_entries: Vec<Value> = sqlx::query_as::<_, (i32, i32, String, Option<i32>, Value, DateTime<Utc>)>(
+        "SELECT id, admin_id, action, target_user_id, details, created_at FROM admin_audit_log
+         WHERE target_user_id = $1 OR admin_id = $1
+         ORDER BY created_at DESC LIMIT 50"
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(database_error)?
+    .into_iter()
+    .map(|(id, admin_id, action, target_user_id, details, created_at)| {
+        json!({
+            "id": id,
+            "admin_id": admin_id,
+            "action": action,
+            "target_user_id": target_user_id,
+            "details": details,
+            "created_at": timestamp::format(&created_at),
+        })
+    })
+    .collect();
+
+    let recent_events = privacy::redact_request_metrics(&metrics_collector.get_user_activity(user_id, 50), true);
+
+    Ok(Json(json!({
+        "user": {
+            "id": user.id,
+            "name": user.name,
+            "email": user.email,
+            "role": user.role,
+            "is_admin": user.is_admin(),
+            "is_active": user.is_active,
+            "merged_into": merged_into,
+            "created_at": timestamp::format(&user.created_at),
+            "updated_at": timestamp::format(&user.updated_at),
+            "created_at_local": display::format_local(&user.created_at, display_timezone.0),
+            "created_at_relative": display::relative_es(&user.created_at, &Utc::now()),
+        },
+        "merged_accounts": merged_accounts,
+        "sessions": sessions,
+        "audit_entries": audit_entries,
+        "recent_events": recent_events,
+        "timestamp": timestamp::now()
+    })))
+}
+
+// GET /api/v1/admin/outbound-calls?destination=&status=&limit=
+// status: código HTTP exacto, "success" o "failed"
+pub async fn get_outbound_calls(
+    Query(params): Query<HashMap<String, String>>,
+) -> Json<Value> {
+    let limit: usize = params
+        .get("limit")
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(100)
+        .min(1000); // Máximo 1000
+
+    let destination = params.get("destination");
+    let status = params.get("status").map(|s| s.to_lowercase());
+
+    let log = outbound_log();
+    let calls = log.recent(limit, |call| {
+        let destination_matches = destination.is_none_or(|d| &call.destination == d);
+        let status_matches = match status.as_deref() {
+            None => true,
+            Some("success") => call.success,
+            Some("failed") => !call.success,
+            Some(code) => call.status.map(|s| s.to_string()).as_deref() == Some(code),
+        };
+        destination_matches && status_matches
+    });
+
+    Json(json!({
+        "calls": calls,
+        "destinations": log.destination_stats(),
+        "limit": limit,
+        "timestamp": crate::models::timestamp::now()
+    }))
+}
+
+// GET /api/v1/admin/events?after_sequence=&limit=
+// Lectura por polling del registro de eventos de dominio, en orden de sequence. El consumidor
+// guarda `next_after_sequence` y lo envía en la siguiente llamada
+pub async fn list_domain_events(
+    State(pool): State<PgPool>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, (StatusCode, Json<AuthError>)> {
+    let after_sequence: i64 = params
+        .get("after_sequence")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0)
+        .max(0);
+    let limit: i64 = params
+        .get("limit")
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(100)
+        .clamp(1, 1000);
+
+    let domain_events = events::list_after(&pool, after_sequence, limit)
+        .await
+        .map_err(database_error)?;
+    let next_after_sequence = domain_events.last().map(|e| e.sequence).unwrap_or(after_sequence);
+
+    Ok(Json(json!({
+        "events": domain_events,
+        "next_after_sequence": next_after_sequence,
+        "limit": limit,
+        "retention_days": events::retention_days(),
+        "timestamp": crate::models::timestamp::now()
+    })))
+}
+
+// POST /api/v1/admin/api-keys
+// La key completa se devuelve solo en esta respuesta; después solo se conoce su prefijo
+pub async fn create_api_key(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<AuthError>)> {
+    if request.name.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(AuthError::new("invalid_name", "El nombre es requerido")),
+        ));
+    }
+
+    if request.scopes.is_empty() || request.scopes.iter().any(|s| !KNOWN_SCOPES.contains(&s.as_str())) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(AuthError::new(
+                "invalid_scopes",
+                &format!("Scopes válidos: {}", KNOWN_SCOPES.join(", ")),
+            )),
+        ));
+    }
+
+    let owner_id = request.user_id.unwrap_or(auth_user.user.id);
+    let owner_exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1 AND is_active = true)")
+        .bind(owner_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(database_error)?;
+
+    if !owner_exists {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(AuthError::user_not_found()),
+        ));
+    }
+
+    let expires_at = request
+        .expires_in_days
+        .filter(|days| *days > 0)
+        .map(|days| Utc::now() + chrono::Duration::days(days));
+
+    let (raw_key, api_key) = api_keys::create(&pool, owner_id, request.name.trim(), &request.scopes, expires_at)
+        .await
+        .map_err(database_error)?;
+
+    audit::record(
+        &pool,
+        auth_user.user.id,
+        "api_key_create",
+        Some(owner_id),
+        json!({
+            "api_key_id": api_key.id,
+            "key_prefix": api_key.key_prefix,
+            "scopes": api_key.scopes,
+        }),
+    )
+    .await
+    .map_err(database_error)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "api_key": api_key,
+            "key": raw_key,
+            "message": "Guarda esta key: no se volverá a mostrar",
+        })),
+    ))
+}
+
+// GET /api/v1/admin/api-keys
+pub async fn list_api_keys(
+    State(pool): State<PgPool>,
+) -> Result<Json<Value>, (StatusCode, Json<AuthError>)> {
+    let keys = api_keys::list(&pool).await.map_err(database_error)?;
+
+    Ok(Json(json!({
+        "api_keys": keys,
+        "timestamp": crate::models::timestamp::now()
+    })))
+}
+
+// DELETE /api/v1/admin/api-keys/:id
+pub async fn revoke_api_key(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<Value>, (StatusCode, Json<AuthError>)> {
+    let api_key = api_keys::revoke(&pool, id)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(AuthError::new("api_key_not_found", "API key no encontrada o ya revocada")),
+            )
+        })?;
+
+    audit::record(
+        &pool,
+        auth_user.user.id,
+        "api_key_revoke",
+        Some(api_key.user_id),
+        json!({ "api_key_id": api_key.id, "key_prefix": api_key.key_prefix }),
+    )
+    .await
+    .map_err(database_error)?;
+
+    Ok(Json(json!({
+        "message": "API key revocada",
+        "api_key": api_key,
+    })))
+}
+
+const CONTACT_MESSAGE_COLUMNS: &str =
+    "id, name, email, subject, message, ip_address, status, assigned_to, handled_by, handled_at, created_at";
+
+fn contact_message_not_found() -> (StatusCode, Json<AuthError>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(AuthError::new("contact_message_not_found", "Mensaje de contacto no encontrado")),
+    )
+}
+
+// GET /api/v1/admin/contact-messages?status=&limit=
+// status: "open" o "handled" (por defecto todos)
+pub async fn list_contact_messages(
+    State(pool): State<PgPool>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, (StatusCode, Json<AuthError>)> {
+    let limit: i64 = params
+        .get("limit")
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(100)
+        .clamp(1, 1000);
+    let status = params.get("status").map(|s| s.to_lowercase());
+
+    let messages = sqlx::query_as::<_, ContactMessage>(&format!(
+        "SELECT {} FROM contact_messages
+         WHERE ($1::TEXT IS NULL OR status = $1)
+         ORDER BY created_at DESC LIMIT $2",
+        CONTACT_MESSAGE_COLUMNS
+    ))
+    .bind(status)
+    .bind(limit)
+    .fetch_all(&pool)
+    .await
+    .map_err(database_error)?;
+
+    Ok(Json(json!({
+        "contact_messages": messages,
+        "limit": limit,
+        "timestamp": crate::models::timestamp::now()
+    })))
+}
+
+// POST /api/v1/admin/contact-messages/:id/assign
+// Sin admin_id se asigna a quien hace el request
+pub async fn assign_contact_message(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+    request: Option<Json<AssignContactRequest>>,
+) -> Result<Json<Value>, (StatusCode, Json<AuthError>)> {
+    let Json(request) = request.unwrap_or_default();
+    let admin_id = request.admin_id.unwrap_or(auth_user.user.id);
+
+    let can_handle: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM users WHERE id = $1 AND role IN ('moderator', 'admin') AND is_active = true)"
+    )
+    .bind(admin_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(database_error)?;
+
+    if !can_handle {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(AuthError::new("invalid_assignee", "Solo se puede asignar a un moderador o admin activo")),
+        ));
+    }
+
+    let message = sqlx::query_as::<_, ContactMessage>(&format!(
+        "UPDATE contact_messages SET assigned_to = $2 WHERE id = $1 RETURNING {}",
+        CONTACT_MESSAGE_COLUMNS
+    ))
+    .bind(id)
+    .bind(admin_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(contact_message_not_found)?;
+
+    audit::record(
+        &pool,
+        auth_user.user.id,
+        "contact_message_assign",
+        Some(admin_id),
+        json!({ "contact_message_id": message.id }),
+    )
+    .await
+    .map_err(database_error)?;
+
+    Ok(Json(json!({
+        "message": "Mensaje de contacto asignado",
+        "contact_message": message,
+    })))
+}
+
+// POST /api/v1/admin/contact-messages/:id/handle
+pub async fn handle_contact_message(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    auth_user: AuthUser,
+) -> Result<Json<Value>, (StatusCode, Json<AuthError>)> {
+    let message = sqlx::query_as::<_, ContactMessage>(&format!(
+        "UPDATE contact_messages
+         SET status = 'handled', handled_by = $2, handled_at = NOW(),
+             assigned_to = COALESCE(assigned_to, $2)
+         WHERE id = $1 AND status <> 'handled'
+         RETURNING {}",
+        CONTACT_MESSAGE_COLUMNS
+    ))
+    .bind(id)
+    .bind(auth_user.user.id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(AuthError::new(
+                "contact_message_not_found",
+                "Mensaje de contacto no encontrado o ya atendido",
+            )),
+        )
+    })?;
+
+    audit::record(
+        &pool,
+        auth_user.user.id,
+        "contact_message_handle",
+        None,
+        json!({ "contact_message_id": message.id, "subject": message.subject }),
+    )
+    .await
+    .map_err(database_error)?;
+
+    Ok(Json(json!({
+        "message": "Mensaje de contacto marcado como atendido",
+        "contact_message": message,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+    use crate::models::user::Role;
+    use crate::test_support::{unique_email, TestApp, PASSWORD};
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn impersonation_token_is_limited() {
+        let app = TestApp::spawn().await;
+        let (admin_id, admin_token) = app.user_with_role(Role::Admin).await;
+        let (user_id, _) = app.register(&unique_email("suplantado"), None).await;
+
+        let (status, _, body) = app
+            .request(Method::POST, &format!("/api/v1/admin/impersonate/{}", user_id), Some(&admin_token), None)
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["user"]["id"], user_id);
+        let token = body["token"].as_str().unwrap();
+
+        let claims = crate::auth::verify_token(token).unwrap();
+        assert_eq!(claims.impersonated_by, Some(admin_id));
+        assert_eq!(claims.sub, user_id.to_string());
+
+        // Actúa como el usuario...
+        let (status, _, body) = app.request(Method::GET, "/api/v1/auth/sessions", Some(token), None).await;
+        assert_eq!(status, StatusCode::OK);
+        let session_id = body["sessions"][0]["id"].as_i64().unwrap();
+
+        // ...pero no toca sus credenciales ni sesiones, no exporta sus datos ni encadena suplantaciones
+        let denied = [
+            (
+                Method::POST,
+                "/api/v1/auth/change-password".to_string(),
+                Some(json!({ "current_password": PASSWORD, "new_password": "Monte-Verde-2027" })),
+            ),
+            (Method::GET, "/api/v1/auth/me/export".to_string(), None),
+            (Method::POST, "/api/v1/auth/logout-all".to_string(), None),
+            (Method::DELETE, format!("/api/v1/auth/sessions/{}", session_id), None),
+            (Method::DELETE, "/api/v1/auth/devices/1".to_string(), None),
+            (Method::POST, "/api/v1/auth/2fa/enable".to_string(), None),
+            (Method::POST, "/api/v1/auth/2fa/verify".to_string(), Some(json!({ "code": "000000" }))),
+            (Method::POST, "/api/v1/auth/oauth/google/link".to_string(), None),
+            (Method::POST, format!("/api/v1/admin/impersonate/{}", user_id), None),
+        ];
+        for (method, uri, body) in denied {
+            let (status, _, response) = app.request(method, &uri, Some(token), body).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{}: {}", uri, response);
+            if uri.starts_with("/api/v1/auth/") {
+                assert_eq!(response["error"], "impersonation_not_allowed", "{}", uri);
+            }
+        }
+
+        app.finish().await;
+    }
+
+    async fn delete_account(app: &TestApp, token: &str) {
+        let (status, _, body) = app
+            .request(Method::DELETE, "/api/v1/auth/me", Some(token), Some(json!({ "current_password": PASSWORD })))
+            .await;
+        assert!(status.is_success(), "{}: {}", status, body);
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn restore_conflicts_when_email_was_reused() {
+        let app = TestApp::spawn().await;
+        let (_, admin_token) = app.user_with_role(Role::Admin).await;
+        let email = unique_email("restaurar");
+        let (old_id, token) = app.register(&email, None).await;
+        let restore = format!("/api/v1/admin/users/{}/restore", old_id);
+
+        delete_account(&app, &token).await;
+
+        // El email de una cuenta dada de baja se puede volver a registrar
+        let (new_id, new_token) = app.register(&email, None).await;
+        assert_ne!(new_id, old_id);
+
+        let (status, _, body) = app.request(Method::POST, &restore, Some(&admin_token), None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error"], "email_taken");
+
+        // Liberado el email, la restauración procede
+        delete_account(&app, &new_token).await;
+        let (status, _, body) = app.request(Method::POST, &restore, Some(&admin_token), None).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["user"]["id"], old_id);
+        assert_eq!(app.login(&email, PASSWORD).await.0, StatusCode::OK);
+
+        let (status, _, body) = app.request(Method::POST, &restore, Some(&admin_token), None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error"], "account_not_deleted");
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn restore_conflicts_when_username_was_reused() {
+        let app = TestApp::spawn().await;
+        let (_, admin_token) = app.user_with_role(Role::Admin).await;
+        let (old_id, token) = app.register(&unique_email("antes"), Some("mamani")).await;
+
+        delete_account(&app, &token).await;
+        app.register(&unique_email("despues"), Some("mamani")).await;
+
+        let (status, _, body) = app
+            .request(Method::POST, &format!("/api/v1/admin/users/{}/restore", old_id), Some(&admin_token), None)
+            .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error"], "username_taken");
+
+        app.finish().await;
+    }
+}
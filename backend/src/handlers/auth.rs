@@ -1,21 +1,46 @@
 use axum::{
-    extract::State,
+    extract::{ConnectInfo, Path, State},
     http::StatusCode,
-    response::Json,
+    response::{IntoResponse, Json, Response},
+    Extension,
 };
-use bcrypt::{hash, verify, DEFAULT_COST};
 use chrono::Utc;
-use sqlx::PgPool;
-use crate::auth::generate_token;
-use crate::models::auth::{AuthError, AuthResponse, LoginRequest, RegisterRequest};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgPool};
+use std::net::SocketAddr;
+use crate::auth::account_deletion;
+use crate::auth::api_keys;
+use crate::auth::lockout::login_attempts;
+use crate::auth::magic_link::{self, Redemption};
+use crate::auth::middleware::AuthUser;
+use crate::auth::rate_limit::{rate_limited_response, rate_limiter};
+use crate::auth::sessions::{self, ClientInfo};
+use crate::auth::totp;
+use crate::auth::{
+    generate_admin_token, generate_challenge_token, generate_refresh_token, generate_token,
+    verify_challenge_token, hash_password, verify_password, verify_refresh_token,
+    ADMIN_TOKEN_MAX_TTL_HOURS,
+};
+use crate::events;
+use crate::handlers::created;
+use crate::logging::{middleware::get_client_ip, Logger, RequestId};
+use crate::models::auth::{
+    AdminTokenRequest, AuthError, AuthResponse, ChangePasswordRequest, DeleteAccountRequest, ForgotPasswordRequest, IntrospectRequest,
+    IntrospectResponse, LoginRequest, MagicLinkRequest, MagicLinkVerifyRequest, RefreshRequest, RegisterRequest,
+    ResetPasswordRequest, TotpCodeRequest, TwoFactorChallengeRequest, TwoFactorChallengeResponse,
+};
+use crate::models::display::DisplayTimezone;
 use crate::models::user::{CreateUserRequest, User};
 
 // POST /api/v1/auth/register
 pub async fn register(
     
     State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: axum::http::HeaderMap,
     Json(request): Json<RegisterRequest>,
-) -> Result<Json<AuthResponse>, (StatusCode, Json<AuthError>)> {
+) -> Result<Response, (StatusCode, Json<AuthError>)> {
     tracing::info!("🔄 Intento de registro: email={}", request.email);
     // Validar datos de entrada
     if request.name.trim().is_empty() {
@@ -32,18 +57,40 @@ pub async fn register(
         ));
     }
 
-    if request.password.len() < 6 {
+    if crate::validation::denylist().is_disposable(&request.email) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(AuthError::disposable_email()),
+        ));
+    }
+
+    if let Err(violations) = crate::validation::password_policy().check(&request.password, Some(&request.email)) {
         return Err((
             StatusCode::BAD_REQUEST,
-            Json(AuthError::new("weak_password", "La contraseña debe tener al menos 6 caracteres")),
+            Json(AuthError::weak_password(violations)),
         ));
     }
 
+    // Nombre de usuario opcional: solo letras y números ASCII, así nunca se confunde con un email
+    let username = match request.username.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+        Some(username) => {
+            if !(3..=30).contains(&username.len()) || !username.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(AuthError::invalid_username()),
+                ));
+            }
+            Some(username.to_lowercase())
+        }
+        None => None,
+    };
+
     // Verificar que el email no exista
-    let existing_user = sqlx::query!(
-        "SELECT id FROM users WHERE email = $1",
-        request.email.trim().to_lowercase()
+    // Las cuentas dadas de baja no cuentan (mismo predicado que el índice único)
+    let existing_user: Option<i32> = sqlx::query_scalar(
+        "SELECT id FROM users WHERE email = $1 AND deleted_at IS NULL"
     )
+    .bind(request.email.trim().to_lowercase())
     .fetch_optional(&pool)
     .await
     .map_err(|_| {
@@ -60,26 +107,37 @@ pub async fn register(
         ));
     }
 
+    if let Some(username) = &username {
+        let existing_username: Option<i32> = sqlx::query_scalar(
+            "SELECT id FROM users WHERE username = $1 AND deleted_at IS NULL"
+        )
+        .bind(username)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(AuthError::new("database_error", "Error de base de datos")),
+            )
+        })?;
+
+        if existing_username.is_some() {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(AuthError::username_exists()),
+            ));
+        }
+    }
+
     // Hash de la contraseña
-    let password_hash = hash(&request.password, DEFAULT_COST).map_err(|_| {
+    let password_hash = hash_password(&request.password).map_err(|_| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(AuthError::new("hash_error", "Error al procesar contraseña")),
         )
     })?;
 
-    // Crear usuario
-    let user = sqlx::query_as::<_, User>(
-        "INSERT INTO users (name, email, password_hash, is_admin, is_active, created_at, updated_at)
-         VALUES ($1, $2, $3, false, true, $4, $4)
-         RETURNING id, name, email, password_hash, is_admin, is_active, created_at, updated_at"
-    )
-    .bind(request.name.trim())
-    .bind(request.email.trim().to_lowercase())
-    .bind(password_hash)
-    .fetch_one(&pool)
-    .await
-    .map_err(|e| {
+    let create_error = |e: sqlx::Error| {
     tracing::error!(
         error = %e,
         email = %request.email,
@@ -89,37 +147,89 @@ pub async fn register(
         StatusCode::INTERNAL_SERVER_ERROR,
         Json(AuthError::new("create_user_error", "Error al crear usuario")),
     )
-})?;
+};
 
-    // Generar token JWT
-    let token = generate_token(&user).map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(AuthError::new("token_error", "Error al generar token")),
-        )
+    // Crear usuario y su evento de dominio en la misma transacción
+    let mut tx = pool.begin().await.map_err(create_error)?;
+
+    let user = sqlx::query_as::<_, User>(
+        "INSERT INTO users (name, email, password_hash, is_admin, is_active, username)
+         VALUES ($1, $2, $3, false, true, $4)
+         RETURNING id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at"
+    )
+    .bind(request.name.trim())
+    .bind(request.email.trim().to_lowercase())
+    .bind(password_hash)
+    .bind(&username)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        // Registro concurrente con el mismo email o usuario: lo detectan los índices únicos
+        sqlx::Error::Database(ref db_error) if db_error.is_unique_violation() => {
+            let conflict = if db_error.constraint() == Some("idx_users_username_active") {
+                AuthError::username_exists()
+            } else {
+                AuthError::email_exists()
+            };
+            (StatusCode::CONFLICT, Json(conflict))
+        }
+        e => create_error(e),
     })?;
 
-    // Calcular expiración (24 horas)
-    let expires_at = (Utc::now() + chrono::Duration::hours(24)).timestamp();
+    events::record(
+        &mut tx,
+        events::EVENT_USER_REGISTERED,
+        events::AGGREGATE_USER,
+        user.id.into(),
+        serde_json::json!({ "method": "password" }),
+    )
+    .await
+    .map_err(create_error)?;
 
-    Ok(Json(AuthResponse {
-        token,
-        user: user.to_public(),
-        expires_at,
-    }))
+    tx.commit().await.map_err(create_error)?;
+
+    // Generar tokens JWT
+    let response = start_session(&pool, &user, &ClientInfo::from_request(&headers, &addr), false).await?;
+
+    Ok(created(format!("/api/v1/users/{}", user.id), token_response(response)))
+}
+
+// Un hash corrupto en BD no es un error del cliente: se registra y cuenta como
+// contraseña incorrecta (invalid_credentials) en vez de responder 500
+fn check_password(password: &str, stored: &str, user_id: Option<i32>) -> bool {
+    verify_password(password, stored).unwrap_or_else(|e| {
+        tracing::warn!(error = %e, user_id = ?user_id, "⚠️ Hash de contraseña ilegible");
+        false
+    })
+}
+
+// Respuesta con tokens; con AUTH_TRANSPORT=cookie agrega además la cookie de sesión
+pub(crate) fn token_response(response: AuthResponse) -> Response {
+    let mut http_response = Json(&response).into_response();
+    crate::auth::cookie::set_token_cookie(&mut http_response, &response.token, response.expires_at);
+    http_response
 }
 
 // POST /api/v1/auth/login
 pub async fn login(
     State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request_id: Option<Extension<RequestId>>,
+    headers: axum::http::HeaderMap,
     Json(request): Json<LoginRequest>,
-) -> Result<Json<AuthResponse>, (StatusCode, Json<AuthError>)> {
-    // Buscar usuario por email
+) -> Result<Response, (StatusCode, Json<AuthError>)> {
+    // Email o nombre de usuario; ambos se guardan en minúsculas
+    let identifier = request.email.trim().to_lowercase();
+    let client_ip = get_client_ip(&headers, &addr);
+    let request_id = request_id.map(|Extension(id)| id.0).unwrap_or_default();
+    let attempts = login_attempts();
+
+    // Buscar usuario por email o nombre de usuario (un username nunca contiene '@')
     let user = sqlx::query_as::<_, User>(
-        "SELECT id, name, email, password_hash, is_admin, is_active, created_at, updated_at
-         FROM users WHERE email = $1"
+        "SELECT id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at
+         FROM users WHERE (email = $1 OR username = $1) AND deleted_at IS NULL"
     )
-    .bind(request.email.trim().to_lowercase())
+    .bind(&identifier)
     .fetch_optional(&pool)
     .await
     .map_err(|_| {
@@ -127,14 +237,41 @@ pub async fn login(
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(AuthError::new("database_error", "Error de base de datos")),
         )
-    })?
-    .ok_or_else(|| {
-        (
-            StatusCode::UNAUTHORIZED,
-            Json(AuthError::invalid_credentials()),
-        )
     })?;
 
+    // El bloqueo se lleva por el email de la cuenta: entrar por email o por username
+    // suma al mismo contador. Un identificador sin cuenta se cuenta tal cual
+    let email = user.as_ref().map_or(identifier, |u| u.email.clone());
+
+    // Cuenta bloqueada por intentos fallidos
+    if let Some(remaining) = attempts.locked_for(&email) {
+        Logger::log_auth_event("login_blocked", user.as_ref().map(|u| u.id), Some(&email), Some(&client_ip), false, &request_id);
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(AuthError::account_locked(remaining.as_secs().div_ceil(60))),
+        ));
+    }
+
+    // Verificar contraseña (un email inexistente cuenta como fallo igual que una contraseña errónea)
+    let password_valid = match user.as_ref().and_then(|u| u.password_hash.as_ref()) {
+        Some(password_hash) => check_password(&request.password, password_hash, user.as_ref().map(|u| u.id)),
+        None => false,
+    };
+
+    let user = match user {
+        Some(user) if password_valid => user,
+        user => {
+            let user_id = user.map(|u| u.id);
+            if attempts.record_failure(&email) {
+                Logger::log_auth_event("account_locked", user_id, Some(&email), Some(&client_ip), false, &request_id);
+            }
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                Json(AuthError::invalid_credentials()),
+            ));
+        }
+    };
+
     // Verificar que el usuario esté activo
     if !user.is_active {
         return Err((
@@ -143,77 +280,451 @@ pub async fn login(
         ));
     }
 
-    // Verificar contraseña
-    let password_hash = user.password_hash.as_ref().ok_or_else(|| {
+    // Migrar el hash al algoritmo configurado aprovechando la contraseña en claro
+    if user.password_hash.as_deref().is_some_and(crate::auth::password::needs_rehash) {
+        match hash_password(&request.password) {
+            Ok(new_hash) => {
+                if let Err(e) = sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+                    .bind(new_hash)
+                    .bind(user.id)
+                    .execute(&pool)
+                    .await
+                {
+                    tracing::warn!(error = %e, user_id = user.id, "⚠️ No se pudo migrar el hash de contraseña");
+                } else {
+                    tracing::info!(event = "password_rehashed", user_id = user.id, "🔁 Hash de contraseña migrado");
+                }
+            }
+            Err(e) => tracing::warn!(error = %e, user_id = user.id, "⚠️ No se pudo migrar el hash de contraseña"),
+        }
+    }
+
+    // Con 2FA activo se devuelve un desafío; el contador de fallos se reinicia
+    // recién al validar el código, para que no se pueda eludir reintentando el login
+    if let Some(challenge) = two_factor_challenge_for(&pool, &user, request.remember_me).await? {
+        return Ok(Json(challenge).into_response());
+    }
+
+    attempts.reset(&email);
+
+    // Registrar el dispositivo recién con ambos factores validados (con 2FA, lo hace
+    // two_factor_challenge); uno desconocido genera una alerta
+    let user_agent = headers.get(axum::http::header::USER_AGENT).and_then(|h| h.to_str().ok());
+    crate::auth::devices::track_login(&pool, &user, user_agent, &client_ip).await;
+
+    // Generar tokens JWT
+    let response = start_session(&pool, &user, &ClientInfo::from_request(&headers, &addr), request.remember_me).await?;
+
+    Ok(token_response(response))
+}
+
+// POST /api/v1/auth/refresh
+pub async fn refresh(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<RefreshRequest>,
+) -> Result<Response, (StatusCode, Json<AuthError>)> {
+    let invalid = || {
         (
             StatusCode::UNAUTHORIZED,
-            Json(AuthError::invalid_credentials()),
+            Json(AuthError::invalid_refresh_token()),
         )
-    })?;
+    };
 
-    let password_valid = verify(&request.password, password_hash).map_err(|_| {
+    let database_error = |e: sqlx::Error| {
+        tracing::error!(error = %e, "🚨 Error de base de datos al renovar tokens");
         (
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(AuthError::new("verification_error", "Error al verificar contraseña")),
+            Json(AuthError::new("database_error", "Error de base de datos")),
         )
-    })?;
+    };
 
-    if !password_valid {
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(AuthError::invalid_credentials()),
-        ));
+    // Verificar firma, expiración y tipo del token
+    let claims = verify_refresh_token(&request.refresh_token).map_err(|_| invalid())?;
+    let jti = claims.jti.clone().ok_or_else(invalid)?;
+    let user_id: i32 = claims.sub.parse().map_err(|_| invalid())?;
+
+    // Revocar el token presentado y emitir su reemplazo en una sola transacción: si algo
+    // falla, el token presentado sigue vigente y no queda un reemplazo huérfano
+    let mut tx = pool.begin().await.map_err(database_error)?;
+
+    // Revocar el token presentado; si ya estaba revocado o no existe, se rechaza
+    let revoked = sqlx::query(
+        "UPDATE refresh_tokens SET revoked_at = NOW()
+         WHERE jti = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW()"
+    )
+    .bind(&jti)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    if revoked.rows_affected() == 0 {
+        // Un token ya rotado que vuelve a presentarse indica que alguien más lo tiene:
+        // se cierra la sesión entera (el reemplazo también deja de servir)
+        let replaced_by: Option<Option<String>> = sqlx::query_scalar(
+            "SELECT replaced_by FROM refresh_tokens WHERE jti = $1 AND user_id = $2"
+        )
+        .bind(&jti)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(database_error)?;
+        drop(tx);
+
+        match (replaced_by.flatten(), claims.sid) {
+            (Some(_), Some(session_id)) => {
+                sessions::revoke(&pool, user_id, session_id).await.map_err(database_error)?;
+                tracing::warn!(
+                    event = "refresh_token_reuse",
+                    user_id = user_id,
+                    session_id = session_id,
+                    "🚨 Refresh token ya rotado reutilizado; sesión revocada"
+                );
+            }
+            _ => tracing::warn!(
+                event = "refresh_token_rejected",
+                user_id = user_id,
+                "⚠️ Refresh token revocado o desconocido"
+            ),
+        }
+        return Err(invalid());
+    }
+
+    // El usuario debe seguir existiendo y estar activo
+    let user = sqlx::query_as::<_, User>(
+        "SELECT id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at
+         FROM users WHERE id = $1 AND is_active = true"
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(invalid)?;
+
+    if crate::auth::jwt::is_token_stale(&claims, &user) {
+        tracing::warn!(
+            event = "refresh_token_rejected",
+            user_id = user_id,
+            "⚠️ Refresh token anterior a una invalidación global"
+        );
+        return Err(invalid());
+    }
+
+    // Emitir nuevo par de tokens (rotación) en la misma sesión, que debe seguir vigente;
+    // los refresh tokens anteriores a las sesiones abren una nueva
+    let session_id = match claims.sid {
+        Some(session_id) => {
+            let active = sessions::touch(&mut *tx, session_id, user.id, None).await.map_err(database_error)?;
+            if !active {
+                tracing::warn!(
+                    event = "refresh_token_rejected",
+                    user_id = user_id,
+                    session_id = session_id,
+                    "⚠️ Refresh token de una sesión revocada"
+                );
+                return Err(invalid());
+            }
+            session_id
+        }
+        None => sessions::create(&mut *tx, user.id, &ClientInfo::from_request(&headers, &addr))
+            .await
+            .map_err(database_error)?,
+    };
+
+    let response = issue_tokens_in(&mut tx, &user, session_id, claims.rem, Some(&jti)).await?;
+    tx.commit().await.map_err(database_error)?;
+
+    Ok(token_response(response))
+}
+
+// Desafío de 2FA si el usuario lo tiene activo (None = se pueden emitir tokens)
+pub(crate) async fn two_factor_challenge_for(
+    pool: &PgPool,
+    user: &User,
+    remember: bool,
+) -> Result<Option<TwoFactorChallengeResponse>, (StatusCode, Json<AuthError>)> {
+    let totp_enabled: bool = sqlx::query_scalar("SELECT totp_enabled FROM users WHERE id = $1")
+        .bind(user.id)
+        .fetch_one(pool)
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(AuthError::new("database_error", "Error de base de datos")),
+            )
+        })?;
+
+    if !totp_enabled {
+        return Ok(None);
     }
 
-    // Generar token JWT
-    let token = generate_token(&user).map_err(|_| {
+    let (challenge_token, claims) = generate_challenge_token(user, remember).map_err(|_| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(AuthError::new("token_error", "Error al generar token")),
         )
     })?;
 
-    // Calcular expiración
-    let expires_at = (Utc::now() + chrono::Duration::hours(24)).timestamp();
+    Ok(Some(TwoFactorChallengeResponse {
+        two_factor_required: true,
+        challenge_token,
+        expires_at: claims.exp as i64,
+    }))
+}
 
-    Ok(Json(AuthResponse {
+// Abrir una sesión nueva (login, registro, OAuth) y emitir sus tokens
+pub(crate) async fn start_session(
+    pool: &PgPool,
+    user: &User,
+    client: &ClientInfo,
+    remember: bool,
+) -> Result<AuthResponse, (StatusCode, Json<AuthError>)> {
+    let session_id = sessions::create(pool, user.id, client).await.map_err(|e| {
+        tracing::error!(error = %e, user_id = user.id, "🚨 Error al crear sesión");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    })?;
+
+    issue_tokens(pool, user, session_id, remember).await
+}
+
+// Generar token de acceso + refresh token de una sesión y persistir este último
+pub(crate) async fn issue_tokens(
+    pool: &PgPool,
+    user: &User,
+    session_id: i32,
+    remember: bool,
+) -> Result<AuthResponse, (StatusCode, Json<AuthError>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!(error = %e, user_id = user.id, "🚨 Error al obtener conexión");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    })?;
+
+    issue_tokens_in(&mut conn, user, session_id, remember, None).await
+}
+
+// Como issue_tokens, sobre la conexión (o transacción) del llamador. `replaces` es el jti
+// del refresh token rotado: queda enlazado al nuevo en replaced_by
+async fn issue_tokens_in(
+    conn: &mut PgConnection,
+    user: &User,
+    session_id: i32,
+    remember: bool,
+    replaces: Option<&str>,
+) -> Result<AuthResponse, (StatusCode, Json<AuthError>)> {
+    let token_error = || {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("token_error", "Error al generar token")),
+        )
+    };
+    let database_error = |e: sqlx::Error| {
+        tracing::error!(error = %e, user_id = user.id, "🚨 Error al guardar refresh token");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    };
+
+    let (token, claims) = generate_token(user, Some(session_id), remember).map_err(|_| token_error())?;
+    let (refresh_token, refresh_claims) = generate_refresh_token(user, Some(session_id), remember).map_err(|_| token_error())?;
+
+    let refresh_expires_at = chrono::DateTime::<Utc>::from_timestamp(refresh_claims.exp as i64, 0)
+        .ok_or_else(token_error)?;
+
+    sqlx::query(
+        "INSERT INTO refresh_tokens (user_id, jti, expires_at, session_id) VALUES ($1, $2, $3, $4)"
+    )
+    .bind(user.id)
+    .bind(&refresh_claims.jti)
+    .bind(refresh_expires_at)
+    .bind(session_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    if let Some(replaced_jti) = replaces {
+        sqlx::query("UPDATE refresh_tokens SET replaced_by = $1 WHERE jti = $2")
+            .bind(&refresh_claims.jti)
+            .bind(replaced_jti)
+            .execute(&mut *conn)
+            .await
+            .map_err(database_error)?;
+    }
+
+    // La sesión recuerda su token de acceso vigente
+    sessions::touch(&mut *conn, session_id, user.id, claims.jti.as_deref())
+        .await
+        .map_err(database_error)?;
+
+    Ok(AuthResponse {
         token,
+        refresh_token,
         user: user.to_public(),
-        expires_at,
-    }))
+        expires_at: claims.exp as i64,
+    })
+}
+
+// GET /.well-known/jwks.json
+// Claves públicas de firma para que otros servicios verifiquen tokens sin el secreto.
+// Solo existen con JWT_ALGORITHM=RS256; con HS256 no hay nada que publicar
+pub async fn jwks() -> Response {
+    match crate::auth::jwt_config().jwks() {
+        Some(jwks) => (
+            [(axum::http::header::CACHE_CONTROL, "public, max-age=300")],
+            Json(jwks),
+        )
+            .into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(AuthError::new("jwks_unavailable", "Los tokens no se firman con claves asimétricas")),
+        )
+            .into_response(),
+    }
+}
+
+// POST /api/v1/auth/introspect
+// Validez y vida restante de un token: firma, tipo, expiración, revocación (logout),
+// token_version del usuario y sesión vigente, con los mismos criterios que auth_middleware.
+// Un token inválido responde 200 con `active: false`. Los servicios
+// internos lo llaman con una API key (X-Api-Key, scope tokens:introspect) y el token en el cuerpo
+pub async fn introspect(
+    State(pool): State<PgPool>,
+    headers: axum::http::HeaderMap,
+    body: Option<Json<IntrospectRequest>>,
+) -> Result<Json<IntrospectResponse>, (StatusCode, Json<AuthError>)> {
+    if let Some(raw_key) = headers.get("x-api-key").and_then(|h| h.to_str().ok()) {
+        let (user, api_key) = api_keys::authenticate(&pool, raw_key.trim())
+            .await
+            .map_err(|_| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(AuthError::new("database_error", "Error de base de datos")),
+                )
+            })?
+            .ok_or_else(|| {
+                (
+                    StatusCode::UNAUTHORIZED,
+                    Json(AuthError::new("invalid_api_key", "API key inválida, vencida o revocada")),
+                )
+            })?;
+
+        if !api_key.scopes.iter().any(|s| s == api_keys::SCOPE_TOKENS_INTROSPECT) {
+            tracing::warn!(
+                event = "api_key_scope_denied",
+                user_id = user.id,
+                required_scope = api_keys::SCOPE_TOKENS_INTROSPECT,
+                "🔑 API key sin el scope requerido"
+            );
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(AuthError::insufficient_scope(api_keys::SCOPE_TOKENS_INTROSPECT)),
+            ));
+        }
+    }
+
+    // Token del cuerpo o, si no viene, del header Authorization (o de la cookie de sesión)
+    let token = match body.and_then(|Json(request)| request.token) {
+        Some(token) => token,
+        None => match crate::auth::cookie::request_token(&headers) {
+            Ok(token) => token.to_string(),
+            // Header presente pero mal formado: token inválido, no request inválido
+            Err(_) if headers.contains_key(axum::http::header::AUTHORIZATION) => {
+                return Ok(Json(IntrospectResponse::inactive()));
+            }
+            Err((_, error)) => return Err((StatusCode::BAD_REQUEST, error)),
+        },
+    };
+
+    let Ok(claims) = crate::auth::jwt::verify_token(&token) else {
+        return Ok(Json(IntrospectResponse::inactive()));
+    };
+
+    // Lista de revocados (solo tokens con jti)
+    let revoked = crate::auth::revocation::is_revoked(&pool, &claims).await.map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    })?;
+
+    if revoked {
+        return Ok(Json(IntrospectResponse::inactive()));
+    }
+
+    let Ok(user_id) = claims.sub.parse::<i32>() else {
+        return Ok(Json(IntrospectResponse::inactive()));
+    };
+
+    // Usuario activo, sin invalidación global posterior al token y con la sesión vigente
+    // (sin actualizar last_seen_at: consultar un token no es actividad de la sesión)
+    let current = sqlx::query_scalar::<_, bool>(
+        "SELECT u.token_version = $2
+                AND ($3::int IS NULL OR EXISTS (
+                    SELECT 1 FROM sessions s
+                    WHERE s.id = $3 AND s.user_id = u.id AND s.revoked_at IS NULL
+                ))
+         FROM users u
+         WHERE u.id = $1 AND u.is_active = true"
+    )
+    .bind(user_id)
+    .bind(claims.ver)
+    .bind(claims.sid)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    })?;
+
+    if current != Some(true) {
+        return Ok(Json(IntrospectResponse::inactive()));
+    }
+
+    Ok(Json(IntrospectResponse::from_claims(&claims)))
 }
 
 // GET /api/v1/auth/me
 pub async fn get_current_user(
     State(pool): State<PgPool>,
+    display_timezone: DisplayTimezone,
     headers: axum::http::HeaderMap,
 ) -> Result<Json<crate::models::user::PublicUser>, (StatusCode, Json<AuthError>)> {
-    // Extraer token del header
-    let auth_header = headers
-        .get("authorization")
-        .and_then(|h| h.to_str().ok())
-        .ok_or_else(|| {
-            (
-                StatusCode::UNAUTHORIZED,
-                Json(AuthError::new("missing_token", "Token de autorización requerido")),
-            )
-        })?;
+    // Extraer token del header (o de la cookie de sesión)
+    let token = crate::auth::cookie::request_token(&headers)?;
 
-    let token = crate::auth::jwt::extract_token_from_header(auth_header).ok_or_else(|| {
+    // Verificar token
+    let claims = crate::auth::jwt::verify_token(token).map_err(|e| {
         (
             StatusCode::UNAUTHORIZED,
-            Json(AuthError::new("invalid_format", "Formato de token inválido")),
+            Json(AuthError::from_jwt_error(&e)),
         )
     })?;
 
-    // Verificar token
-    let claims = crate::auth::jwt::verify_token(token).map_err(|_| {
+    // Rechazar tokens revocados (logout)
+    let revoked = crate::auth::revocation::is_revoked(&pool, &claims).await.map_err(|_| {
         (
-            StatusCode::UNAUTHORIZED,
-            Json(AuthError::invalid_token()),
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
         )
     })?;
 
+    if revoked {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(AuthError::token_revoked()),
+        ));
+    }
+
     // Buscar usuario en base de datos
     let user_id: i32 = claims.sub.parse().map_err(|_| {
         (
@@ -223,7 +734,7 @@ pub async fn get_current_user(
     })?;
 
     let user = sqlx::query_as::<_, User>(
-        "SELECT id, name, email, password_hash, is_admin, is_active, created_at, updated_at 
+        "SELECT id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at 
          FROM users WHERE id = $1 AND is_active = true"
     )
     .bind(user_id)
@@ -242,14 +753,1784 @@ pub async fn get_current_user(
         )
     })?;
 
-    Ok(Json(user.to_public()))
+    // Tokens emitidos antes de una invalidación global (token_version)
+    if crate::auth::jwt::is_token_stale(&claims, &user) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(AuthError::token_revoked()),
+        ));
+    }
+
+    // La sesión del token debe seguir vigente (se puede revocar desde /auth/sessions)
+    if let Some(session_id) = claims.sid {
+        let active = crate::auth::sessions::touch(&pool, session_id, user.id, None)
+            .await
+            .map_err(|_| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(AuthError::new("database_error", "Error de base de datos")),
+                )
+            })?;
+        if !active {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                Json(AuthError::token_revoked()),
+            ));
+        }
+    }
+
+    Ok(Json(user.to_public().with_display(display_timezone)))
+}
+
+// Identidad externa vinculada, tal como se exporta
+#[derive(serde::Serialize, sqlx::FromRow)]
+struct ExportedIdentity {
+    provider: String,
+    subject: String,
+    email: String,
+    #[serde(with = "crate::models::timestamp")]
+    created_at: chrono::DateTime<Utc>,
+}
+
+// Acción de un admin sobre la cuenta (sin datos del admin)
+#[derive(serde::Serialize, sqlx::FromRow)]
+struct ExportedAuditEntry {
+    action: String,
+    #[serde(with = "crate::models::timestamp")]
+    created_at: chrono::DateTime<Utc>,
+}
+
+// Todos los datos asociados al usuario. Cada dominio nuevo con datos personales
+// (publicaciones, ofertas, mensajes) debe agregar su sección acá
+async fn collect_personal_data(pool: &PgPool, user: &User) -> Result<serde_json::Value, sqlx::Error> {
+    let (totp_enabled, updated_at): (bool, chrono::DateTime<Utc>) =
+        sqlx::query_as("SELECT totp_enabled, updated_at FROM users WHERE id = $1")
+            .bind(user.id)
+            .fetch_one(pool)
+            .await?;
+
+    let identities = sqlx::query_as::<_, ExportedIdentity>(
+        "SELECT provider, subject, email, created_at FROM user_identities WHERE user_id = $1 ORDER BY id"
+    )
+    .bind(user.id)
+    .fetch_all(pool)
+    .await?;
+
+    let contact_messages = sqlx::query_as::<_, crate::models::contact::ContactMessage>(
+        "SELECT id, name, email, subject, message, ip_address, status, assigned_to, handled_by, handled_at, created_at
+         FROM contact_messages WHERE email = $1 ORDER BY created_at"
+    )
+    .bind(&user.email)
+    .fetch_all(pool)
+    .await?;
+
+    let account_actions = sqlx::query_as::<_, ExportedAuditEntry>(
+        "SELECT action, created_at FROM admin_audit_log WHERE target_user_id = $1 ORDER BY created_at"
+    )
+    .bind(user.id)
+    .fetch_all(pool)
+    .await?;
+
+    let account_events = sqlx::query_as::<_, crate::events::DomainEvent>(
+        "SELECT sequence, event_type, aggregate_type, aggregate_id, payload, occurred_at
+         FROM domain_events WHERE aggregate_type = $1 AND aggregate_id = $2 ORDER BY sequence"
+    )
+    .bind(crate::events::AGGREGATE_USER)
+    .bind(i64::from(user.id))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(serde_json::json!({
+        "exported_at": crate::models::timestamp::now(),
+        "profile": {
+            "user": user.to_public(),
+            "updated_at": crate::models::timestamp::format(&updated_at),
+            "two_factor_enabled": totp_enabled,
+            "has_password": user.password_hash.is_some()
+        },
+        "identities": identities,
+        "sessions": sessions::list(pool, user.id).await?,
+        "devices": crate::auth::devices::list(pool, user.id).await?,
+        "api_keys": api_keys::list_for_user(pool, user.id).await?,
+        "contact_messages": contact_messages,
+        "account_actions": account_actions,
+        "account_events": account_events
+    }))
+}
+
+// GET /api/v1/auth/me/export
+// Copia descargable de los datos personales del usuario autenticado
+pub async fn export_personal_data(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request_id: Option<Extension<RequestId>>,
+    headers: axum::http::HeaderMap,
+    auth_user: AuthUser,
+) -> Result<Response, (StatusCode, Json<AuthError>)> {
+    let user = &auth_user.user;
+    let export = collect_personal_data(&pool, user).await.map_err(|e| {
+        tracing::error!(error = %e, user_id = user.id, "🚨 Error al exportar datos personales");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    })?;
+
+    let request_id = request_id.map(|Extension(id)| id.0).unwrap_or_default();
+    Logger::log_auth_event(
+        "personal_data_export",
+        Some(user.id),
+        Some(&user.email),
+        Some(&get_client_ip(&headers, &addr)),
+        true,
+        &request_id,
+    );
+
+    let filename = format!("venta-libre-datos-{}-{}.json", user.id, Utc::now().format("%Y%m%d"));
+    Ok((
+        [
+            (axum::http::header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+            (axum::http::header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+        Json(export),
+    )
+        .into_response())
 }
 
 // POST /api/v1/auth/logout
-pub async fn logout() -> Result<Json<serde_json::Value>, StatusCode> {
-    // En JWT no hay logout real del lado del servidor
-    // El frontend debe eliminar el token
-    Ok(Json(serde_json::json!({
+pub async fn logout(
+    State(pool): State<PgPool>,
+    headers: axum::http::HeaderMap,
+) -> Result<Response, (StatusCode, Json<AuthError>)> {
+    let token = crate::auth::cookie::request_token(&headers)?;
+
+    let claims = crate::auth::jwt::verify_token(token).map_err(|e| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(AuthError::from_jwt_error(&e)),
+        )
+    })?;
+
+    let database_error = |e: sqlx::Error| {
+        tracing::error!(error = %e, user_id = %claims.sub, "🚨 Error al revocar token");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    };
+
+    // Revocar el token hasta su expiración y, si pertenece a una sesión, cerrarla junto con
+    // sus refresh tokens (todo o nada); el frontend igualmente debe eliminar el token
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    crate::auth::revocation::revoke_token(&mut *tx, &claims).await.map_err(database_error)?;
+    if let Some(session_id) = claims.sid {
+        let user_id: i32 = claims.sub.parse().unwrap_or_default();
+        sessions::revoke(&mut *tx, user_id, session_id).await.map_err(database_error)?;
+        sessions::revoke_refresh_tokens(&mut *tx, user_id, session_id).await.map_err(database_error)?;
+    }
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(event = "logout", user_id = %claims.sub, "👋 Token revocado por logout");
+
+    let mut response = Json(serde_json::json!({
         "message": "Sesión cerrada exitosamente"
-    })))
-}
\ No newline at end of file
+    }))
+    .into_response();
+    crate::auth::cookie::clear_token_cookie(&mut response);
+
+    Ok(response)
+}
+
+// Duración de los tokens de restablecimiento (PASSWORD_RESET_TTL_MINUTES, por defecto 60)
+fn password_reset_ttl() -> chrono::Duration {
+    let minutes = std::env::var("PASSWORD_RESET_TTL_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60);
+    chrono::Duration::minutes(minutes)
+}
+
+// Hash del token de restablecimiento: en BD nunca se guarda el token en claro
+fn hash_reset_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// POST /api/v1/auth/forgot-password
+pub async fn forgot_password(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request_id: Option<Extension<RequestId>>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<ForgotPasswordRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<AuthError>)> {
+    let email = request.email.trim().to_lowercase();
+    let client_ip = get_client_ip(&headers, &addr);
+    let request_id = request_id.map(|Extension(id)| id.0).unwrap_or_default();
+
+    let user_id: Option<i32> = sqlx::query_scalar(
+        "SELECT id FROM users WHERE email = $1 AND is_active = true"
+    )
+    .bind(&email)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    })?;
+
+    if let Some(user_id) = user_id {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        let expires_at = Utc::now() + password_reset_ttl();
+
+        // Un solo token vigente por usuario
+        let database_error = |e: sqlx::Error| {
+            tracing::error!(error = %e, user_id = user_id, "🚨 Error al crear token de restablecimiento");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(AuthError::new("database_error", "Error de base de datos")),
+            )
+        };
+
+        let mut tx = pool.begin().await.map_err(database_error)?;
+
+        sqlx::query("DELETE FROM password_resets WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(database_error)?;
+
+        sqlx::query("INSERT INTO password_resets (user_id, token_hash, expires_at) VALUES ($1, $2, $3)")
+            .bind(user_id)
+            .bind(hash_reset_token(&token))
+            .bind(expires_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(database_error)?;
+
+        tx.commit().await.map_err(database_error)?;
+
+        // Aún no hay envío de emails: en desarrollo el token queda en los logs
+        if std::env::var("ENVIRONMENT").unwrap_or_else(|_| "development".to_string()) == "development" {
+            tracing::info!(user_id = user_id, reset_token = %token, "🔑 Token de restablecimiento (solo desarrollo)");
+        }
+    }
+
+    Logger::log_auth_event(
+        "password_reset_requested",
+        user_id,
+        Some(&email),
+        Some(&client_ip),
+        user_id.is_some(),
+        &request_id,
+    );
+
+    // Misma respuesta exista o no el email (evita enumeración de usuarios)
+    Ok(Json(serde_json::json!({
+        "message": "Si el email está registrado, recibirás instrucciones para restablecer tu contraseña"
+    })))
+}
+
+// POST /api/v1/auth/reset-password
+pub async fn reset_password(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request_id: Option<Extension<RequestId>>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<ResetPasswordRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<AuthError>)> {
+    let client_ip = get_client_ip(&headers, &addr);
+    let request_id = request_id.map(|Extension(id)| id.0).unwrap_or_default();
+
+    // Email del dueño del token (sin consumirlo) para la regla de la política
+    let owner_email: Option<String> = sqlx::query_scalar(
+        "SELECT u.email FROM password_resets r JOIN users u ON u.id = r.user_id WHERE r.token_hash = $1"
+    )
+    .bind(hash_reset_token(request.token.trim()))
+    .fetch_optional(&pool)
+    .await
+    .map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    })?;
+
+    if let Err(violations) = crate::validation::password_policy().check(&request.new_password, owner_email.as_deref()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(AuthError::weak_password(violations)),
+        ));
+    }
+
+    let invalid = || {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(AuthError::new("invalid_reset_token", "Token de restablecimiento inválido o expirado")),
+        )
+    };
+
+    let database_error = |_: sqlx::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    };
+
+    let password_hash = hash_password(&request.new_password).map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("hash_error", "Error al procesar contraseña")),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+
+    // Borrar la fila al consumirla garantiza un solo uso, incluso con requests concurrentes
+    let reset: Option<(i32, chrono::DateTime<Utc>)> = sqlx::query_as(
+        "DELETE FROM password_resets WHERE token_hash = $1 RETURNING user_id, expires_at"
+    )
+    .bind(hash_reset_token(request.token.trim()))
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    let user_id = match reset {
+        Some((user_id, expires_at)) if expires_at > Utc::now() => user_id,
+        Some((user_id, _)) => {
+            // Token expirado: se confirma el borrado y se rechaza
+            tx.commit().await.map_err(database_error)?;
+            Logger::log_auth_event("password_reset", Some(user_id), None, Some(&client_ip), false, &request_id);
+            return Err(invalid());
+        }
+        None => {
+            Logger::log_auth_event("password_reset", None, None, Some(&client_ip), false, &request_id);
+            return Err(invalid());
+        }
+    };
+
+    let updated = sqlx::query("UPDATE users SET password_hash = $1, token_version = token_version + 1 WHERE id = $2 AND is_active = true")
+        .bind(password_hash)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+    // Cuenta desactivada o dada de baja desde que se pidió el token: se consume sin aplicarlo
+    if updated.rows_affected() == 0 {
+        tx.commit().await.map_err(database_error)?;
+        Logger::log_auth_event("password_reset", Some(user_id), None, Some(&client_ip), false, &request_id);
+        return Err(invalid());
+    }
+
+    // Cerrar las sesiones existentes: los refresh tokens anteriores dejan de servir
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+    sessions::revoke_all(&mut *tx, user_id, None)
+        .await
+        .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    Logger::log_auth_event("password_reset", Some(user_id), None, Some(&client_ip), true, &request_id);
+
+    Ok(Json(serde_json::json!({
+        "message": "Contraseña restablecida exitosamente"
+    })))
+}
+
+// Acceso sin contraseña desactivado (falta MAGIC_LINK_SECRET)
+fn magic_link_disabled() -> (StatusCode, Json<AuthError>) {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(AuthError::new("magic_link_disabled", "El acceso por enlace no está disponible")),
+    )
+}
+
+// POST /api/v1/auth/magic-link
+// El límite por IP lo aplica el middleware de auth; acá se agrega uno por email
+pub async fn request_magic_link(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request_id: Option<Extension<RequestId>>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<MagicLinkRequest>,
+) -> Result<Response, (StatusCode, Json<AuthError>)> {
+    if !magic_link::is_enabled() {
+        return Err(magic_link_disabled());
+    }
+
+    let email = request.email.trim().to_lowercase();
+    let client_ip = get_client_ip(&headers, &addr);
+    let request_id = request_id.map(|Extension(id)| id.0).unwrap_or_default();
+
+    if let Err(retry_after) = rate_limiter().check("magic-link-email", &email) {
+        return Ok(rate_limited_response("magic-link-email", &client_ip, retry_after));
+    }
+
+    let user_id: Option<i32> = sqlx::query_scalar(
+        "SELECT id FROM users WHERE email = $1 AND is_active = true"
+    )
+    .bind(&email)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    })?;
+
+    if let Some(user_id) = user_id {
+        let link = magic_link::create(&pool, user_id).await.map_err(|e| {
+            tracing::error!(error = %e, user_id = user_id, "🚨 Error al crear enlace de acceso");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(AuthError::new("database_error", "Error de base de datos")),
+            )
+        })?;
+
+        // Aún no hay envío de emails: en desarrollo el enlace queda en los logs
+        if let Some((token, _)) = link {
+            if std::env::var("ENVIRONMENT").unwrap_or_else(|_| "development".to_string()) == "development" {
+                tracing::info!(user_id = user_id, magic_link = %magic_link::link_url(&token), "🔑 Enlace de acceso (solo desarrollo)");
+            }
+        }
+    }
+
+    Logger::log_auth_event(
+        "magic_link_requested",
+        user_id,
+        Some(&email),
+        Some(&client_ip),
+        user_id.is_some(),
+        &request_id,
+    );
+
+    // Misma respuesta exista o no el email (evita enumeración de usuarios)
+    Ok(Json(serde_json::json!({
+        "message": "Si el email está registrado, recibirás un enlace para iniciar sesión"
+    }))
+    .into_response())
+}
+
+// POST /api/v1/auth/magic-link/verify
+pub async fn verify_magic_link(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request_id: Option<Extension<RequestId>>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<MagicLinkVerifyRequest>,
+) -> Result<Response, (StatusCode, Json<AuthError>)> {
+    if !magic_link::is_enabled() {
+        return Err(magic_link_disabled());
+    }
+
+    let client_ip = get_client_ip(&headers, &addr);
+    let request_id = request_id.map(|Extension(id)| id.0).unwrap_or_default();
+    let database_error = |e: sqlx::Error| {
+        tracing::error!(error = %e, "🚨 Error al canjear enlace de acceso");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    };
+
+    let user_id = match magic_link::redeem(&pool, request.token.trim()).await.map_err(database_error)? {
+        Redemption::Valid(user_id) => user_id,
+        Redemption::AlreadyUsed => {
+            Logger::log_auth_event("magic_link_reused", None, None, Some(&client_ip), false, &request_id);
+            return Err((StatusCode::UNAUTHORIZED, Json(AuthError::magic_link_used())));
+        }
+        Redemption::Expired | Redemption::Unknown => {
+            Logger::log_auth_event("magic_link_login", None, None, Some(&client_ip), false, &request_id);
+            return Err((StatusCode::UNAUTHORIZED, Json(AuthError::invalid_magic_link())));
+        }
+    };
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at
+         FROM users WHERE id = $1 AND is_active = true"
+    )
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(AuthError::invalid_magic_link())))?;
+
+    Logger::log_auth_event("magic_link_login", Some(user.id), Some(&user.email), Some(&client_ip), true, &request_id);
+
+    // Mismo flujo que el login con contraseña: 2FA si está activo, si no dispositivo y tokens
+    match two_factor_challenge_for(&pool, &user, false).await? {
+        Some(challenge) => Ok(Json(challenge).into_response()),
+        None => {
+            let user_agent = headers.get(axum::http::header::USER_AGENT).and_then(|h| h.to_str().ok());
+            crate::auth::devices::track_login(&pool, &user, user_agent, &client_ip).await;
+            Ok(token_response(
+                start_session(&pool, &user, &ClientInfo::from_request(&headers, &addr), false).await?,
+            ))
+        }
+    }
+}
+
+// POST /api/v1/auth/change-password
+pub async fn change_password(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request_id: Option<Extension<RequestId>>,
+    headers: axum::http::HeaderMap,
+    auth_user: AuthUser,
+    Json(request): Json<ChangePasswordRequest>,
+) -> Result<Response, (StatusCode, Json<AuthError>)> {
+    let client_ip = get_client_ip(&headers, &addr);
+    let request_id = request_id.map(|Extension(id)| id.0).unwrap_or_default();
+    let user = &auth_user.user;
+    let attempts = login_attempts();
+
+    // Operación sensible: no se acepta un token de "recordarme"
+    if auth_user.claims.rem {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(AuthError::fresh_login_required()),
+        ));
+    }
+
+    // Los fallos cuentan para el mismo bloqueo que el login (evita adivinar la actual)
+    if let Some(remaining) = attempts.locked_for(&user.email) {
+        Logger::log_auth_event("password_change_blocked", Some(user.id), Some(&user.email), Some(&client_ip), false, &request_id);
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(AuthError::account_locked(remaining.as_secs().div_ceil(60))),
+        ));
+    }
+
+    // Cuentas creadas con Google no tienen contraseña: deben usar el restablecimiento
+    let Some(current_hash) = user.password_hash.as_deref() else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(AuthError::new("no_password", "La cuenta no tiene contraseña; usa el restablecimiento")),
+        ));
+    };
+
+    let current_valid = check_password(&request.current_password, current_hash, Some(user.id));
+
+    if !current_valid {
+        if attempts.record_failure(&user.email) {
+            Logger::log_auth_event("account_locked", Some(user.id), Some(&user.email), Some(&client_ip), false, &request_id);
+        }
+        Logger::log_auth_event("password_change", Some(user.id), Some(&user.email), Some(&client_ip), false, &request_id);
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(AuthError::new("invalid_current_password", "La contraseña actual es incorrecta")),
+        ));
+    }
+
+    if let Err(violations) = crate::validation::password_policy().check(&request.new_password, Some(&user.email)) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(AuthError::weak_password(violations)),
+        ));
+    }
+
+    let password_hash = hash_password(&request.new_password).map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("hash_error", "Error al procesar contraseña")),
+        )
+    })?;
+
+    let database_error = |e: sqlx::Error| {
+        tracing::error!(error = %e, user_id = user.id, "🚨 Error al cambiar contraseña");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    };
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+
+    // Subir token_version invalida todos los tokens de acceso anteriores;
+    // la sesión sigue con los que se emiten abajo
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET password_hash = $1, updated_at = NOW(), token_version = token_version + 1
+         WHERE id = $2 AND is_active = true
+         RETURNING id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at"
+    )
+    .bind(password_hash)
+    .bind(user.id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    // Cerrar las demás sesiones: los refresh tokens anteriores dejan de servir
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+    sessions::revoke_all(&mut *tx, user.id, auth_user.claims.sid)
+        .await
+        .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    attempts.reset(&user.email);
+    Logger::log_auth_event("password_change", Some(user.id), Some(&user.email), Some(&client_ip), true, &request_id);
+
+    let response = match auth_user.claims.sid {
+        Some(session_id) => issue_tokens(&pool, &user, session_id, false).await?,
+        None => start_session(&pool, &user, &ClientInfo::from_request(&headers, &addr), false).await?,
+    };
+
+    Ok(token_response(response))
+}
+
+// DELETE /api/v1/auth/me (auth_middleware)
+// Baja de la cuenta por el propio usuario. Borrarla rompería las referencias de otras tablas,
+// así que se desactiva (token_version invalida todos los tokens) y se anonimiza al terminar
+// el plazo de restauración (ver auth::account_deletion)
+pub async fn delete_account(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request_id: Option<Extension<RequestId>>,
+    headers: axum::http::HeaderMap,
+    auth_user: AuthUser,
+    Json(request): Json<DeleteAccountRequest>,
+) -> Result<Response, (StatusCode, Json<AuthError>)> {
+    let client_ip = get_client_ip(&headers, &addr);
+    let request_id = request_id.map(|Extension(id)| id.0).unwrap_or_default();
+    let user = &auth_user.user;
+    let attempts = login_attempts();
+
+    // Operación sensible: no se acepta un token de "recordarme"
+    if auth_user.claims.rem {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(AuthError::fresh_login_required()),
+        ));
+    }
+
+    // Un admin suplantando al usuario no puede darle de baja
+    if auth_user.is_impersonated() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(AuthError::impersonation_not_allowed()),
+        ));
+    }
+
+    // Los fallos cuentan para el mismo bloqueo que el login (evita adivinar la contraseña)
+    if let Some(remaining) = attempts.locked_for(&user.email) {
+        Logger::log_auth_event("account_delete_blocked", Some(user.id), Some(&user.email), Some(&client_ip), false, &request_id);
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(AuthError::account_locked(remaining.as_secs().div_ceil(60))),
+        ));
+    }
+
+    // Cuentas creadas con Google no tienen contraseña: deben definir una con el restablecimiento
+    let Some(current_hash) = user.password_hash.as_deref() else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(AuthError::new("no_password", "La cuenta no tiene contraseña; usa el restablecimiento")),
+        ));
+    };
+
+    if !check_password(&request.current_password, current_hash, Some(user.id)) {
+        if attempts.record_failure(&user.email) {
+            Logger::log_auth_event("account_locked", Some(user.id), Some(&user.email), Some(&client_ip), false, &request_id);
+        }
+        Logger::log_auth_event("account_delete", Some(user.id), Some(&user.email), Some(&client_ip), false, &request_id);
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(AuthError::new("invalid_current_password", "La contraseña actual es incorrecta")),
+        ));
+    }
+
+    let database_error = |e: sqlx::Error| {
+        tracing::error!(error = %e, user_id = user.id, "🚨 Error al dar de baja la cuenta");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    };
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    account_deletion::soft_delete(&mut tx, user.id)
+        .await
+        .map_err(database_error)?;
+    tx.commit().await.map_err(database_error)?;
+
+    attempts.reset(&user.email);
+    Logger::log_auth_event("account_delete", Some(user.id), None, Some(&client_ip), true, &request_id);
+
+    let mut response = StatusCode::NO_CONTENT.into_response();
+    crate::auth::cookie::clear_token_cookie(&mut response);
+    Ok(response)
+}
+
+// POST /api/v1/auth/logout-all (auth_middleware)
+// Invalida todos los tokens y sesiones del usuario; el que llama recibe tokens nuevos
+pub async fn logout_all(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request_id: Option<Extension<RequestId>>,
+    headers: axum::http::HeaderMap,
+    auth_user: AuthUser,
+) -> Result<Response, (StatusCode, Json<AuthError>)> {
+    let client_ip = get_client_ip(&headers, &addr);
+    let request_id = request_id.map(|Extension(id)| id.0).unwrap_or_default();
+
+    let database_error = |e: sqlx::Error| {
+        tracing::error!(error = %e, user_id = auth_user.user.id, "🚨 Error al cerrar todas las sesiones");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    };
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+
+    // Subir token_version invalida todos los tokens de acceso anteriores
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET token_version = token_version + 1
+         WHERE id = $1 AND is_active = true
+         RETURNING id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at"
+    )
+    .bind(auth_user.user.id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+    let revoked_sessions = sessions::revoke_all(&mut *tx, user.id, auth_user.claims.sid)
+        .await
+        .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    Logger::log_auth_event("logout_all", Some(user.id), Some(&user.email), Some(&client_ip), true, &request_id);
+    tracing::info!(
+        event = "logout_all",
+        user_id = user.id,
+        revoked_sessions = revoked_sessions,
+        token_version = user.token_version,
+        "👋 Todas las sesiones del usuario cerradas"
+    );
+
+    let response = match auth_user.claims.sid {
+        Some(session_id) => issue_tokens(&pool, &user, session_id, auth_user.claims.rem).await?,
+        None => start_session(&pool, &user, &ClientInfo::from_request(&headers, &addr), false).await?,
+    };
+
+    Ok(token_response(response))
+}
+
+// POST /api/v1/auth/admin-token (auth_middleware + require_role(Role::Admin))
+// Token de acceso de larga duración, sin refresh; queda como una sesión revocable
+pub async fn issue_admin_token(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request_id: Option<Extension<RequestId>>,
+    headers: axum::http::HeaderMap,
+    auth_user: AuthUser,
+    request: Option<Json<AdminTokenRequest>>,
+) -> Result<Json<AuthResponse>, (StatusCode, Json<AuthError>)> {
+    let user = &auth_user.user;
+
+    // require_role ya lo garantiza; se repite para no depender del InvalidToken
+    // que devuelve generate_admin_token con usuarios no admin
+    if !user.is_admin() {
+        return Err((StatusCode::FORBIDDEN, Json(AuthError::forbidden())));
+    }
+
+    // Operación sensible: no se acepta un token de "recordarme"
+    if auth_user.claims.rem {
+        return Err((StatusCode::FORBIDDEN, Json(AuthError::fresh_login_required())));
+    }
+
+    let request = request.map(|Json(r)| r).unwrap_or_default();
+    let ttl_hours = request.ttl_hours.unwrap_or(ADMIN_TOKEN_MAX_TTL_HOURS);
+    if ttl_hours < 1 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(AuthError::new("invalid_ttl", "ttl_hours debe ser al menos 1")),
+        ));
+    }
+    let ttl_hours = ttl_hours.min(ADMIN_TOKEN_MAX_TTL_HOURS);
+
+    let client = ClientInfo::from_request(&headers, &addr);
+    let request_id = request_id.map(|Extension(id)| id.0).unwrap_or_default();
+
+    let database_error = |e: sqlx::Error| {
+        tracing::error!(error = %e, user_id = user.id, "🚨 Error al emitir token de admin");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("database_error", "Error de base de datos")),
+        )
+    };
+
+    let session_id = sessions::create(&pool, user.id, &client)
+        .await
+        .map_err(database_error)?;
+
+    let (token, claims) = generate_admin_token(user, ttl_hours, Some(session_id)).map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(AuthError::new("token_error", "Error al generar token")),
+        )
+    })?;
+
+    sessions::touch(&pool, session_id, user.id, claims.jti.as_deref())
+        .await
+        .map_err(database_error)?;
+
+    crate::audit::record(
+        &pool,
+        user.id,
+        "admin_token_issue",
+        Some(user.id),
+        serde_json::json!({
+            "ip": client.ip,
+            "ttl_hours": ttl_hours,
+            "session_id": session_id,
+        }),
+    )
+    .await
+    .map_err(database_error)?;
+
+    Logger::log_auth_event("admin_token_issued", Some(user.id), Some(&user.email), Some(&client.ip), true, &request_id);
+
+    Ok(Json(AuthResponse {
+        token,
+        refresh_token: String::new(),
+        user: user.to_public(),
+        expires_at: claims.exp as i64,
+    }))
+}
+
+fn sessions_database_error(e: sqlx::Error) -> (StatusCode, Json<AuthError>) {
+    tracing::error!(error = %e, "🚨 Error de BD en sesiones");
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(AuthError::new("database_error", "Error de base de datos")),
+    )
+}
+
+// GET /api/v1/auth/sessions
+pub async fn list_sessions(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<AuthError>)> {
+    let mut sessions = sessions::list(&pool, auth_user.user.id)
+        .await
+        .map_err(sessions_database_error)?;
+
+    for session in &mut sessions {
+        session.current = auth_user.claims.sid == Some(session.id);
+    }
+
+    Ok(Json(serde_json::json!({
+        "sessions": sessions,
+        "total": sessions.len()
+    })))
+}
+
+// DELETE /api/v1/auth/sessions/:id
+pub async fn revoke_session(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(session_id): Path<i32>,
+) -> Result<StatusCode, (StatusCode, Json<AuthError>)> {
+    let revoked = sessions::revoke(&pool, auth_user.user.id, session_id)
+        .await
+        .map_err(sessions_database_error)?;
+
+    if !revoked {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(AuthError::new("session_not_found", "Sesión no encontrada")),
+        ));
+    }
+
+    tracing::info!(event = "session_revoked", user_id = auth_user.user.id, session_id = session_id, "🔒 Sesión revocada");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn devices_database_error(e: sqlx::Error) -> (StatusCode, Json<AuthError>) {
+    tracing::error!(error = %e, "🚨 Error de BD en dispositivos");
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(AuthError::new("database_error", "Error de base de datos")),
+    )
+}
+
+// GET /api/v1/auth/devices
+pub async fn list_devices(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<AuthError>)> {
+    let devices = crate::auth::devices::list(&pool, auth_user.user.id)
+        .await
+        .map_err(devices_database_error)?;
+
+    Ok(Json(serde_json::json!({
+        "devices": devices,
+        "total": devices.len()
+    })))
+}
+
+// DELETE /api/v1/auth/devices/:id
+pub async fn revoke_device(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Path(device_id): Path<i32>,
+) -> Result<StatusCode, (StatusCode, Json<AuthError>)> {
+    let removed = crate::auth::devices::revoke(&pool, auth_user.user.id, device_id)
+        .await
+        .map_err(devices_database_error)?;
+
+    if !removed {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(AuthError::new("device_not_found", "Dispositivo no encontrado")),
+        ));
+    }
+
+    tracing::info!(event = "device_revoked", user_id = auth_user.user.id, device_id = device_id, "📱 Dispositivo olvidado");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+
+fn totp_database_error(e: sqlx::Error) -> (StatusCode, Json<AuthError>) {
+    tracing::error!(error = %e, "🚨 Error de base de datos en 2FA");
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(AuthError::new("database_error", "Error de base de datos")),
+    )
+}
+
+// Secreto TOTP del usuario (descifrado) y último paso usado
+async fn load_totp_secret(
+    pool: &PgPool,
+    user_id: i32,
+) -> Result<Option<(Vec<u8>, Option<i64>)>, (StatusCode, Json<AuthError>)> {
+    let row: Option<(Option<String>, Option<i64>)> = sqlx::query_as(
+        "SELECT totp_secret, totp_last_step FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(totp_database_error)?;
+
+    let Some((Some(stored), last_step)) = row else {
+        return Ok(None);
+    };
+
+    let secret = crate::crypto::decrypt(&stored)
+        .ok()
+        .and_then(|encoded| totp::base32_decode(&encoded))
+        .ok_or_else(|| {
+            tracing::error!(user_id = user_id, "🚨 No se pudo descifrar el secreto TOTP");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(AuthError::new("totp_error", "Error al procesar 2FA")),
+            )
+        })?;
+
+    Ok(Some((secret, last_step)))
+}
+
+// POST /api/v1/auth/2fa/enable
+pub async fn enable_two_factor(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<AuthError>)> {
+    let enabled: bool = sqlx::query_scalar("SELECT totp_enabled FROM users WHERE id = $1")
+        .bind(auth_user.user.id)
+        .fetch_one(&pool)
+        .await
+        .map_err(totp_database_error)?;
+
+    if enabled {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(AuthError::new("totp_already_enabled", "La autenticación en dos pasos ya está activa")),
+        ));
+    }
+
+    // Nuevo secreto pendiente de confirmación (reemplaza uno anterior sin confirmar)
+    let secret = totp::base32_encode(&totp::generate_secret());
+    let stored = crate::crypto::encrypt(&secret).map_err(|e| {
+        tracing::error!(error = %e, "🚨 Error al cifrar secreto TOTP");
+        match e {
+            // Configuración del servidor, no un fallo del request: se puede reintentar luego
+            crate::crypto::secrets::CryptoError::MissingKey => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(AuthError::new(
+                    "encryption_unavailable",
+                    "La autenticación en dos pasos no está disponible: falta configurar el cifrado de secretos",
+                )),
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(AuthError::new("totp_error", "Error al procesar 2FA")),
+            ),
+        }
+    })?;
+
+    sqlx::query("UPDATE users SET totp_secret = $1, totp_last_step = NULL WHERE id = $2")
+        .bind(stored)
+        .bind(auth_user.user.id)
+        .execute(&pool)
+        .await
+        .map_err(totp_database_error)?;
+
+    Ok(Json(serde_json::json!({
+        "otpauth_uri": totp::otpauth_uri(&secret, &auth_user.user.email),
+        "secret": secret,
+        "message": "Escanea el código y confirma con /api/v1/auth/2fa/verify"
+    })))
+}
+
+// POST /api/v1/auth/2fa/verify
+pub async fn verify_two_factor(
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    Json(request): Json<TotpCodeRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<AuthError>)> {
+    let (secret, last_step) = load_totp_secret(&pool, auth_user.user.id)
+        .await?
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(AuthError::new("totp_not_enrolled", "Primero activa la autenticación en dos pasos")),
+            )
+        })?;
+
+    let step = totp::verify_code(&secret, &request.code, last_step).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(AuthError::invalid_totp_code()),
+        )
+    })?;
+
+    sqlx::query("UPDATE users SET totp_enabled = true, totp_last_step = $1 WHERE id = $2")
+        .bind(step)
+        .bind(auth_user.user.id)
+        .execute(&pool)
+        .await
+        .map_err(totp_database_error)?;
+
+    tracing::info!(event = "totp_enabled", user_id = auth_user.user.id, "🔐 2FA activado");
+
+    Ok(Json(serde_json::json!({
+        "enabled": true,
+        "message": "Autenticación en dos pasos activada"
+    })))
+}
+
+// POST /api/v1/auth/2fa/challenge
+pub async fn two_factor_challenge(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request_id: Option<Extension<RequestId>>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<TwoFactorChallengeRequest>,
+) -> Result<Response, (StatusCode, Json<AuthError>)> {
+    let client_ip = get_client_ip(&headers, &addr);
+    let request_id = request_id.map(|Extension(id)| id.0).unwrap_or_default();
+
+    let claims = verify_challenge_token(&request.challenge_token).map_err(|_| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(AuthError::invalid_token()),
+        )
+    })?;
+    let user_id: i32 = claims.sub.parse().map_err(|_| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(AuthError::invalid_token()),
+        )
+    })?;
+
+    // Los códigos fallidos cuentan para el mismo bloqueo que las contraseñas
+    let attempts = login_attempts();
+    if let Some(remaining) = attempts.locked_for(&claims.email) {
+        Logger::log_auth_event("login_blocked", Some(user_id), Some(&claims.email), Some(&client_ip), false, &request_id);
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(AuthError::account_locked(remaining.as_secs().div_ceil(60))),
+        ));
+    }
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at
+         FROM users WHERE id = $1 AND is_active = true AND totp_enabled = true"
+    )
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(totp_database_error)?
+    .filter(|user| !crate::auth::jwt::is_token_stale(&claims, user))
+    .ok_or_else(|| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(AuthError::invalid_token()),
+        )
+    })?;
+
+    let (secret, last_step) = load_totp_secret(&pool, user.id).await?.ok_or_else(|| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(AuthError::invalid_token()),
+        )
+    })?;
+
+    let Some(step) = totp::verify_code(&secret, &request.code, last_step) else {
+        if attempts.record_failure(&claims.email) {
+            Logger::log_auth_event("account_locked", Some(user.id), Some(&claims.email), Some(&client_ip), false, &request_id);
+        }
+        Logger::log_auth_event("two_factor", Some(user.id), Some(&claims.email), Some(&client_ip), false, &request_id);
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(AuthError::invalid_totp_code()),
+        ));
+    };
+
+    // Registrar el paso usado: el mismo código no sirve dos veces
+    let updated = sqlx::query(
+        "UPDATE users SET totp_last_step = $1
+         WHERE id = $2 AND (totp_last_step IS NULL OR totp_last_step < $1)"
+    )
+    .bind(step)
+    .bind(user.id)
+    .execute(&pool)
+    .await
+    .map_err(totp_database_error)?;
+
+    if updated.rows_affected() == 0 {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(AuthError::invalid_totp_code()),
+        ));
+    }
+
+    attempts.reset(&claims.email);
+    Logger::log_auth_event("two_factor", Some(user.id), Some(&claims.email), Some(&client_ip), true, &request_id);
+
+    // El dispositivo cuenta como conocido solo después del segundo factor
+    let user_agent = headers.get(axum::http::header::USER_AGENT).and_then(|h| h.to_str().ok());
+    crate::auth::devices::track_login(&pool, &user, user_agent, &client_ip).await;
+
+    let response = start_session(&pool, &user, &ClientInfo::from_request(&headers, &addr), claims.rem).await?;
+
+    Ok(token_response(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header, Method, StatusCode};
+    use serde_json::json;
+    use crate::auth::password::hash_password;
+    use crate::models::user::Role;
+    use crate::test_support::{unique_email, TestApp, PASSWORD};
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn bcrypt_and_argon2_users_can_log_in() {
+        let app = TestApp::spawn().await;
+        let bcrypt_email = unique_email("bcrypt");
+        let argon2_email = unique_email("argon2");
+        app.insert_user(&bcrypt_email, &bcrypt::hash(PASSWORD, 4).unwrap(), Role::User).await;
+        app.insert_user(&argon2_email, &hash_password(PASSWORD).unwrap(), Role::User).await;
+
+        for email in [&bcrypt_email, &argon2_email] {
+            let (status, body) = app.login(email, PASSWORD).await;
+            assert_eq!(status, StatusCode::OK, "{}: {}", email, body);
+            assert!(body["token"].is_string());
+
+            let (status, body) = app.login(email, "Otra-Clave-2026").await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(body["error"], "invalid_credentials");
+        }
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn legacy_bcrypt_hash_is_upgraded_on_login() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("legado");
+        let id = app.insert_user(&email, &bcrypt::hash(PASSWORD, 4).unwrap(), Role::User).await;
+
+        let (status, _) = app.login(&email, PASSWORD).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let stored: String = sqlx::query_scalar("SELECT password_hash FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+        assert!(stored.starts_with("$argon2id$"), "{}", stored);
+
+        // El hash migrado sigue aceptando la misma contraseña
+        let (status, _) = app.login(&email, PASSWORD).await;
+        assert_eq!(status, StatusCode::OK);
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn corrupted_hash_counts_as_invalid_credentials() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("corrupto");
+        app.insert_user(&email, "$2b$12$truncado", Role::User).await;
+
+        let (status, body) = app.login(&email, PASSWORD).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "invalid_credentials");
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn change_password_checks_current_and_policy() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("cambio");
+        let (_, token) = app.register(&email, None).await;
+        let change = |current: &str, new: &str| json!({ "current_password": current, "new_password": new });
+
+        let (status, _, body) = app
+            .request(Method::POST, "/api/v1/auth/change-password", Some(&token), Some(change("Otra-Clave-2026", "Monte-Verde-2027")))
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "invalid_current_password");
+
+        let (status, _, body) = app
+            .request(Method::POST, "/api/v1/auth/change-password", Some(&token), Some(change(PASSWORD, "corta")))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "weak_password");
+        assert!(!body["violations"].as_array().unwrap().is_empty());
+
+        let (status, _, body) = app
+            .request(Method::POST, "/api/v1/auth/change-password", Some(&token), Some(change(PASSWORD, "Monte-Verde-2027")))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let new_token = body["token"].as_str().unwrap();
+
+        // El token anterior queda invalidado; el emitido con el cambio sirve
+        let (status, _, _) = app.request(Method::GET, "/api/v1/auth/sessions", Some(&token), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _, _) = app.request(Method::GET, "/api/v1/auth/sessions", Some(new_token), None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        assert_eq!(app.login(&email, PASSWORD).await.0, StatusCode::UNAUTHORIZED);
+        assert_eq!(app.login(&email, "Monte-Verde-2027").await.0, StatusCode::OK);
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn remember_me_token_cannot_change_password() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("recordarme");
+        app.register(&email, None).await;
+
+        let (status, _, body) = app
+            .request(
+                Method::POST,
+                "/api/v1/auth/login",
+                None,
+                Some(json!({ "identifier": email, "password": PASSWORD, "remember_me": true })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let token = body["token"].as_str().unwrap();
+
+        // Sirve para el uso normal...
+        let (status, _, _) = app.request(Method::GET, "/api/v1/auth/sessions", Some(token), None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // ...pero no para operaciones sensibles
+        let (status, _, body) = app
+            .request(
+                Method::POST,
+                "/api/v1/auth/change-password",
+                Some(token),
+                Some(json!({ "current_password": PASSWORD, "new_password": "Monte-Verde-2027" })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["error"], "fresh_login_required");
+
+        let (status, _, body) = app
+            .request(Method::DELETE, "/api/v1/auth/me", Some(token), Some(json!({ "current_password": PASSWORD })))
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["error"], "fresh_login_required");
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn logout_all_invalidates_every_other_token() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("todas");
+        let (_, first) = app.register(&email, None).await;
+        let (status, body) = app.login(&email, PASSWORD).await;
+        assert_eq!(status, StatusCode::OK);
+        let second = body["token"].as_str().unwrap().to_string();
+        let second_refresh = body["refresh_token"].as_str().unwrap().to_string();
+
+        let (status, _, body) = app.request(Method::POST, "/api/v1/auth/logout-all", Some(&first), None).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let replacement = body["token"].as_str().unwrap().to_string();
+
+        for old in [&first, &second] {
+            let (status, _, body) = app.request(Method::GET, "/api/v1/auth/sessions", Some(old), None).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(body["error"], "token_revoked");
+        }
+
+        let (status, _, _) = app
+            .request(Method::POST, "/api/v1/auth/refresh", None, Some(json!({ "refresh_token": second_refresh })))
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // El token devuelto sigue en la sesión de quien cerró las demás
+        let (status, _, body) = app.request(Method::GET, "/api/v1/auth/sessions", Some(&replacement), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 1);
+        assert_eq!(body["sessions"][0]["current"], true);
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn introspect_reports_revoked_sessions_as_inactive() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("introspeccion");
+        let (_, first) = app.register(&email, None).await;
+        let introspect = |token: &str| json!({ "token": token });
+
+        let (status, _, body) = app.request(Method::POST, "/api/v1/auth/introspect", None, Some(introspect(&first))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["active"], true);
+        assert_eq!(body["email"], email.as_str());
+
+        // Sesión revocada desde otra sesión
+        let (_, body) = app.login(&email, PASSWORD).await;
+        let second = body["token"].as_str().unwrap().to_string();
+        let (_, _, body) = app.request(Method::GET, "/api/v1/auth/sessions", Some(&first), None).await;
+        let first_session = body["sessions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|s| s["current"] == true)
+            .unwrap()["id"]
+            .as_i64()
+            .unwrap();
+        let (status, _, _) = app
+            .request(Method::DELETE, &format!("/api/v1/auth/sessions/{}", first_session), Some(&second), None)
+            .await;
+        assert!(status.is_success());
+
+        let (_, _, body) = app.request(Method::POST, "/api/v1/auth/introspect", None, Some(introspect(&first))).await;
+        assert_eq!(body["active"], false);
+        assert!(body.get("sub").is_none());
+
+        // token_version subido por logout-all
+        let (_, _, body) = app.request(Method::POST, "/api/v1/auth/logout-all", Some(&second), None).await;
+        let replacement = body["token"].as_str().unwrap().to_string();
+        let (_, _, body) = app.request(Method::POST, "/api/v1/auth/introspect", None, Some(introspect(&second))).await;
+        assert_eq!(body["active"], false);
+        let (_, _, body) = app.request(Method::POST, "/api/v1/auth/introspect", None, Some(introspect(&replacement))).await;
+        assert_eq!(body["active"], true);
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn export_is_an_attachment_with_the_profile() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("exportar");
+        let (user_id, token) = app.register(&email, Some("anaexporta")).await;
+
+        let (status, headers, body) = app.request(Method::GET, "/api/v1/auth/me/export", Some(&token), None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let disposition = headers[header::CONTENT_DISPOSITION].to_str().unwrap();
+        assert!(disposition.starts_with(&format!("attachment; filename=\"venta-libre-datos-{}-", user_id)), "{}", disposition);
+        assert_eq!(headers[header::CACHE_CONTROL], "no-store");
+
+        let profile = &body["profile"];
+        assert_eq!(profile["user"]["id"], user_id);
+        assert_eq!(profile["user"]["email"], email.as_str());
+        assert_eq!(profile["user"]["username"], "anaexporta");
+        assert_eq!(profile["has_password"], true);
+        assert!(profile["user"].get("password_hash").is_none());
+        assert_eq!(body["sessions"].as_array().unwrap().len(), 1);
+        assert_eq!(body["account_events"][0]["event_type"], crate::events::EVENT_USER_REGISTERED);
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn deleted_account_can_no_longer_log_in() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("baja");
+        let (user_id, token) = app.register(&email, None).await;
+
+        let (status, _, body) = app
+            .request(Method::DELETE, "/api/v1/auth/me", Some(&token), Some(json!({ "current_password": "Otra-Clave-2026" })))
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", body);
+
+        let (status, _, body) = app
+            .request(Method::DELETE, "/api/v1/auth/me", Some(&token), Some(json!({ "current_password": PASSWORD })))
+            .await;
+        assert!(status.is_success(), "{}: {}", status, body);
+
+        let (status, body) = app.login(&email, PASSWORD).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "invalid_credentials");
+
+        let (status, _, _) = app.request(Method::GET, "/api/v1/auth/sessions", Some(&token), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, _, _) = app.request(Method::GET, &format!("/api/v1/users/{}", user_id), None, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // El nombre se borra en el momento; el email queda hasta la anonimización
+        let (name, stored_email): (String, String) = sqlx::query_as("SELECT name, email FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+        assert_eq!(name, "Usuario eliminado");
+        assert_eq!(stored_email, email);
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn login_accepts_username_and_rejects_taken_ones() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("usuario");
+        app.register(&email, Some("AnaQuispe")).await;
+
+        // Se guarda y se compara en minúsculas
+        let (status, body) = app.login("anaquispe", PASSWORD).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["user"]["email"], email.as_str());
+        assert_eq!(app.login("ANAQUISPE", PASSWORD).await.0, StatusCode::OK);
+        assert_eq!(app.login("anaquispe", "Otra-Clave-2026").await.0, StatusCode::UNAUTHORIZED);
+
+        let (status, _, body) = app
+            .request(
+                Method::POST,
+                "/api/v1/auth/register",
+                None,
+                Some(json!({
+                    "name": "Otra Ana",
+                    "email": unique_email("otra"),
+                    "password": PASSWORD,
+                    "username": "anaquispe",
+                })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error"], "username_exists");
+
+        app.finish().await;
+    }
+
+    async fn refresh(app: &TestApp, refresh_token: &str) -> (StatusCode, serde_json::Value) {
+        let (status, _, body) = app
+            .request(Method::POST, "/api/v1/auth/refresh", None, Some(json!({ "refresh_token": refresh_token })))
+            .await;
+        (status, body)
+    }
+
+    async fn login_tokens(app: &TestApp, email: &str) -> (String, String) {
+        let (status, body) = app.login(email, PASSWORD).await;
+        assert_eq!(status, StatusCode::OK);
+        (
+            body["token"].as_str().unwrap().to_string(),
+            body["refresh_token"].as_str().unwrap().to_string(),
+        )
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn refresh_rotates_the_token_pair() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("renovar");
+        app.register(&email, None).await;
+        let (_, refresh_token) = login_tokens(&app, &email).await;
+
+        let (status, body) = refresh(&app, &refresh_token).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let access = body["token"].as_str().unwrap();
+        let rotated = body["refresh_token"].as_str().unwrap();
+        assert_ne!(rotated, refresh_token);
+
+        let (status, _, _) = app.request(Method::GET, "/api/v1/auth/sessions", Some(access), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(refresh(&app, rotated).await.0, StatusCode::OK);
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn expired_or_revoked_refresh_tokens_are_rejected() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("vencido");
+        let (user_id, _) = app.register(&email, None).await;
+
+        let (_, expired) = login_tokens(&app, &email).await;
+        sqlx::query("UPDATE refresh_tokens SET expires_at = NOW() - INTERVAL '1 minute' WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&app.pool)
+            .await
+            .unwrap();
+        let (status, body) = refresh(&app, &expired).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "invalid_refresh_token");
+
+        let (_, revoked) = login_tokens(&app, &email).await;
+        sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+            .bind(user_id)
+            .execute(&app.pool)
+            .await
+            .unwrap();
+        assert_eq!(refresh(&app, &revoked).await.0, StatusCode::UNAUTHORIZED);
+
+        assert_eq!(refresh(&app, "no-es-un-jwt").await.0, StatusCode::UNAUTHORIZED);
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn reused_refresh_token_revokes_the_session() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("reuso");
+        app.register(&email, None).await;
+        let (access, original) = login_tokens(&app, &email).await;
+
+        let (status, body) = refresh(&app, &original).await;
+        assert_eq!(status, StatusCode::OK);
+        let replacement = body["refresh_token"].as_str().unwrap().to_string();
+        let new_access = body["token"].as_str().unwrap().to_string();
+
+        // El token ya rotado vuelve a aparecer: alguien más lo tiene
+        assert_eq!(refresh(&app, &original).await.0, StatusCode::UNAUTHORIZED);
+
+        // La sesión entera queda cerrada, también para el reemplazo legítimo
+        assert_eq!(refresh(&app, &replacement).await.0, StatusCode::UNAUTHORIZED);
+        for token in [&access, &new_access] {
+            let (status, _, _) = app.request(Method::GET, "/api/v1/auth/sessions", Some(token), None).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+        }
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn logout_closes_the_session_and_its_refresh_tokens() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("salida");
+        app.register(&email, None).await;
+        let (token, refresh_token) = login_tokens(&app, &email).await;
+        let (other, _) = login_tokens(&app, &email).await;
+
+        let (status, _, body) = app.request(Method::POST, "/api/v1/auth/logout", Some(&token), None).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+
+        let (status, body) = refresh(&app, &refresh_token).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "invalid_refresh_token");
+
+        // Solo el refresh token de la sesión cerrada queda revocado
+        let revoked: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM refresh_tokens WHERE revoked_at IS NOT NULL")
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+        assert_eq!(revoked, 1);
+
+        // Quedan la sesión del registro y la del otro login; la cerrada ya no se lista
+        let (status, _, body) = app.request(Method::GET, "/api/v1/auth/sessions", Some(&other), None).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["total"], 2);
+
+        app.finish().await;
+    }
+
+    async fn reset_token_for(app: &TestApp, user_id: i32, expires_in: &str) -> String {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        sqlx::query(&format!(
+            "INSERT INTO password_resets (user_id, token_hash, expires_at) VALUES ($1, $2, NOW() + INTERVAL '{}')",
+            expires_in
+        ))
+        .bind(user_id)
+        .bind(super::hash_reset_token(&token))
+        .execute(&app.pool)
+        .await
+        .unwrap();
+        token
+    }
+
+    async fn reset(app: &TestApp, token: &str) -> (StatusCode, serde_json::Value) {
+        let (status, _, body) = app
+            .request(
+                Method::POST,
+                "/api/v1/auth/reset-password",
+                None,
+                Some(json!({ "token": token, "new_password": "Monte-Verde-2027" })),
+            )
+            .await;
+        (status, body)
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn forgot_password_answers_the_same_for_unknown_emails() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("olvido");
+        app.register(&email, None).await;
+
+        let mut messages = Vec::new();
+        for target in [email.clone(), unique_email("nadie")] {
+            let (status, _, body) = app
+                .request(Method::POST, "/api/v1/auth/forgot-password", None, Some(json!({ "email": target })))
+                .await;
+            assert_eq!(status, StatusCode::OK);
+            messages.push(body["message"].clone());
+        }
+        assert_eq!(messages[0], messages[1]);
+
+        // Solo el email registrado genera un token
+        let pending: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM password_resets")
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+        assert_eq!(pending, 1);
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn reset_tokens_are_single_use_and_expire() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("restablecer");
+        let (user_id, old_token) = app.register(&email, None).await;
+
+        let token = reset_token_for(&app, user_id, "1 hour").await;
+        let (status, body) = reset(&app, &token).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(app.login(&email, "Monte-Verde-2027").await.0, StatusCode::OK);
+        let (status, _, _) = app.request(Method::GET, "/api/v1/auth/sessions", Some(&old_token), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, body) = reset(&app, &token).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_reset_token");
+
+        let expired = reset_token_for(&app, user_id, "-1 minute").await;
+        let (status, body) = reset(&app, &expired).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_reset_token");
+
+        assert_eq!(reset(&app, "token-inexistente").await.0, StatusCode::BAD_REQUEST);
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn reset_fails_for_deactivated_accounts() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("inactiva");
+        let (user_id, _) = app.register(&email, None).await;
+        let token = reset_token_for(&app, user_id, "1 hour").await;
+
+        sqlx::query("UPDATE users SET is_active = false WHERE id = $1")
+            .bind(user_id)
+            .execute(&app.pool)
+            .await
+            .unwrap();
+
+        let (status, body) = reset(&app, &token).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_reset_token");
+
+        let stored: String = sqlx::query_scalar("SELECT password_hash FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+        assert!(crate::auth::password::verify_password(PASSWORD, &stored).unwrap());
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn device_is_recorded_only_after_the_second_factor() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("dispositivo");
+        let password_hash = hash_password(PASSWORD).unwrap();
+        let user_id = app.insert_user(&email, &password_hash, Role::User).await;
+        let devices = || {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM known_devices WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_one(&app.pool)
+        };
+
+        let (status, body) = app.login(&email, PASSWORD).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(devices().await.unwrap(), 1);
+
+        // Con 2FA la contraseña sola no registra el dispositivo
+        sqlx::query("DELETE FROM known_devices WHERE user_id = $1").bind(user_id).execute(&app.pool).await.unwrap();
+        sqlx::query("UPDATE users SET totp_enabled = true WHERE id = $1").bind(user_id).execute(&app.pool).await.unwrap();
+        let (status, body) = app.login(&email, PASSWORD).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["two_factor_required"], true);
+        assert_eq!(devices().await.unwrap(), 0);
+
+        app.finish().await;
+    }
+}
@@ -0,0 +1,125 @@
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+use sqlx::PgPool;
+use std::net::SocketAddr;
+use crate::auth::rate_limit::rate_limiter;
+use crate::logging::middleware::get_client_ip;
+use crate::models::auth::AuthError;
+use crate::models::contact::ContactRequest;
+use crate::validation::captcha;
+
+const MAX_NAME_CHARS: usize = 100;
+const MAX_MESSAGE_CHARS: usize = 5000;
+
+fn bad_request(error: &str, message: &str) -> Response {
+    (StatusCode::BAD_REQUEST, Json(AuthError::new(error, message))).into_response()
+}
+
+// Misma respuesta para mensajes guardados y para bots descartados por el honeypot
+fn accepted() -> Response {
+    (
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "message": "Mensaje recibido. Te responderemos por email lo antes posible",
+            "timestamp": crate::models::timestamp::now()
+        })),
+    )
+        .into_response()
+}
+
+// POST /api/v1/contact (sin autenticación)
+pub async fn submit_contact(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<ContactRequest>,
+) -> Response {
+    let client_ip = get_client_ip(&headers, &addr);
+
+    // Honeypot: un humano no ve el campo; se descarta sin delatar la detección
+    if request.website.as_deref().is_some_and(|w| !w.trim().is_empty()) {
+        tracing::info!(event = "contact_honeypot", client_ip = %client_ip, "🍯 Mensaje de contacto descartado por honeypot");
+        return accepted();
+    }
+
+    if let Err(retry_after) = rate_limiter().check("contact", &client_ip) {
+        let retry_after_secs = retry_after.as_secs().max(1);
+        tracing::warn!(
+            event = "rate_limited",
+            route = "contact",
+            client_ip = %client_ip,
+            retry_after_secs = retry_after_secs,
+            "🚦 Límite de requests excedido"
+        );
+
+        let mut response = (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(AuthError::new(
+                "rate_limited",
+                "Demasiadas solicitudes. Intenta de nuevo más tarde",
+            )),
+        )
+            .into_response();
+        if let Ok(value) = retry_after_secs.to_string().parse() {
+            response.headers_mut().insert(header::RETRY_AFTER, value);
+        }
+        return response;
+    }
+
+    let name = request.name.trim();
+    let email = request.email.trim().to_lowercase();
+    let message = request.message.trim();
+
+    if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+        return bad_request("invalid_name", "El nombre es requerido (máximo 100 caracteres)");
+    }
+    if email.is_empty() || !email.contains('@') || email.len() > 255 {
+        return bad_request("invalid_email", "Email inválido");
+    }
+    if message.is_empty() || message.chars().count() > MAX_MESSAGE_CHARS {
+        return bad_request("invalid_message", "El mensaje es requerido (máximo 5000 caracteres)");
+    }
+
+    if !captcha::verify(request.captcha_token.as_deref(), &client_ip).await {
+        return bad_request("invalid_captcha", "Verificación de captcha inválida");
+    }
+
+    let id: i32 = match sqlx::query_scalar(
+        "INSERT INTO contact_messages (name, email, subject, message, ip_address)
+         VALUES ($1, $2, $3, $4, $5) RETURNING id"
+    )
+    .bind(name)
+    .bind(&email)
+    .bind(request.subject.as_str())
+    .bind(message)
+    .bind(&client_ip)
+    .fetch_one(&pool)
+    .await
+    {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!(error = %e, "🚨 Error al guardar mensaje de contacto");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(AuthError::new("database_error", "Error de base de datos")),
+            )
+                .into_response();
+        }
+    };
+
+    // Aún no hay centro de notificaciones ni envío de emails: el aviso a los admins
+    // queda en los logs y el mensaje en GET /admin/contact-messages
+    tracing::warn!(
+        event = "contact_message_received",
+        contact_message_id = id,
+        subject = request.subject.as_str(),
+        client_ip = %client_ip,
+        "📨 Nuevo mensaje de contacto para los admins"
+    );
+
+    accepted()
+}
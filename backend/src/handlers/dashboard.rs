@@ -0,0 +1,28 @@
+use axum::{
+    http::header,
+    response::{Html, IntoResponse},
+};
+
+// Panel de administración embebido en el binario
+const DASHBOARD_TEMPLATE: &str = include_str!("../../static/admin_dashboard.html");
+
+// Indica si el panel está habilitado (ADMIN_DASHBOARD_ENABLED=false lo desactiva)
+pub fn is_enabled() -> bool {
+    std::env::var("ADMIN_DASHBOARD_ENABLED")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true)
+}
+
+// GET /admin
+// La página es pública; los datos se piden a los endpoints de admin con el token del usuario
+pub async fn admin_dashboard() -> impl IntoResponse {
+    let environment = std::env::var("ENVIRONMENT").unwrap_or_else(|_| "development".to_string());
+    let page = DASHBOARD_TEMPLATE
+        .replace("{{version}}", env!("CARGO_PKG_VERSION"))
+        .replace("{{environment}}", &environment);
+
+    (
+        [(header::CACHE_CONTROL, "no-store")],
+        Html(page),
+    )
+}
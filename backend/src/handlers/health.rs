@@ -1,8 +1,9 @@
 use axum::{
     extract::State,
-    http::StatusCode,
-    response::Json,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json},
 };
+use crate::handlers::to_json;
 use crate::health::HealthChecker;
 use crate::logging::get_request_id;
 use std::sync::Arc;
@@ -32,10 +33,11 @@ pub async fn health_check(
         "🏥 Health check ejecutado"
     );
     
+    let body = to_json(&health_response)?;
     if status_code.is_success() {
-        Ok(Json(serde_json::to_value(health_response).unwrap()))
+        Ok(Json(body))
     } else {
-        Err((status_code, Json(serde_json::to_value(health_response).unwrap())))
+        Err((status_code, Json(body)))
     }
 }
 
@@ -73,16 +75,60 @@ pub async fn readiness_check(
     }
 }
 
+// Health check solo de base de datos
+pub async fn database_check(
+    State(health_checker): State<Arc<HealthChecker>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let (is_healthy, database_response) = health_checker.check_database_health().await;
+    
+    tracing::debug!(
+        event = "database_check",
+        is_healthy = %is_healthy,
+        "💾 Database health check"
+    );
+    
+    if is_healthy {
+        Ok(Json(database_response))
+    } else {
+        Err((StatusCode::SERVICE_UNAVAILABLE, Json(database_response)))
+    }
+}
+
+// Startup probe - pasa cuando el warm-up crítico terminó
+pub async fn startup_check(
+    State(health_checker): State<Arc<HealthChecker>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let (started, startup_response) = health_checker.check_startup();
+    
+    if started {
+        Ok(Json(startup_response))
+    } else {
+        Err((StatusCode::SERVICE_UNAVAILABLE, Json(startup_response)))
+    }
+}
+
 // Status simple para load balancers
 pub async fn status_check() -> Json<serde_json::Value> {
     Json(serde_json::json!({
         "status": "ok",
         "service": "venta-libre-api",
         "version": env!("CARGO_PKG_VERSION"),
-        "timestamp": chrono::Utc::now()
+        "timestamp": crate::models::timestamp::now()
     }))
 }
 
+// Datos para la página de estado pública (cacheados)
+pub async fn status_page(
+    State(health_checker): State<Arc<HealthChecker>>,
+) -> impl IntoResponse {
+    let status_page = health_checker.status_page().await;
+    
+    (
+        [(header::CACHE_CONTROL, "public, max-age=60")],
+        Json(status_page),
+    )
+}
+
 // Información del servidor
 pub async fn server_info(
     State(health_checker): State<Arc<HealthChecker>>,
@@ -3,66 +3,95 @@ use axum::{
     http::StatusCode,
     response::Json,
 };
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use std::collections::HashMap;
-use crate::metrics::MetricsCollector;
+use crate::handlers::to_json;
+use crate::logging::timing::slow_requests;
+use crate::metrics::{reporting_timezone, MetricsCollector};
+use crate::auth::concurrency::user_concurrency;
 use crate::auth::middleware::AuthUser;
+use crate::metrics::privacy::{can_view_user_ids, user_id_value};
+use crate::single_flight::{self, SingleFlight};
 
 // Obtener métricas generales del sistema
 pub async fn get_metrics(
     State(metrics_collector): State<Arc<MetricsCollector>>,
-    auth_user: Option<AuthUser>,
+    auth_user: AuthUser,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
-    // Solo admins pueden ver métricas completas
-    if let Some(ref user) = auth_user {
-        if !user.user.is_admin {
-            return Err((
-                StatusCode::FORBIDDEN,
-                Json(serde_json::json!({
-                    "error": "forbidden",
-                    "message": "Solo administradores pueden acceder a las métricas"
-                }))
-            ));
-        }
-    } else {
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(serde_json::json!({
-                "error": "unauthorized",
-                "message": "Autenticación requerida"
-            }))
-        ));
-    }
-
     let snapshot = metrics_collector.get_metrics_snapshot();
     
     tracing::info!(
         event = "metrics_accessed",
-        user_id = auth_user.as_ref().map(|u| u.user.id),
+        user_id = auth_user.user.id,
         total_requests = snapshot.total_requests,
         uptime_seconds = snapshot.uptime_seconds,
         "📊 Métricas accedidas por admin"
     );
 
-    Ok(Json(serde_json::to_value(snapshot).unwrap()))
+    // Usuarios con más requests simultáneos en curso
+    let raw_ids = can_view_user_ids(&auth_user.user);
+    let limiter = user_concurrency();
+    let top_concurrency: Vec<serde_json::Value> = limiter
+        .top_users(10)
+        .into_iter()
+        .map(|(user_id, in_flight)| serde_json::json!({
+            "user_id": user_id_value(Some(user_id), raw_ids),
+            "in_flight": in_flight
+        }))
+        .collect();
+
+    let mut body = to_json(&snapshot)?;
+    body["user_concurrency"] = serde_json::json!({
+        "max_per_user": limiter.max(),
+        "top_users": top_concurrency
+    });
+    // Lecturas costosas deduplicadas por single flight
+    body["request_coalescing"] = serde_json::json!(single_flight::stats());
+
+    Ok(Json(body))
 }
 
-// Métricas públicas básicas (sin autenticación)
-pub async fn get_public_metrics(
-    State(metrics_collector): State<Arc<MetricsCollector>>,
-) -> Json<serde_json::Value> {
+// Resumen público compartido por los requests concurrentes (ver single_flight)
+fn public_summary_flight() -> &'static SingleFlight<(), serde_json::Value> {
+    static FLIGHT: OnceLock<SingleFlight<(), serde_json::Value>> = OnceLock::new();
+    FLIGHT.get_or_init(|| SingleFlight::new("public_metrics_summary"))
+}
+
+fn public_summary(metrics_collector: &MetricsCollector) -> serde_json::Value {
     let snapshot = metrics_collector.get_metrics_snapshot();
     
     // Solo información básica sin datos sensibles
-    Json(serde_json::json!({
+    serde_json::json!({
         "service": "venta-libre-api",
         "version": env!("CARGO_PKG_VERSION"),
         "uptime_seconds": snapshot.uptime_seconds,
         "total_requests": snapshot.total_requests,
         "requests_per_minute": snapshot.requests_per_minute,
         "avg_response_time_ms": snapshot.avg_response_time_ms,
-        "timestamp": snapshot.timestamp
-    }))
+        "online_now": metrics_collector.online_users(),
+        "timestamp": crate::models::timestamp::format(&snapshot.timestamp)
+    })
+}
+
+// Métricas públicas básicas (sin autenticación)
+pub async fn get_public_metrics(
+    State(metrics_collector): State<Arc<MetricsCollector>>,
+) -> Json<serde_json::Value> {
+    // El snapshot recorre todas las métricas en memoria: se calcula fuera del runtime
+    // y una sola vez para todos los requests que llegan mientras tanto
+    let summary = public_summary_flight()
+        .run((), || async {
+            let collector = metrics_collector.clone();
+            tokio::task::spawn_blocking(move || public_summary(&collector))
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::error!(error = %e, "🚨 Error al calcular el resumen de métricas públicas");
+                    public_summary(&metrics_collector)
+                })
+        })
+        .await;
+
+    Json(summary)
 }
 
 // Métricas de un endpoint específico
@@ -71,17 +100,6 @@ pub async fn get_endpoint_metrics(
     Path((method, path)): Path<(String, String)>,
     auth_user: AuthUser,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
-    // Solo admins pueden ver métricas de endpoints
-    if !auth_user.user.is_admin {
-        return Err((
-            StatusCode::FORBIDDEN,
-            Json(serde_json::json!({
-                "error": "forbidden",
-                "message": "Solo administradores pueden acceder a estas métricas"
-            }))
-        ));
-    }
-
     match metrics_collector.get_endpoint_metrics(&method, &path) {
         Some(endpoint_metrics) => {
             tracing::debug!(
@@ -92,7 +110,7 @@ pub async fn get_endpoint_metrics(
                 "📈 Métricas de endpoint accedidas"
             );
             
-            Ok(Json(serde_json::to_value(endpoint_metrics).unwrap()))
+            Ok(Json(to_json(&endpoint_metrics)?))
         }
         None => Err((
             StatusCode::NOT_FOUND,
@@ -108,19 +126,7 @@ pub async fn get_endpoint_metrics(
 pub async fn get_top_endpoints(
     State(metrics_collector): State<Arc<MetricsCollector>>,
     Query(params): Query<HashMap<String, String>>,
-    auth_user: AuthUser,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
-    // Solo admins
-    if !auth_user.user.is_admin {
-        return Err((
-            StatusCode::FORBIDDEN,
-            Json(serde_json::json!({
-                "error": "forbidden",
-                "message": "Solo administradores pueden acceder a esta información"
-            }))
-        ));
-    }
-
     let snapshot = metrics_collector.get_metrics_snapshot();
     
     // Parámetro opcional para limitar resultados
@@ -139,7 +145,7 @@ pub async fn get_top_endpoints(
     Ok(Json(serde_json::json!({
         "top_endpoints": top_endpoints,
         "limit": limit,
-        "timestamp": chrono::Utc::now()
+        "timestamp": crate::models::timestamp::now()
     })))
 }
 
@@ -147,18 +153,7 @@ pub async fn get_top_endpoints(
 pub async fn get_slowest_endpoints(
     State(metrics_collector): State<Arc<MetricsCollector>>,
     Query(params): Query<HashMap<String, String>>,
-    auth_user: AuthUser,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
-    if !auth_user.user.is_admin {
-        return Err((
-            StatusCode::FORBIDDEN,
-            Json(serde_json::json!({
-                "error": "forbidden",
-                "message": "Solo administradores pueden acceder a esta información"
-            }))
-        ));
-    }
-
     let snapshot = metrics_collector.get_metrics_snapshot();
     
     let limit: usize = params
@@ -175,26 +170,17 @@ pub async fn get_slowest_endpoints(
 
     Ok(Json(serde_json::json!({
         "slowest_endpoints": slowest_endpoints,
+        // Requests lentos recientes con su desglose (middleware, auth, handler, serialización)
+        "recent_slow_requests": slow_requests().recent(),
         "limit": limit,
-        "timestamp": chrono::Utc::now()
+        "timestamp": crate::models::timestamp::now()
     })))
 }
 
 // Distribución de códigos de estado
 pub async fn get_status_distribution(
     State(metrics_collector): State<Arc<MetricsCollector>>,
-    auth_user: AuthUser,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
-    if !auth_user.user.is_admin {
-        return Err((
-            StatusCode::FORBIDDEN,
-            Json(serde_json::json!({
-                "error": "forbidden",
-                "message": "Solo administradores pueden acceder a esta información"
-            }))
-        ));
-    }
-
     let snapshot = metrics_collector.get_metrics_snapshot();
 
     // Agrupar por categorías de status
@@ -215,31 +201,27 @@ pub async fn get_status_distribution(
         "status_distribution": snapshot.status_code_distribution,
         "categories": categories,
         "total_requests": snapshot.total_requests,
-        "timestamp": chrono::Utc::now()
+        "timestamp": crate::models::timestamp::now()
     })))
 }
 
 // Estadísticas por hora
 pub async fn get_hourly_stats(
     State(metrics_collector): State<Arc<MetricsCollector>>,
-    auth_user: AuthUser,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
-    if !auth_user.user.is_admin {
-        return Err((
-            StatusCode::FORBIDDEN,
-            Json(serde_json::json!({
-                "error": "forbidden",
-                "message": "Solo administradores pueden acceder a esta información"
-            }))
-        ));
-    }
-
     let snapshot = metrics_collector.get_metrics_snapshot();
 
+    // Hora pico en la zona de reporte
+    let peak_hour = snapshot.hourly_stats.iter()
+        .max_by_key(|h| h.requests)
+        .map(|h| h.local_hour.clone());
+
     Ok(Json(serde_json::json!({
         "hourly_stats": snapshot.hourly_stats,
+        "timezone": reporting_timezone().name(),
         "summary": {
             "total_hours": snapshot.hourly_stats.len(),
+            "peak_hour": peak_hour,
             "avg_requests_per_hour": snapshot.hourly_stats.iter()
                 .map(|h| h.requests)
                 .sum::<u64>() as f64 / snapshot.hourly_stats.len().max(1) as f64,
@@ -247,6 +229,6 @@ pub async fn get_hourly_stats(
                 .map(|h| h.avg_response_time_ms)
                 .sum::<f64>() / snapshot.hourly_stats.len().max(1) as f64,
         },
-        "timestamp": chrono::Utc::now()
+        "timestamp": crate::models::timestamp::now()
     })))
 }
\ No newline at end of file
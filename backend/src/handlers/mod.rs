@@ -1,4 +1,37 @@
 pub mod users;
 pub mod auth;
+pub mod oauth;
 pub mod health;
-pub mod metrics;
\ No newline at end of file
+pub mod metrics;
+pub mod admin;
+pub mod dashboard;
+pub mod contact;
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+// Error de API genérico con el mismo formato que AuthError
+pub type ApiError = (StatusCode, Json<serde_json::Value>);
+
+// Serializar una respuesta sin provocar panics en el camino del request
+pub fn to_json<T: Serialize>(value: &T) -> Result<serde_json::Value, ApiError> {
+    serde_json::to_value(value).map_err(|e| {
+        tracing::error!(error = %e, "🚨 Error al serializar respuesta");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": "serialization_error",
+                "message": "Error al generar la respuesta"
+            })),
+        )
+    })
+}
+
+// Respuesta 201 Created con header Location apuntando al nuevo recurso
+pub fn created<T: IntoResponse>(location: String, body: T) -> Response {
+    (StatusCode::CREATED, [(header::LOCATION, location)], body).into_response()
+}
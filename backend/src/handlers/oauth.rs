@@ -0,0 +1,305 @@
+use axum::{
+    extract::{ConnectInfo, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Redirect, Response},
+    Extension,
+};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::PgPool;
+use std::net::SocketAddr;
+use crate::auth::middleware::AuthUser;
+use crate::auth::oauth::{fetch_google_profile, google_config, oauth_states, GoogleOAuthConfig, GoogleProfile, STATE_COOKIE};
+use crate::auth::sessions::ClientInfo;
+use crate::events;
+use crate::handlers::auth::{start_session, token_response, two_factor_challenge_for};
+use crate::logging::{middleware::get_client_ip, Logger, RequestId};
+use crate::models::auth::AuthError;
+use crate::models::user::User;
+
+const PROVIDER_GOOGLE: &str = "google";
+
+type OAuthResult<T> = Result<T, (StatusCode, Json<AuthError>)>;
+
+#[derive(Debug, Deserialize)]
+pub struct OAuthCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+fn database_error(e: sqlx::Error) -> (StatusCode, Json<AuthError>) {
+    tracing::error!(error = %e, "🚨 Error de base de datos en OAuth");
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(AuthError::new("database_error", "Error de base de datos")),
+    )
+}
+
+fn require_config() -> OAuthResult<&'static GoogleOAuthConfig> {
+    google_config().ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(AuthError::new("oauth_not_configured", "Login con Google no disponible")),
+        )
+    })
+}
+
+// Cookie HttpOnly con el state: el callback solo se acepta en el navegador que inició el flujo
+fn state_cookie(state: &str, max_age_secs: u64) -> HeaderValue {
+    let cookie = format!(
+        "{}={}; Path=/api/v1/auth/oauth; HttpOnly; Secure; SameSite=Lax; Max-Age={}",
+        STATE_COOKIE, state, max_age_secs
+    );
+    HeaderValue::from_str(&cookie).unwrap_or_else(|_| HeaderValue::from_static(""))
+}
+
+fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+// GET /api/v1/auth/oauth/google
+pub async fn google_authorize() -> OAuthResult<Response> {
+    let config = require_config()?;
+    let states = oauth_states();
+    let (state, code_challenge) = states.begin(None);
+
+    let mut response = Redirect::to(&config.authorization_url(&state, &code_challenge)).into_response();
+    response
+        .headers_mut()
+        .insert(header::SET_COOKIE, state_cookie(&state, states.ttl().as_secs()));
+
+    Ok(response)
+}
+
+// POST /api/v1/auth/oauth/google/link
+// Vinculación explícita de Google a la cuenta autenticada; el cliente navega a la URL devuelta
+pub async fn google_link(auth_user: AuthUser) -> OAuthResult<Response> {
+    let config = require_config()?;
+    let states = oauth_states();
+    let (state, code_challenge) = states.begin(Some(auth_user.user.id));
+
+    let mut response = Json(json!({
+        "authorization_url": config.authorization_url(&state, &code_challenge),
+    }))
+    .into_response();
+    response
+        .headers_mut()
+        .insert(header::SET_COOKIE, state_cookie(&state, states.ttl().as_secs()));
+
+    Ok(response)
+}
+
+// GET /api/v1/auth/oauth/google/callback
+pub async fn google_callback(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request_id: Option<Extension<RequestId>>,
+    headers: HeaderMap,
+    Query(query): Query<OAuthCallbackQuery>,
+) -> OAuthResult<Response> {
+    let config = require_config()?;
+    let client_ip = get_client_ip(&headers, &addr);
+    let request_id = request_id.map(|Extension(id)| id.0).unwrap_or_default();
+    let invalid_state = || {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(AuthError::new("invalid_oauth_state", "Estado de OAuth inválido o vencido")),
+        )
+    };
+
+    // El state debe coincidir con la cookie del navegador y haber sido emitido por nosotros (CSRF)
+    let state = query.state.as_deref().ok_or_else(invalid_state)?;
+    if cookie_value(&headers, STATE_COOKIE) != Some(state) {
+        Logger::log_auth_event("oauth_state_mismatch", None, None, Some(&client_ip), false, &request_id);
+        return Err(invalid_state());
+    }
+    let flow = oauth_states().take(state).ok_or_else(invalid_state)?;
+
+    if let Some(error) = query.error {
+        tracing::warn!(event = "oauth_denied", provider = PROVIDER_GOOGLE, error = %error, "⚠️ Autorización de Google cancelada");
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(AuthError::new("oauth_denied", "Autorización cancelada")),
+        ));
+    }
+    let code = query.code.as_deref().ok_or_else(invalid_state)?;
+
+    let profile = fetch_google_profile(config, code, &flow.code_verifier)
+        .await
+        .map_err(|e| {
+            tracing::warn!(event = "oauth_exchange_failed", provider = PROVIDER_GOOGLE, error = %e, "⚠️ Error canjeando código de Google");
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(AuthError::new("oauth_exchange_failed", "No se pudo completar el login con Google")),
+            )
+        })?;
+
+    if !profile.email_verified {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(AuthError::new("oauth_email_unverified", "El email de Google no está verificado")),
+        ));
+    }
+
+    let user = match flow.link_user_id {
+        Some(user_id) => link_identity(&pool, user_id, &profile).await?,
+        None => find_or_create_user(&pool, &profile).await?,
+    };
+
+    if !user.is_active {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(AuthError::new("user_inactive", "Usuario inactivo")),
+        ));
+    }
+
+    Logger::log_auth_event("oauth_login", Some(user.id), Some(&user.email), Some(&client_ip), true, &request_id);
+
+    // Mismo flujo que el login con contraseña: 2FA si está activo, si no dispositivo y tokens
+    let mut response = match two_factor_challenge_for(&pool, &user, false).await? {
+        Some(challenge) => Json(challenge).into_response(),
+        None => {
+            let user_agent = headers.get(header::USER_AGENT).and_then(|h| h.to_str().ok());
+            crate::auth::devices::track_login(&pool, &user, user_agent, &client_ip).await;
+            token_response(start_session(&pool, &user, &ClientInfo::from_request(&headers, &addr), false).await?)
+        }
+    };
+    response
+        .headers_mut()
+        .insert(header::SET_COOKIE, state_cookie("", 0));
+
+    Ok(response)
+}
+
+async fn user_by_identity(pool: &PgPool, subject: &str) -> OAuthResult<Option<User>> {
+    sqlx::query_as::<_, User>(
+        "SELECT u.id, u.name, u.email, u.password_hash, u.role, u.is_active, u.token_version, u.username, u.created_at, u.updated_at
+         FROM user_identities i JOIN users u ON u.id = i.user_id
+         WHERE i.provider = $1 AND i.subject = $2"
+    )
+    .bind(PROVIDER_GOOGLE)
+    .bind(subject)
+    .fetch_optional(pool)
+    .await
+    .map_err(database_error)
+}
+
+// Login con Google: identidad ya vinculada o cuenta nueva sin contraseña.
+// Un email ya registrado no se vincula solo: hay que hacerlo desde la cuenta
+async fn find_or_create_user(pool: &PgPool, profile: &GoogleProfile) -> OAuthResult<User> {
+    if let Some(user) = user_by_identity(pool, &profile.sub).await? {
+        return Ok(user);
+    }
+
+    let email = profile.email.trim().to_lowercase();
+    let mut tx = pool.begin().await.map_err(database_error)?;
+
+    let existing: Option<i32> = sqlx::query_scalar("SELECT id FROM users WHERE email = $1 AND deleted_at IS NULL")
+        .bind(&email)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+    if existing.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(AuthError::new(
+                "oauth_link_required",
+                "Ya existe una cuenta con este email; inicia sesión y vincula Google desde tu cuenta",
+            )),
+        ));
+    }
+
+    let name = profile
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| email.split('@').next().unwrap_or_default());
+
+    let user = sqlx::query_as::<_, User>(
+        "INSERT INTO users (name, email, password_hash, is_admin, is_active)
+         VALUES ($1, $2, NULL, false, true)
+         RETURNING id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at"
+    )
+    .bind(name)
+    .bind(&email)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    insert_identity(&mut tx, user.id, profile).await?;
+    events::record(
+        &mut tx,
+        events::EVENT_USER_REGISTERED,
+        events::AGGREGATE_USER,
+        user.id.into(),
+        json!({ "method": PROVIDER_GOOGLE }),
+    )
+    .await
+    .map_err(database_error)?;
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(event = "oauth_user_created", provider = PROVIDER_GOOGLE, user_id = user.id, "👤 Usuario creado con Google");
+
+    Ok(user)
+}
+
+// Vincular la identidad de Google a una cuenta existente (pedido explícito del usuario)
+async fn link_identity(pool: &PgPool, user_id: i32, profile: &GoogleProfile) -> OAuthResult<User> {
+    if let Some(user) = user_by_identity(pool, &profile.sub).await? {
+        if user.id == user_id {
+            return Ok(user);
+        }
+        return Err((
+            StatusCode::CONFLICT,
+            Json(AuthError::new("oauth_identity_in_use", "Esta cuenta de Google ya está vinculada a otro usuario")),
+        ));
+    }
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at
+         FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, Json(AuthError::user_not_found())))?;
+
+    insert_identity(&mut tx, user.id, profile).await?;
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(event = "oauth_identity_linked", provider = PROVIDER_GOOGLE, user_id = user.id, "🔗 Cuenta de Google vinculada");
+
+    Ok(user)
+}
+
+async fn insert_identity(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: i32,
+    profile: &GoogleProfile,
+) -> OAuthResult<()> {
+    sqlx::query(
+        "INSERT INTO user_identities (user_id, provider, subject, email) VALUES ($1, $2, $3, $4)"
+    )
+    .bind(user_id)
+    .bind(PROVIDER_GOOGLE)
+    .bind(&profile.sub)
+    .bind(profile.email.trim().to_lowercase())
+    .execute(&mut **tx)
+    .await
+    .map_err(database_error)?;
+
+    Ok(())
+}
@@ -1,54 +1,402 @@
 use axum::{
     extract::{Path, State},
     http::StatusCode,
-    response::Json,
+    response::{Json, Response},
 };
 use serde_json::{json, Value};
 use sqlx::PgPool;
+use std::sync::OnceLock;
+use crate::auth::middleware::AuthUser;
+use crate::handlers::{created, ApiError};
+use crate::models::auth::AuthError;
+use crate::models::display::DisplayTimezone;
+use crate::models::user::{MinimalUser, UpdateUserRequest, User};
 
-// GET /api/v1/users
-pub async fn get_all_users(State(pool): State<PgPool>) -> Result<Json<Value>, StatusCode> {
-    let users = sqlx::query!("SELECT id, name, email FROM users")
-        .fetch_all(&pool)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+// Tope de filas del listado de usuarios (USERS_LIST_MAX_ROWS, por defecto 1000), leído una vez
+fn users_list_max_rows() -> i64 {
+    static MAX_ROWS: OnceLock<i64> = OnceLock::new();
+    *MAX_ROWS.get_or_init(|| {
+        std::env::var("USERS_LIST_MAX_ROWS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&n: &i64| n > 0)
+            .unwrap_or(1000)
+    })
+}
+
+// GET /api/v1/users (público: solo la vista mínima de cada usuario; excluye cuentas dadas de baja)
+pub async fn get_all_users(State(pool): State<PgPool>) -> Result<Json<Vec<MinimalUser>>, StatusCode> {
+    list_users(&pool, users_list_max_rows()).await.map(Json)
+}
 
-    let users_json: Vec<Value> = users
-        .iter()
-        .map(|user| {
-            json!({
-                "id": user.id,
-                "name": user.name,
-                "email": user.email
-            })
-        })
-        .collect();
+async fn list_users(pool: &PgPool, max_rows: i64) -> Result<Vec<MinimalUser>, StatusCode> {
+    // Se pide una fila extra para saber si se alcanzó el tope
+    let mut users = sqlx::query_as::<_, MinimalUser>(
+        "SELECT id, name, created_at AS member_since FROM users
+         WHERE deleted_at IS NULL
+         ORDER BY id LIMIT $1"
+    )
+    .bind(max_rows + 1)
+    .fetch_all(pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(Json(json!(users_json)))
+    if users.len() as i64 > max_rows {
+        users.truncate(max_rows as usize);
+        tracing::warn!(
+            event = "users_list_capped",
+            max_rows = max_rows,
+            "⚠️ Listado de usuarios truncado al tope de filas"
+        );
+    }
+
+    Ok(users)
 }
 
 // POST /api/v1/users (mantenemos simple por ahora)
-pub async fn create_user() -> Result<Json<Value>, StatusCode> {
-    let response = json!({"message": "Usuario creado", "id": 3});
-    Ok(Json(response))
+pub async fn create_user() -> Result<Response, StatusCode> {
+    let id = 3;
+    let response = json!({"message": "Usuario creado", "id": id});
+    Ok(created(format!("/api/v1/users/{}", id), Json(response)))
 }
 
-// GET /api/v1/users/:id
+// GET /api/v1/users/:id (público: vista mínima; la completa está en /auth/me).
+// Una cuenta dada de baja responde 404
 pub async fn get_user_by_id(
     Path(id): Path<i32>,
     State(pool): State<PgPool>
+) -> Result<Json<MinimalUser>, StatusCode> {
+    let user = sqlx::query_as::<_, MinimalUser>(
+        "SELECT id, name, created_at AS member_since FROM users WHERE id = $1 AND deleted_at IS NULL"
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    user.map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+// PATCH /api/v1/users/:id
+pub async fn update_user(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
+    display_timezone: DisplayTimezone,
+    Json(request): Json<UpdateUserRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let caller_is_admin = auth_user.user.is_admin();
+
+    // Solo el propio usuario o un admin pueden actualizar
+    if auth_user.user.id != id && !caller_is_admin {
+        return Err(api_error(StatusCode::FORBIDDEN, "forbidden", "No tienes permisos para esta acción"));
+    }
+
+    // Durante una suplantación no se cambian credenciales ni identidad del usuario
+    if auth_user.is_impersonated() && request.has_identity_fields() {
+        tracing::warn!(
+            event = "impersonation_denied",
+            user_id = auth_user.user.id,
+            impersonated_by = ?auth_user.impersonated_by,
+            "🎭 Cambio de credenciales o identidad rechazado durante una suplantación"
+        );
+        return Err(api_error(
+            StatusCode::FORBIDDEN,
+            "impersonation_not_allowed",
+            "Acción no permitida durante una suplantación",
+        ));
+    }
+
+    // La contraseña tiene su propio endpoint, que exige la actual, invalida los tokens
+    // y cierra las demás sesiones
+    if request.password.is_some() {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "password_not_updatable",
+            "La contraseña se cambia con POST /api/v1/auth/change-password",
+        ));
+    }
+
+    if request.has_privileged_fields() && !caller_is_admin {
+        tracing::warn!(
+            event = "privileged_fields_ignored",
+            user_id = auth_user.user.id,
+            target_user_id = id,
+            "⚠️ Campos privilegiados ignorados en actualización de usuario"
+        );
+    }
+
+    let changes = request.allowed_changes(caller_is_admin);
+
+    // Validar campos
+    let name = changes.name.map(|n| n.trim().to_string());
+    if name.as_deref() == Some("") {
+        return Err(api_error(StatusCode::BAD_REQUEST, "invalid_name", "El nombre es requerido"));
+    }
+
+    let email = changes.email.map(|e| e.trim().to_lowercase());
+    if let Some(ref email) = email {
+        if email.is_empty() || !email.contains('@') {
+            return Err(api_error(StatusCode::BAD_REQUEST, "invalid_email", "Email inválido"));
+        }
+
+        // Mismo código que en el registro
+        if crate::validation::denylist().is_disposable(email) {
+            let error = AuthError::disposable_email();
+            return Err(api_error(StatusCode::BAD_REQUEST, &error.error, &error.message));
+        }
+
+        // El email identifica la cuenta (login, recuperación): igual que la contraseña,
+        // no se cambia con un token de "recordarme"
+        if auth_user.claims.rem {
+            let error = AuthError::fresh_login_required();
+            return Err(api_error(StatusCode::FORBIDDEN, &error.error, &error.message));
+        }
+    }
+
+    // Un cambio de rol sube token_version: los tokens emitidos llevan el rol anterior
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET
+            name = COALESCE($1, name),
+            email = COALESCE($2, email),
+            role = COALESCE($3, role),
+            is_admin = (COALESCE($3, role) = 'admin'),
+            is_active = COALESCE($4, is_active),
+            token_version = token_version + (COALESCE($3, role) <> role)::int
+         WHERE id = $5
+         RETURNING id, name, email, password_hash, role, is_active, token_version, username, created_at, updated_at"
+    )
+    .bind(name)
+    .bind(email)
+    .bind(changes.role.map(|role| role.as_str()))
+    .bind(changes.is_active)
+    .bind(id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db_error) if db_error.is_unique_violation() => {
+            api_error(StatusCode::CONFLICT, "email_exists", "Este email ya está registrado")
+        }
+        _ => api_error(StatusCode::INTERNAL_SERVER_ERROR, "database_error", "Error de base de datos"),
+    })?
+    .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "user_not_found", "Usuario no encontrado"))?;
+
+    Ok(Json(json!(user.to_public().with_display(display_timezone))))
+}
+
+fn api_error(status: StatusCode, error: &str, message: &str) -> ApiError {
+    (status, Json(json!({ "error": error, "message": message })))
+}
+
+// POST /api/v1/users/:id/revoke-tokens (solo admin)
+// Sube token_version: todos los tokens del usuario dejan de servir al instante
+pub async fn revoke_user_tokens(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    auth_user: AuthUser,
 ) -> Result<Json<Value>, StatusCode> {
-    let user = sqlx::query!("SELECT id, name, email FROM users WHERE id = $1", id)
-        .fetch_optional(&pool)
+    if !auth_user.user.is_admin() {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let mut tx = pool.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let token_version: i32 = sqlx::query_scalar(
+        "UPDATE users SET token_version = token_version + 1 WHERE id = $1 RETURNING token_version"
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    crate::auth::sessions::revoke_all(&mut *tx, id, None)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    match user {
-        Some(user) => Ok(Json(json!({
-            "id": user.id,
-            "name": user.name,
-            "email": user.email
-        }))),
-        None => Err(StatusCode::NOT_FOUND)
+    crate::audit::record(
+        &mut *tx,
+        auth_user.user.id,
+        "user_tokens_revoke",
+        Some(id),
+        json!({ "token_version": token_version }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tracing::warn!(
+        event = "user_tokens_revoked",
+        admin_id = auth_user.user.id,
+        target_user_id = id,
+        token_version = token_version,
+        "🔒 Tokens del usuario invalidados"
+    );
+
+    Ok(Json(json!({
+        "message": "Todos los tokens del usuario fueron invalidados",
+        "user_id": id,
+        "token_version": token_version
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+    use crate::models::user::Role;
+    use crate::test_support::{unique_email, TestApp, PASSWORD};
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn users_list_is_capped() {
+        let app = TestApp::spawn().await;
+        let mut inserted = Vec::new();
+        for _ in 0..5 {
+            inserted.push(app.insert_user(&unique_email("lista"), "sin-hash", Role::User).await);
+        }
+
+        // Orden estable por id: siempre los primeros
+        let users = super::list_users(&app.pool, 3).await.unwrap();
+        let ids: Vec<i32> = users.iter().map(|u| u.id).collect();
+        assert_eq!(ids, inserted[..3]);
+
+        assert_eq!(super::list_users(&app.pool, 5).await.unwrap().len(), 5);
+        assert_eq!(super::list_users(&app.pool, 10).await.unwrap().len(), 5);
+
+        // La ruta usa el tope configurado (1000 por defecto)
+        let (status, _, body) = app.request(Method::GET, "/api/v1/users", None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.as_array().unwrap().len(), 5);
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn revoking_tokens_makes_old_tokens_stale() {
+        let app = TestApp::spawn().await;
+        let (_, admin_token) = app.user_with_role(Role::Admin).await;
+        let email = unique_email("revocado");
+        let (user_id, token) = app.register(&email, None).await;
+        let uri = format!("/api/v1/users/{}/revoke-tokens", user_id);
+
+        // Solo un admin puede invalidar tokens ajenos
+        let (status, _, _) = app.request(Method::POST, &uri, Some(&token), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _, body) = app.request(Method::POST, &uri, Some(&admin_token), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["token_version"], 2);
+
+        // El token emitido con token_version 1 ya no sirve
+        let (status, _, body) = app.request(Method::GET, "/api/v1/auth/sessions", Some(&token), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "token_revoked");
+
+        // Un login nuevo emite tokens con la versión actual
+        let (status, body) = app.login(&email, PASSWORD).await;
+        assert_eq!(status, StatusCode::OK);
+        let fresh = body["token"].as_str().unwrap();
+        let (status, _, _) = app.request(Method::GET, "/api/v1/auth/sessions", Some(fresh), None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        app.finish().await;
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn public_user_view_hides_email() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("publico");
+        let (user_id, _) = app.register(&email, Some("anaquispe")).await;
+
+        let (status, _, body) = app.request(Method::GET, &format!("/api/v1/users/{}", user_id), None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["id"], user_id);
+        assert_eq!(body["name"], "Ana Quispe");
+        assert!(body["member_since"].is_string());
+        assert_eq!(body.as_object().unwrap().len(), 3, "{}", body);
+        assert!(!body.to_string().contains(&email));
+
+        let (status, _, _) = app.request(Method::GET, "/api/v1/users/999999", None, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn email_change_rejects_disposable_domains_and_remember_me() {
+        let app = TestApp::spawn().await;
+        let email = unique_email("correo");
+        let (user_id, token) = app.register(&email, None).await;
+        let uri = format!("/api/v1/users/{}", user_id);
+
+        let (status, _, body) = app
+            .request(Method::PATCH, &uri, Some(&token), Some(json!({ "email": "ana@mailinator.com" })))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "disposable_email");
+
+        let (_, _, body) = app
+            .request(
+                Method::POST,
+                "/api/v1/auth/login",
+                None,
+                Some(json!({ "identifier": email, "password": PASSWORD, "remember_me": true })),
+            )
+            .await;
+        let remembered = body["token"].as_str().unwrap();
+        let new_email = unique_email("nuevo");
+        let (status, _, body) = app
+            .request(Method::PATCH, &uri, Some(remembered), Some(json!({ "email": new_email })))
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["error"], "fresh_login_required");
+
+        // El nombre sí se puede cambiar con ese token
+        let (status, _, _) = app
+            .request(Method::PATCH, &uri, Some(remembered), Some(json!({ "name": "Ana María" })))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _, body) = app
+            .request(Method::PATCH, &uri, Some(&token), Some(json!({ "email": new_email })))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["email"], new_email.as_str());
+
+        app.finish().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn non_admin_cannot_promote_themselves() {
+        let app = TestApp::spawn().await;
+        let (user_id, token) = app.register(&unique_email("ascenso"), None).await;
+
+        let (status, _, body) = app
+            .request(
+                Method::PATCH,
+                &format!("/api/v1/users/{}", user_id),
+                Some(&token),
+                Some(json!({ "is_admin": true, "role": "admin", "is_active": false })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["role"], "user");
+        assert_eq!(body["is_admin"], false);
+
+        let (role, is_admin, is_active): (String, bool, bool) =
+            sqlx::query_as("SELECT role, is_admin, is_active FROM users WHERE id = $1")
+                .bind(user_id)
+                .fetch_one(&app.pool)
+                .await
+                .unwrap();
+        assert_eq!((role.as_str(), is_admin, is_active), ("user", false, true));
+
+        app.finish().await;
+    }
+}
@@ -1,12 +1,25 @@
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
 use sysinfo::System;
 use chrono::{DateTime, Utc};
+use crate::database::SchemaCompatibility;
+use crate::health::status_page::{self, HealthTransition};
+use crate::health::warmup::{self, WarmupItem, WarmupState};
+use crate::health::watchdog::{ProcessResources, ResourceWatchdog};
+
+// Días de historial mostrados en la página de estado
+const STATUS_PAGE_DAYS: i64 = 90;
+// Tiempo que se reutiliza la respuesta de la página de estado
+const STATUS_PAGE_CACHE_TTL: Duration = Duration::from_secs(60);
+// Máximo de transiciones pendientes si la base de datos no está disponible
+const MAX_PENDING_TRANSITIONS: usize = 100;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HealthCheckResponse {
     pub status: String,
+    #[serde(with = "crate::models::timestamp")]
     pub timestamp: DateTime<Utc>,
     pub uptime_seconds: u64,
     pub version: String,
@@ -14,6 +27,7 @@ pub struct HealthCheckResponse {
     pub checks: HealthChecks,
     pub system: SystemMetrics,
     pub database: DatabaseHealth,
+    pub schema: Option<SchemaCompatibility>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -42,6 +56,7 @@ pub struct SystemMetrics {
     pub disk_used_gb: f64,
     pub disk_available_gb: f64,
     pub load_average: Vec<f64>,
+    pub process: ProcessResources,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -58,6 +73,12 @@ pub struct DatabaseHealth {
 pub struct HealthChecker {
     start_time: Instant,
     pool: PgPool,
+    last_status: Mutex<Option<String>>,
+    pending_transitions: Mutex<Vec<HealthTransition>>,
+    status_page_cache: RwLock<Option<(Instant, serde_json::Value)>>,
+    warmup: RwLock<WarmupState>,
+    watchdog: ResourceWatchdog,
+    schema: RwLock<Option<SchemaCompatibility>>,
 }
 
 impl HealthChecker {
@@ -65,6 +86,12 @@ impl HealthChecker {
         Self {
             start_time: Instant::now(),
             pool,
+            last_status: Mutex::new(None),
+            pending_transitions: Mutex::new(Vec::new()),
+            status_page_cache: RwLock::new(None),
+            warmup: RwLock::new(WarmupState::default()),
+            watchdog: ResourceWatchdog::default(),
+            schema: RwLock::new(None),
         }
     }
 
@@ -89,6 +116,9 @@ impl HealthChecker {
             &memory_check,
         ]);
         
+        // Guardar historial de cambios de estado para la página de estado
+        self.record_transition(&overall_status).await;
+        
         HealthCheckResponse {
             status: overall_status,
             timestamp,
@@ -103,6 +133,7 @@ impl HealthChecker {
             },
             system: system_metrics,
             database: database_health,
+            schema: self.schema.read().unwrap_or_else(|e| e.into_inner()).clone(),
         }
     }
 
@@ -110,25 +141,224 @@ impl HealthChecker {
     pub async fn check_liveness(&self) -> serde_json::Value {
         serde_json::json!({
             "status": "alive",
-            "timestamp": Utc::now(),
+            "timestamp": crate::models::timestamp::now(),
             "uptime_seconds": self.start_time.elapsed().as_secs()
         })
     }
 
-    // Check para readiness probe
+    // Check para readiness probe (requiere warm-up crítico completado)
     pub async fn check_readiness(&self) -> (bool, serde_json::Value) {
         let db_check = self.check_database().await;
-        let is_ready = db_check.status == "healthy";
+        let warmup_ready = self.warmup.read().unwrap_or_else(|e| e.into_inner()).is_ready();
+        let draining = self.watchdog.is_draining();
+        let schema_blocked = self
+            .schema
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_some_and(|schema| schema.blocks_readiness());
+        let is_ready = db_check.status == "healthy" && warmup_ready && !draining && !schema_blocked;
         
         let response = serde_json::json!({
             "status": if is_ready { "ready" } else { "not_ready" },
-            "timestamp": Utc::now(),
-            "database": db_check
+            "timestamp": crate::models::timestamp::now(),
+            "database": db_check,
+            "warmup_ready": warmup_ready,
+            "draining": draining,
+            "schema_compatible": !schema_blocked
         });
         
         (is_ready, response)
     }
 
+    // Check solo de base de datos (para alertas específicas)
+    pub async fn check_database_health(&self) -> (bool, serde_json::Value) {
+        let check = self.check_database().await;
+        let database = self.get_database_health().await;
+        let is_healthy = check.status == "healthy";
+        
+        let response = serde_json::json!({
+            "status": check.status,
+            "timestamp": crate::models::timestamp::now(),
+            "check": check,
+            "database": database
+        });
+        
+        (is_healthy, response)
+    }
+
+    // Check para startup probe: estado del warm-up por elemento
+    pub fn check_startup(&self) -> (bool, serde_json::Value) {
+        let warmup = self.warmup.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let started = warmup.is_ready();
+        
+        let response = serde_json::json!({
+            "status": if started { "started" } else if warmup.completed { "failed" } else { "starting" },
+            "timestamp": crate::models::timestamp::now(),
+            "warmup": warmup
+        });
+        
+        (started, response)
+    }
+
+    // Resultado de la verificación de compatibilidad del esquema (al iniciar)
+    pub fn set_schema_compatibility(&self, result: SchemaCompatibility) {
+        *self.schema.write().unwrap_or_else(|e| e.into_inner()) = Some(result);
+    }
+
+    // Watchdog de recursos del proceso (RSS y descriptores abiertos)
+    pub async fn run_watchdog(self: Arc<Self>) {
+        self.watchdog.run().await
+    }
+
+    // Se resuelve cuando el watchdog pide reiniciar el proceso
+    pub async fn shutdown_requested(&self) {
+        self.watchdog.shutdown_requested().await
+    }
+
+    // Precargar caches críticas al iniciar, antes de marcar el servicio como ready
+    pub async fn run_warmup(self: Arc<Self>) {
+        let pool = self.pool.clone();
+        let status_checker = self.clone();
+        
+        let items = vec![
+            WarmupItem {
+                name: "database_pool",
+                critical: true,
+                task: Box::pin(async move {
+                    sqlx::query("SELECT 1")
+                        .execute(&pool)
+                        .await
+                        .map(|_| ())
+                        .map_err(|e| e.to_string())
+                }),
+            },
+            WarmupItem {
+                name: "disposable_email_domains",
+                critical: false,
+                task: Box::pin(async move {
+                    tokio::task::spawn_blocking(|| crate::validation::denylist().domain_count())
+                        .await
+                        .map(|_| ())
+                        .map_err(|e| e.to_string())
+                }),
+            },
+            WarmupItem {
+                name: "status_page",
+                critical: false,
+                task: Box::pin(async move {
+                    status_checker.status_page().await;
+                    Ok(())
+                }),
+            },
+        ];
+        
+        let state = warmup::run(items).await;
+        tracing::info!(
+            ready = state.is_ready(),
+            items = state.items.len(),
+            "🔥 Warm-up de inicio finalizado"
+        );
+        *self.warmup.write().unwrap_or_else(|e| e.into_inner()) = state;
+    }
+
+    // Datos públicos para la página de estado (sin detalles sensibles)
+    //
+    // {
+    //   "status": "healthy" | "degraded" | "unhealthy",
+    //   "components": { "api": "healthy", "database": "healthy" | "unhealthy" },
+    //   "days": [{ "date": "YYYY-MM-DD", "uptime_percent": 99.9 | null }],  // 90 días, del más antiguo al más reciente
+    //   "incidents": [{ "status", "started_at", "ended_at" | null, "duration_seconds" }],
+    //   "generated_at": RFC3339
+    // }
+    pub async fn status_page(&self) -> serde_json::Value {
+        if let Some((generated, cached)) = self.status_page_cache.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            if generated.elapsed() < STATUS_PAGE_CACHE_TTL {
+                return cached.clone();
+            }
+        }
+        
+        let api_check = self.check_api().await;
+        let db_check = self.check_database().await;
+        let overall_status = self.determine_overall_status(&[&api_check, &db_check]);
+        
+        let (days, incidents) = match status_page::build_history(&self.pool, STATUS_PAGE_DAYS).await {
+            Ok(history) => history,
+            Err(e) => {
+                tracing::warn!(error = %e, "⚠️ No se pudo leer el historial de salud");
+                (Vec::new(), Vec::new())
+            }
+        };
+        
+        let response = serde_json::json!({
+            "status": overall_status,
+            "components": {
+                "api": api_check.status,
+                "database": db_check.status
+            },
+            "days": days,
+            "incidents": incidents,
+            "generated_at": crate::models::timestamp::now()
+        });
+        
+        *self.status_page_cache.write().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), response.clone()));
+        response
+    }
+
+    // Muestrear el estado general periódicamente (HEALTH_SAMPLE_INTERVAL_SECS, por defecto 60)
+    // para que el historial de la página de estado avance aunque nadie consulte /health
+    pub async fn run_status_sampler(self: Arc<Self>) {
+        let interval_secs = std::env::var("HEALTH_SAMPLE_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60u64)
+            .max(1);
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            let api_check = self.check_api().await;
+            let db_check = self.check_database().await;
+            let disk_check = self.check_disk_space().await;
+            let memory_check = self.check_memory().await;
+            let overall_status = self.determine_overall_status(&[
+                &api_check,
+                &db_check,
+                &disk_check,
+                &memory_check,
+            ]);
+            self.record_transition(&overall_status).await;
+        }
+    }
+
+    // Registrar un cambio del estado general y persistir las transiciones pendientes
+    async fn record_transition(&self, status: &str) {
+        {
+            let mut last_status = self.last_status.lock().unwrap_or_else(|e| e.into_inner());
+            if last_status.as_deref() == Some(status) {
+                return;
+            }
+            *last_status = Some(status.to_string());
+            
+            let mut pending = self.pending_transitions.lock().unwrap_or_else(|e| e.into_inner());
+            pending.push(HealthTransition {
+                status: status.to_string(),
+                at: Utc::now(),
+            });
+            if pending.len() > MAX_PENDING_TRANSITIONS {
+                let overflow = pending.len() - MAX_PENDING_TRANSITIONS;
+                pending.drain(0..overflow);
+            }
+        }
+        
+        let pending = std::mem::take(&mut *self.pending_transitions.lock().unwrap_or_else(|e| e.into_inner()));
+        let failed = status_page::persist_transitions(&self.pool, pending).await;
+        if !failed.is_empty() {
+            let mut pending = self.pending_transitions.lock().unwrap_or_else(|e| e.into_inner());
+            let newer = std::mem::replace(&mut *pending, failed);
+            pending.extend(newer);
+        }
+    }
+
     // Verificación de API
     async fn check_api(&self) -> CheckStatus {
         CheckStatus {
@@ -271,6 +501,7 @@ impl HealthChecker {
             disk_used_gb: disk_used as f64 / (1024.0 * 1024.0 * 1024.0),
             disk_available_gb: disk_available as f64 / (1024.0 * 1024.0 * 1024.0),
             load_average,
+            process: self.watchdog.snapshot(),
         }
     }
 
@@ -1,4 +1,7 @@
 pub mod checks;
+pub mod status_page;
+pub mod warmup;
+pub mod watchdog;
 
 pub use checks::{
     HealthChecker,
@@ -7,4 +10,4 @@ pub use checks::{
     CheckStatus,
     SystemMetrics,
     DatabaseHealth,
-};
\ No newline at end of file
+};
@@ -0,0 +1,141 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+// Día del historial de la página de estado
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusPageDay {
+    pub date: NaiveDate,
+    // None cuando no hay datos registrados para ese día
+    pub uptime_percent: Option<f64>,
+}
+
+// Incidente: periodo en que el estado general no fue "healthy"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusPageIncident {
+    pub status: String,
+    #[serde(with = "crate::models::timestamp")]
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<String>,
+    pub duration_seconds: i64,
+}
+
+// Transición de estado pendiente de persistir
+#[derive(Debug, Clone)]
+pub struct HealthTransition {
+    pub status: String,
+    pub at: DateTime<Utc>,
+}
+
+// Guardar transiciones en orden; devuelve las que no se pudieron guardar
+pub async fn persist_transitions(
+    pool: &PgPool,
+    transitions: Vec<HealthTransition>,
+) -> Vec<HealthTransition> {
+    let mut remaining = transitions.into_iter();
+
+    while let Some(transition) = remaining.next() {
+        let result = sqlx::query("INSERT INTO health_transitions (status, created_at) VALUES ($1, $2)")
+            .bind(&transition.status)
+            .bind(transition.at)
+            .execute(pool)
+            .await;
+
+        if let Err(e) = result {
+            tracing::debug!(error = %e, "⚠️ No se pudo guardar la transición de salud, se reintentará");
+            return std::iter::once(transition).chain(remaining).collect();
+        }
+    }
+
+    Vec::new()
+}
+
+// Calcular uptime diario e incidentes de los últimos `days` días
+pub async fn build_history(
+    pool: &PgPool,
+    days: i64,
+) -> Result<(Vec<StatusPageDay>, Vec<StatusPageIncident>), sqlx::Error> {
+    let now = Utc::now();
+    let first_day = now.date_naive() - Duration::days(days - 1);
+    let window_start = first_day.and_hms_opt(0, 0, 0).unwrap().and_utc();
+
+    // Último estado conocido antes de la ventana
+    let previous = sqlx::query_as::<_, (String, DateTime<Utc>)>(
+        "SELECT status, created_at FROM health_transitions
+         WHERE created_at < $1 ORDER BY created_at DESC LIMIT 1"
+    )
+    .bind(window_start)
+    .fetch_optional(pool)
+    .await?;
+
+    let rows = sqlx::query_as::<_, (String, DateTime<Utc>)>(
+        "SELECT status, created_at FROM health_transitions
+         WHERE created_at >= $1 ORDER BY created_at"
+    )
+    .bind(window_start)
+    .fetch_all(pool)
+    .await?;
+
+    // Segmentos (inicio, fin, estado) continuos desde la primera transición conocida
+    let mut transitions: Vec<(String, DateTime<Utc>)> = Vec::with_capacity(rows.len() + 1);
+    if let Some((status, _)) = previous {
+        transitions.push((status, window_start));
+    }
+    transitions.extend(rows);
+
+    let segments: Vec<(DateTime<Utc>, DateTime<Utc>, &str)> = transitions
+        .iter()
+        .enumerate()
+        .map(|(i, (status, start))| {
+            let end = transitions.get(i + 1).map(|(_, at)| *at).unwrap_or(now);
+            (*start, end, status.as_str())
+        })
+        .collect();
+
+    let daily = (0..days)
+        .map(|offset| {
+            let date = first_day + Duration::days(offset);
+            let day_start = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+            let day_end = (day_start + Duration::days(1)).min(now);
+
+            let mut covered = 0i64;
+            let mut down = 0i64;
+            for (start, end, status) in &segments {
+                let overlap = ((*end).min(day_end) - (*start).max(day_start)).num_seconds();
+                if overlap > 0 {
+                    covered += overlap;
+                    if *status == "unhealthy" {
+                        down += overlap;
+                    }
+                }
+            }
+
+            StatusPageDay {
+                date,
+                uptime_percent: if covered > 0 {
+                    Some(((covered - down) as f64 / covered as f64) * 100.0)
+                } else {
+                    None
+                },
+            }
+        })
+        .collect();
+
+    let incidents = segments
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, _, status))| *status != "healthy")
+        .map(|(i, (start, end, status))| StatusPageIncident {
+            status: status.to_string(),
+            started_at: *start,
+            ended_at: if i + 1 < segments.len() {
+                Some(crate::models::timestamp::format(end))
+            } else {
+                None
+            },
+            duration_seconds: (*end - *start).num_seconds(),
+        })
+        .collect();
+
+    Ok((daily, incidents))
+}
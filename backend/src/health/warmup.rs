@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+// Tiempo máximo por elemento de warm-up
+const WARMUP_ITEM_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub type WarmupFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+
+// Elemento a precargar al iniciar
+pub struct WarmupItem {
+    pub name: &'static str,
+    // Si falla un elemento crítico el servicio no pasa a "ready"
+    pub critical: bool,
+    pub task: WarmupFuture,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmupItemResult {
+    pub name: String,
+    pub critical: bool,
+    pub status: String,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WarmupState {
+    pub completed: bool,
+    pub critical_ok: bool,
+    pub items: Vec<WarmupItemResult>,
+}
+
+impl WarmupState {
+    pub fn is_ready(&self) -> bool {
+        self.completed && self.critical_ok
+    }
+}
+
+// Ejecutar todos los elementos en paralelo, cada uno con su timeout
+pub async fn run(items: Vec<WarmupItem>) -> WarmupState {
+    let handles: Vec<_> = items
+        .into_iter()
+        .map(|item| {
+            let name = item.name;
+            let critical = item.critical;
+            let handle = tokio::spawn(async move {
+                let start = Instant::now();
+                let result = tokio::time::timeout(WARMUP_ITEM_TIMEOUT, item.task).await;
+                (start.elapsed().as_millis() as u64, result)
+            });
+            (name, critical, handle)
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(handles.len());
+    for (name, critical, handle) in handles {
+        let (status, duration_ms, error) = match handle.await {
+            Ok((duration_ms, Ok(Ok(())))) => ("ok", duration_ms, None),
+            Ok((duration_ms, Ok(Err(e)))) => ("failed", duration_ms, Some(e)),
+            Ok((duration_ms, Err(_))) => ("timeout", duration_ms, Some("Tiempo de espera agotado".to_string())),
+            Err(e) => ("failed", 0, Some(e.to_string())),
+        };
+
+        if status == "ok" {
+            tracing::info!(item = name, duration_ms = duration_ms, "🔥 Warm-up completado");
+        } else if critical {
+            tracing::error!(item = name, status = status, error = ?error, "🚨 Warm-up crítico fallido");
+        } else {
+            tracing::warn!(item = name, status = status, error = ?error, "⚠️ Warm-up no crítico fallido, continuando");
+        }
+
+        results.push(WarmupItemResult {
+            name: name.to_string(),
+            critical,
+            status: status.to_string(),
+            duration_ms,
+            error,
+        });
+    }
+
+    WarmupState {
+        completed: true,
+        critical_ok: results.iter().all(|r| !r.critical || r.status == "ok"),
+        items: results,
+    }
+}
@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+use sysinfo::{Pid, ProcessRefreshKind, System};
+use tokio::sync::Notify;
+use crate::config::app_config;
+
+// Configuración del watchdog de recursos del proceso
+//   RESOURCE_WATCHDOG_INTERVAL_SECS=30   intervalo de muestreo
+//   PROCESS_RSS_WARN_MB=1024             aviso por memoria residente
+//   PROCESS_RSS_LIMIT_MB=2048            límite duro de memoria residente
+//   PROCESS_FDS_WARN=4096                aviso por descriptores abiertos
+//   PROCESS_FDS_LIMIT=8192               límite duro de descriptores abiertos
+//   WATCHDOG_SELF_RESTART=true           al superar un límite duro: not_ready + apagado ordenado
+//                                        (desactivado por defecto en desarrollo)
+//   WATCHDOG_DRAIN_SECS=10               espera entre not_ready y el apagado
+// Recargable en caliente: el watchdog lee la configuración vigente en cada muestreo
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchdogConfig {
+    pub interval: Duration,
+    pub rss_warn_mb: u64,
+    pub rss_limit_mb: u64,
+    pub fds_warn: u64,
+    pub fds_limit: u64,
+    pub self_restart: bool,
+    pub drain: Duration,
+}
+
+fn var_or<T: std::str::FromStr>(var: &impl Fn(&str) -> Option<String>, name: &str, default: T) -> T {
+    var(name)
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+impl WatchdogConfig {
+    // Construir la configuración leyendo cada variable con `var` (ver config::AppConfig)
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let environment = var("ENVIRONMENT").unwrap_or_else(|| "development".to_string());
+
+        Self {
+            interval: Duration::from_secs(var_or(&var, "RESOURCE_WATCHDOG_INTERVAL_SECS", 30u64).max(1)),
+            rss_warn_mb: var_or(&var, "PROCESS_RSS_WARN_MB", 1024),
+            rss_limit_mb: var_or(&var, "PROCESS_RSS_LIMIT_MB", 2048),
+            fds_warn: var_or(&var, "PROCESS_FDS_WARN", 4096),
+            fds_limit: var_or(&var, "PROCESS_FDS_LIMIT", 8192),
+            self_restart: var_or(&var, "WATCHDOG_SELF_RESTART", environment != "development"),
+            drain: Duration::from_secs(var_or(&var, "WATCHDOG_DRAIN_SECS", 10)),
+        }
+    }
+
+    // Variables definidas con un valor que no se puede interpretar (from_vars las ignora)
+    pub fn var_errors(var: impl Fn(&str) -> Option<String>) -> Vec<String> {
+        let numeric = [
+            "RESOURCE_WATCHDOG_INTERVAL_SECS",
+            "PROCESS_RSS_WARN_MB",
+            "PROCESS_RSS_LIMIT_MB",
+            "PROCESS_FDS_WARN",
+            "PROCESS_FDS_LIMIT",
+            "WATCHDOG_DRAIN_SECS",
+        ]
+        .into_iter()
+        .filter(|name| var(name).is_some_and(|v| v.parse::<u64>().is_err()));
+        let boolean = std::iter::once("WATCHDOG_SELF_RESTART")
+            .filter(|name| var(name).is_some_and(|v| v.parse::<bool>().is_err()));
+
+        numeric
+            .chain(boolean)
+            .map(|name| format!("{}: valor inválido", name))
+            .collect()
+    }
+
+    // Coherencia entre umbrales: el aviso no puede superar al límite duro
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.rss_warn_mb > self.rss_limit_mb {
+            errors.push(format!(
+                "watchdog: rss_warn_mb ({}) mayor que rss_limit_mb ({})",
+                self.rss_warn_mb, self.rss_limit_mb
+            ));
+        }
+        if self.fds_warn > self.fds_limit {
+            errors.push(format!(
+                "watchdog: fds_warn ({}) mayor que fds_limit ({})",
+                self.fds_warn, self.fds_limit
+            ));
+        }
+        errors
+    }
+}
+
+// Uso de recursos del proceso expuesto en /health
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessResources {
+    pub rss_mb: u64,
+    pub open_fds: Option<u64>,
+    pub draining: bool,
+}
+
+// Estado compartido del watchdog (lo lee readiness y lo espera el servidor)
+#[derive(Debug, Default)]
+pub struct ResourceWatchdog {
+    rss_bytes: AtomicU64,
+    open_fds: AtomicU64,
+    fds_available: AtomicBool,
+    draining: AtomicBool,
+    shutdown: Notify,
+}
+
+impl ResourceWatchdog {
+    pub fn snapshot(&self) -> ProcessResources {
+        ProcessResources {
+            rss_mb: self.rss_bytes.load(Ordering::Relaxed) / 1024 / 1024,
+            open_fds: self
+                .fds_available
+                .load(Ordering::Relaxed)
+                .then(|| self.open_fds.load(Ordering::Relaxed)),
+            draining: self.is_draining(),
+        }
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+
+    // Se resuelve cuando el watchdog decide apagar el servidor
+    pub async fn shutdown_requested(&self) {
+        self.shutdown.notified().await
+    }
+
+    pub async fn run(&self) {
+        let pid = sysinfo::get_current_pid().ok();
+        let mut system = System::new();
+
+        loop {
+            let config = app_config().watchdog.clone();
+
+            let rss_bytes = pid
+                .and_then(|pid| current_rss(&mut system, pid))
+                .unwrap_or(0);
+            let open_fds = count_open_fds();
+
+            self.rss_bytes.store(rss_bytes, Ordering::Relaxed);
+            self.open_fds.store(open_fds.unwrap_or(0), Ordering::Relaxed);
+            self.fds_available.store(open_fds.is_some(), Ordering::Relaxed);
+
+            let rss_mb = rss_bytes / 1024 / 1024;
+            let over_limit = rss_mb >= config.rss_limit_mb
+                || open_fds.is_some_and(|fds| fds >= config.fds_limit);
+
+            if over_limit {
+                tracing::error!(
+                    event = "resource_limit_exceeded",
+                    rss_mb = rss_mb,
+                    open_fds = ?open_fds,
+                    rss_limit_mb = config.rss_limit_mb,
+                    fds_limit = config.fds_limit,
+                    self_restart = config.self_restart,
+                    "🚨 Límite duro de recursos superado"
+                );
+
+                if config.self_restart && !self.is_draining() {
+                    self.begin_drain(config.drain).await;
+                    return;
+                }
+            } else if rss_mb >= config.rss_warn_mb || open_fds.is_some_and(|fds| fds >= config.fds_warn) {
+                tracing::warn!(
+                    event = "resource_warning",
+                    rss_mb = rss_mb,
+                    open_fds = ?open_fds,
+                    rss_warn_mb = config.rss_warn_mb,
+                    fds_warn = config.fds_warn,
+                    "⚠️ Uso de recursos del proceso elevado"
+                );
+            }
+
+            tokio::time::sleep(config.interval).await;
+        }
+    }
+
+    // Marcar not_ready, dar tiempo al orquestador para retirar tráfico y pedir el apagado
+    async fn begin_drain(&self, drain: Duration) {
+        self.draining.store(true, Ordering::Relaxed);
+        tracing::warn!(
+            event = "watchdog_draining",
+            drain_secs = drain.as_secs(),
+            "🛑 Readiness en not_ready; apagado ordenado en breve"
+        );
+
+        tokio::time::sleep(drain).await;
+        self.shutdown.notify_one();
+    }
+}
+
+fn current_rss(system: &mut System, pid: Pid) -> Option<u64> {
+    system.refresh_process_specifics(pid, ProcessRefreshKind::new().with_memory());
+    system.process(pid).map(|process| process.memory())
+}
+
+// Descriptores abiertos (solo disponible en Linux)
+fn count_open_fds() -> Option<u64> {
+    std::fs::read_dir("/proc/self/fd")
+        .ok()
+        .map(|entries| entries.count() as u64)
+}
@@ -16,10 +16,15 @@ impl Logger {
     let env = env::var("ENVIRONMENT").unwrap_or_else(|_| "development".to_string());
     let log_level = env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string());
     
-    // Configuración super simple para debug
-    tracing_subscriber::fmt()
-        .with_env_filter(&log_level)
-        .with_target(false)
+    // Configuración super simple para debug; el filtro de nivel va solo en la
+    // salida para que la captura de queries (X-Debug-Queries) no dependa de RUST_LOG
+    tracing_subscriber::registry()
+        .with(
+            fmt::layer()
+                .with_target(false)
+                .with_filter(EnvFilter::new(&log_level)),
+        )
+        .with(crate::database::query_log::capture_layer())
         .init();
     
     tracing::info!(
@@ -129,6 +134,7 @@ impl Logger {
         memory_usage: u64,
         active_connections: usize,
         db_pool_size: u32,
+        reasons: &[&str],
     ) {
         tracing::info!(
             event = "system_metrics",
@@ -136,6 +142,7 @@ impl Logger {
             memory_usage_mb = %(memory_usage / 1024 / 1024),
             active_connections = %active_connections,
             db_pool_size = %db_pool_size,
+            reasons = %reasons.join(","),
             timestamp = %chrono::Utc::now().to_rfc3339(),
             "📊 Métricas del sistema"
         );
@@ -5,10 +5,13 @@ use axum::{
     http::{HeaderMap, StatusCode},
 };
 use std::time::Instant;
-use uuid::Uuid;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::OnceLock;
 use crate::logging::logger::Logger;
+use crate::logging::request_id::generate_request_id;
+use crate::logging::timing::{self, RequestTiming, SlowRequestSample};
 use crate::auth::middleware::AuthUser;
+use crate::envelope::{deprecations, Deprecations};
 
 // Extension para request ID único
 #[derive(Clone)]
@@ -32,7 +35,7 @@ pub async fn logging_middleware(
     let start_time = Instant::now();
     
     // Generar ID único para el request
-    let request_id = Uuid::new_v4().to_string();
+    let request_id = generate_request_id();
     let method = request.method().to_string();
     let path = request.uri().path().to_string();
     let query = request.uri().query().map(|q| q.to_string());
@@ -53,6 +56,8 @@ pub async fn logging_middleware(
         method: method.clone(),
         path: path.clone(),
     });
+    let timing = RequestTiming::default();
+    request.extensions_mut().insert(timing.clone());
     
     // Log del inicio del request
     tracing::info!(
@@ -81,6 +86,23 @@ pub async fn logging_middleware(
         .extensions()
         .get::<AuthUser>()
         .map(|auth| auth.user.id);
+    let impersonated_by = response
+        .extensions()
+        .get::<AuthUser>()
+        .and_then(|auth| auth.impersonated_by);
+    
+    // Contar requests que todavía usan funcionalidades deprecadas
+    if let Some(notices) = response.extensions().get::<Deprecations>() {
+        deprecations::record_usage(notices);
+        tracing::info!(
+            event = "deprecated_usage",
+            request_id = %request_id,
+            method = %method,
+            path = %path,
+            deprecations = ?notices.0,
+            "⏳ Request con funcionalidad deprecada"
+        );
+    }
     
     // Log estructurado del request completo
     Logger::log_request(
@@ -103,6 +125,7 @@ pub async fn logging_middleware(
         client_ip = %client_ip,
         user_agent = %user_agent,
         user_id = ?user_id,
+        impersonated_by = ?impersonated_by,
         response_size = ?get_response_size(&response),
         "✅ Request completado"
     );
@@ -121,6 +144,13 @@ pub async fn logging_middleware(
         headers.insert("x-response-time-ms", header_value);
     }
     
+    // Desglose por fase para el frontend (Server-Timing)
+    if timing::server_timing_enabled() {
+        if let Ok(header_value) = timing.breakdown(duration).server_timing().parse() {
+            headers.insert("server-timing", header_value);
+        }
+    }
+    
     response
 }
 
@@ -132,19 +162,17 @@ pub async fn slow_request_middleware(
     let start_time = Instant::now();
     let method = request.method().to_string();
     let path = request.uri().path().to_string();
+    let request_id = get_request_id(&request);
+    let timing = request.extensions().get::<RequestTiming>().cloned().unwrap_or_default();
     
     let response = next.run(request).await;
     
     let duration = start_time.elapsed();
     let duration_ms = duration.as_millis() as u64;
     
-    // Log warning para requests lentos (> 1 segundo)
+    // Log warning para requests lentos (> 1 segundo), con el desglose por fase
     if duration_ms > 1000 {
-        let request_id = response
-            .extensions()
-            .get::<RequestId>()
-            .map(|r| r.0.clone())
-            .unwrap_or_else(|| "unknown".to_string());
+        let breakdown = timing.breakdown(duration);
         
         tracing::warn!(
             event = "slow_request",
@@ -152,8 +180,21 @@ pub async fn slow_request_middleware(
             method = %method,
             path = %path,
             duration_ms = %duration_ms,
+            middleware_ms = breakdown.middleware_ms,
+            auth_ms = breakdown.auth_ms,
+            handler_ms = breakdown.handler_ms,
+            serialization_ms = breakdown.serialization_ms,
             "🐌 Request lento detectado"
         );
+        
+        timing::slow_requests().record(SlowRequestSample {
+            request_id,
+            method,
+            path,
+            status: response.status().as_u16(),
+            timing: breakdown,
+            timestamp: chrono::Utc::now(),
+        });
     }
     
     response
@@ -168,7 +209,7 @@ pub async fn error_handling_middleware(
         .extensions()
         .get::<RequestId>()
         .map(|r| r.0.clone())
-        .unwrap_or_else(|| Uuid::new_v4().to_string());
+        .unwrap_or_else(generate_request_id);
     
     let method = request.method().to_string();
     let path = request.uri().path().to_string();
@@ -222,20 +263,45 @@ pub async fn error_handling_middleware(
     }
 }
 
+// Proxies de confianza (TRUSTED_PROXIES, IPs separadas por coma); solo a ellos
+// se les aceptan los headers X-Forwarded-For / X-Real-IP
+fn trusted_proxies() -> &'static Vec<IpAddr> {
+    static TRUSTED: OnceLock<Vec<IpAddr>> = OnceLock::new();
+    TRUSTED.get_or_init(|| {
+        std::env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|ip| ip.trim().parse().ok())
+            .collect()
+    })
+}
+
 // Función auxiliar para obtener IP del cliente
-fn get_client_ip(headers: &HeaderMap, addr: &SocketAddr) -> String {
-    // Intentar obtener IP de headers de proxy
-    if let Some(forwarded_for) = headers.get("x-forwarded-for") {
-        if let Ok(forwarded_str) = forwarded_for.to_str() {
-            if let Some(first_ip) = forwarded_str.split(',').next() {
-                return first_ip.trim().to_string();
-            }
+pub fn get_client_ip(headers: &HeaderMap, addr: &SocketAddr) -> String {
+    client_ip(headers, addr, trusted_proxies())
+}
+
+fn client_ip(headers: &HeaderMap, addr: &SocketAddr, trusted: &[IpAddr]) -> String {
+    // Headers de proxy solo si la conexión directa viene de un proxy de confianza;
+    // de lo contrario cualquiera podría falsificar su IP
+    if !trusted.contains(&addr.ip()) {
+        return addr.ip().to_string();
+    }
+
+    // X-Forwarded-For: la primera IP desde la derecha que no sea un proxy propio
+    if let Some(forwarded_for) = headers.get("x-forwarded-for").and_then(|h| h.to_str().ok()) {
+        let hops: Vec<IpAddr> = forwarded_for
+            .split(',')
+            .filter_map(|ip| ip.trim().parse().ok())
+            .collect();
+        if let Some(client) = hops.iter().rev().find(|ip| !trusted.contains(ip)).or(hops.first()) {
+            return client.to_string();
         }
     }
     
-    if let Some(real_ip) = headers.get("x-real-ip") {
-        if let Ok(real_ip_str) = real_ip.to_str() {
-            return real_ip_str.to_string();
+    if let Some(real_ip) = headers.get("x-real-ip").and_then(|h| h.to_str().ok()) {
+        if let Ok(real_ip) = real_ip.trim().parse::<IpAddr>() {
+            return real_ip.to_string();
         }
     }
     
@@ -259,4 +325,46 @@ pub fn get_request_id(request: &Request) -> String {
         .get::<RequestId>()
         .map(|r| r.0.clone())
         .unwrap_or_else(|| "unknown".to_string())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(forwarded_for: Option<&str>, real_ip: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = forwarded_for {
+            headers.insert("x-forwarded-for", value.parse().unwrap());
+        }
+        if let Some(value) = real_ip {
+            headers.insert("x-real-ip", value.parse().unwrap());
+        }
+        headers
+    }
+
+    fn addr(ip: &str) -> SocketAddr {
+        SocketAddr::new(ip.parse().unwrap(), 40000)
+    }
+
+    #[test]
+    fn ignores_forwarded_headers_from_untrusted_peer() {
+        let trusted: Vec<IpAddr> = vec!["10.0.0.1".parse().unwrap()];
+        let spoofed = headers(Some("1.2.3.4"), Some("5.6.7.8"));
+
+        assert_eq!(client_ip(&spoofed, &addr("203.0.113.9"), &trusted), "203.0.113.9");
+        assert_eq!(client_ip(&spoofed, &addr("203.0.113.9"), &[]), "203.0.113.9");
+    }
+
+    #[test]
+    fn uses_forwarded_for_from_trusted_proxy() {
+        let trusted: Vec<IpAddr> = vec!["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()];
+
+        // El cliente puede anteponer IPs falsas; cuenta la primera no confiable desde la derecha
+        let forwarded = headers(Some("1.2.3.4, 198.51.100.7, 10.0.0.2"), None);
+        assert_eq!(client_ip(&forwarded, &addr("10.0.0.1"), &trusted), "198.51.100.7");
+
+        let real_ip = headers(None, Some("198.51.100.8"));
+        assert_eq!(client_ip(&real_ip, &addr("10.0.0.1"), &trusted), "198.51.100.8");
+
+        assert_eq!(client_ip(&HeaderMap::new(), &addr("10.0.0.1"), &trusted), "10.0.0.1");
+    }
+}
@@ -1,5 +1,8 @@
 pub mod logger;
 pub mod middleware;
+pub mod request_id;
+pub mod system_metrics;
+pub mod timing;
 
 pub use logger::Logger;
 pub use middleware::{
@@ -9,4 +12,6 @@ pub use middleware::{
     RequestId,
     RequestMetrics,
     get_request_id,
-};
\ No newline at end of file
+};
+pub use request_id::generate_request_id;
+pub use system_metrics::{SystemMetricsLogConfig, SystemMetricsReporter, SystemMetricsSample};
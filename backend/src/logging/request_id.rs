@@ -0,0 +1,39 @@
+use std::sync::OnceLock;
+use ulid::Ulid;
+use uuid::Uuid;
+
+// Formato de los request IDs (REQUEST_ID_FORMAT=uuid-v4|uuid-v7|ulid)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RequestIdFormat {
+    UuidV4,
+    UuidV7,
+    Ulid,
+}
+
+impl RequestIdFormat {
+    pub fn from_env() -> Self {
+        match std::env::var("REQUEST_ID_FORMAT")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "uuid-v7" | "uuidv7" | "v7" => Self::UuidV7,
+            "ulid" => Self::Ulid,
+            _ => Self::UuidV4,
+        }
+    }
+}
+
+fn format() -> RequestIdFormat {
+    static FORMAT: OnceLock<RequestIdFormat> = OnceLock::new();
+    *FORMAT.get_or_init(RequestIdFormat::from_env)
+}
+
+// Generador único de request IDs (usado por SetRequestIdLayer y el middleware de logging)
+pub fn generate_request_id() -> String {
+    match format() {
+        RequestIdFormat::UuidV4 => Uuid::new_v4().to_string(),
+        RequestIdFormat::UuidV7 => Uuid::now_v7().to_string(),
+        RequestIdFormat::Ulid => Ulid::new().to_string(),
+    }
+}
@@ -0,0 +1,118 @@
+use crate::health::HealthCheckResponse;
+
+// Configuración del logging de métricas del sistema
+#[derive(Debug, Clone)]
+pub struct SystemMetricsLogConfig {
+    pub interval_secs: u64,
+    pub cpu_delta_percent: f32,
+    pub memory_delta_mb: u64,
+    pub cpu_threshold_percent: f32,
+    pub memory_threshold_percent: f32,
+}
+
+impl SystemMetricsLogConfig {
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self {
+            interval_secs: var("SYSTEM_METRICS_INTERVAL_SECS", 60u64).max(1),
+            cpu_delta_percent: var("SYSTEM_METRICS_CPU_DELTA", 10.0),
+            memory_delta_mb: var("SYSTEM_METRICS_MEMORY_DELTA_MB", 256),
+            cpu_threshold_percent: var("SYSTEM_METRICS_CPU_THRESHOLD", 80.0),
+            memory_threshold_percent: var("SYSTEM_METRICS_MEMORY_THRESHOLD", 90.0),
+        }
+    }
+}
+
+// Muestra de métricas relevante para decidir si se loguea
+#[derive(Debug, Clone)]
+pub struct SystemMetricsSample {
+    pub cpu_usage_percent: f32,
+    pub memory_used_mb: u64,
+    pub memory_total_mb: u64,
+    pub db_pool_size: u32,
+}
+
+impl SystemMetricsSample {
+    pub fn from_health(health: &HealthCheckResponse) -> Self {
+        Self {
+            cpu_usage_percent: health.system.cpu_usage_percent,
+            memory_used_mb: health.system.memory_used_mb,
+            memory_total_mb: health.system.memory_total_mb,
+            db_pool_size: health.database.pool_size,
+        }
+    }
+
+    fn memory_percent(&self) -> f32 {
+        if self.memory_total_mb == 0 {
+            return 0.0;
+        }
+        self.memory_used_mb as f32 / self.memory_total_mb as f32 * 100.0
+    }
+}
+
+// Decide cuándo una muestra merece log: umbrales cruzados respecto a la muestra
+// anterior, o cambios mayores al delta respecto a la última muestra logueada
+// (así una deriva lenta también termina apareciendo)
+#[derive(Debug)]
+pub struct SystemMetricsReporter {
+    config: SystemMetricsLogConfig,
+    previous: Option<SystemMetricsSample>,
+    last_logged: Option<SystemMetricsSample>,
+}
+
+impl SystemMetricsReporter {
+    pub fn new(config: SystemMetricsLogConfig) -> Self {
+        Self {
+            config,
+            previous: None,
+            last_logged: None,
+        }
+    }
+
+    // Devuelve los motivos para loguear la muestra (vacío = no loguear)
+    pub fn observe(&mut self, sample: SystemMetricsSample) -> Vec<&'static str> {
+        let mut reasons = Vec::new();
+
+        match (&self.previous, &self.last_logged) {
+            (Some(previous), Some(logged)) => {
+                let cfg = &self.config;
+
+                if crossed(previous.cpu_usage_percent, sample.cpu_usage_percent, cfg.cpu_threshold_percent) {
+                    reasons.push("cpu_threshold");
+                }
+                if crossed(previous.memory_percent(), sample.memory_percent(), cfg.memory_threshold_percent) {
+                    reasons.push("memory_threshold");
+                }
+                if (sample.cpu_usage_percent - logged.cpu_usage_percent).abs() >= cfg.cpu_delta_percent {
+                    reasons.push("cpu_delta");
+                }
+                if sample.memory_used_mb.abs_diff(logged.memory_used_mb) >= cfg.memory_delta_mb {
+                    reasons.push("memory_delta");
+                }
+                if sample.db_pool_size != logged.db_pool_size {
+                    reasons.push("db_pool_size");
+                }
+            }
+            // Primera muestra: siempre se loguea como línea base
+            _ => reasons.push("initial"),
+        }
+
+        if !reasons.is_empty() {
+            self.last_logged = Some(sample.clone());
+        }
+        self.previous = Some(sample);
+
+        reasons
+    }
+}
+
+// Cruce de umbral en cualquier dirección
+fn crossed(previous: f32, current: f32, threshold: f32) -> bool {
+    (previous < threshold) != (current < threshold)
+}
@@ -0,0 +1,150 @@
+use axum::{extract::Request, middleware::Next, response::Response};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// Fases que registran las capas intermedias
+pub const PHASE_AUTH: &str = "auth";
+pub const PHASE_SERIALIZATION: &str = "ser";
+
+// Marcas de tiempo del request. logging_middleware la inserta en las extensions y las capas
+// que miden algo (auth, router, envelope) acumulan su fase
+#[derive(Clone, Default)]
+pub struct RequestTiming(Arc<Mutex<Marks>>);
+
+#[derive(Default)]
+struct Marks {
+    // Tiempo dentro del router (rutas, auth y handler)
+    router: Option<Duration>,
+    auth: Duration,
+    serialization: Duration,
+}
+
+impl RequestTiming {
+    pub fn record(&self, phase: &'static str, elapsed: Duration) {
+        let mut marks = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        match phase {
+            PHASE_AUTH => marks.auth += elapsed,
+            PHASE_SERIALIZATION => marks.serialization += elapsed,
+            _ => {}
+        }
+    }
+
+    // Desglose del request a partir del total medido por la capa más externa.
+    // middleware = lo que no es router ni serialización; handler = router sin auth
+    pub fn breakdown(&self, total: Duration) -> TimingBreakdown {
+        let marks = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let router = marks.router.unwrap_or_default();
+
+        TimingBreakdown {
+            total_ms: millis(total),
+            middleware_ms: millis(total.saturating_sub(router).saturating_sub(marks.serialization)),
+            auth_ms: millis(marks.auth),
+            handler_ms: millis(router.saturating_sub(marks.auth)),
+            serialization_ms: millis(marks.serialization),
+        }
+    }
+}
+
+// Registrar una fase si el request trae marcas (no las trae fuera de logging_middleware)
+pub fn record_phase(timing: Option<&RequestTiming>, phase: &'static str, started: Instant) {
+    if let Some(timing) = timing {
+        timing.record(phase, started.elapsed());
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 10_000.0).round() / 10.0
+}
+
+// Desglose en milisegundos (resolución de 0.1 ms)
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TimingBreakdown {
+    pub total_ms: f64,
+    pub middleware_ms: f64,
+    pub auth_ms: f64,
+    pub handler_ms: f64,
+    pub serialization_ms: f64,
+}
+
+impl TimingBreakdown {
+    // Valor del header Server-Timing (visible en las devtools del navegador)
+    pub fn server_timing(&self) -> String {
+        format!(
+            "mw;desc=\"middleware\";dur={}, auth;desc=\"auth\";dur={}, handler;desc=\"handler\";dur={}, ser;desc=\"serialization\";dur={}, total;dur={}",
+            self.middleware_ms, self.auth_ms, self.handler_ms, self.serialization_ms, self.total_ms
+        )
+    }
+}
+
+// Header Server-Timing en las respuestas (SERVER_TIMING=false lo desactiva)
+pub fn server_timing_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        std::env::var("SERVER_TIMING")
+            .map(|v| !matches!(v.trim().to_lowercase().as_str(), "false" | "0" | "no"))
+            .unwrap_or(true)
+    })
+}
+
+// Capa más interna de la app: mide el tiempo dentro del router
+pub async fn router_timing_middleware(request: Request, next: Next) -> Response {
+    let timing = request.extensions().get::<RequestTiming>().cloned();
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    if let Some(timing) = timing {
+        let mut marks = timing.0.lock().unwrap_or_else(|e| e.into_inner());
+        *marks.router.get_or_insert_with(Duration::default) += started.elapsed();
+    }
+    response
+}
+
+// Request lento con su desglose, para ver después dónde se fue el tiempo
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowRequestSample {
+    pub request_id: String,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    pub timing: TimingBreakdown,
+    #[serde(with = "crate::models::timestamp")]
+    pub timestamp: DateTime<Utc>,
+}
+
+// Últimos requests lentos (SLOW_REQUEST_SAMPLES, por defecto 50)
+pub struct SlowRequestLog {
+    max_samples: usize,
+    samples: Mutex<VecDeque<SlowRequestSample>>,
+}
+
+impl SlowRequestLog {
+    pub fn record(&self, sample: SlowRequestSample) {
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        if samples.len() >= self.max_samples {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    // Más recientes primero
+    pub fn recent(&self) -> Vec<SlowRequestSample> {
+        let samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        samples.iter().rev().cloned().collect()
+    }
+}
+
+pub fn slow_requests() -> &'static SlowRequestLog {
+    static LOG: OnceLock<SlowRequestLog> = OnceLock::new();
+    LOG.get_or_init(|| SlowRequestLog {
+        max_samples: std::env::var("SLOW_REQUEST_SAMPLES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(50usize)
+            .max(1),
+        samples: Mutex::new(VecDeque::new()),
+    })
+}
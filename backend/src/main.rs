@@ -1,14 +1,27 @@
+mod audit;
 mod auth;
+mod chaos;
+mod config;
+mod crypto;
 mod database;
+mod envelope;
+mod events;
+mod grpc;
 mod handlers;
 mod health;
 mod logging;
 mod metrics;
 mod models;
+mod outbound;
 mod routes;
+mod server;
+mod single_flight;
+#[cfg(test)]
+mod test_support;
+mod validation;
 
 use axum::{
-    http::{HeaderValue, Method},
+    http::Method,
     middleware,
     routing::{get, post},
     Router,
@@ -18,25 +31,24 @@ use std::time::Duration;
 use tower::ServiceBuilder;
 use tower_http::{
     catch_panic::CatchPanicLayer,
-    cors::CorsLayer,
+    cors::{AllowOrigin, CorsLayer},
     request_id::{MakeRequestId, RequestId, SetRequestIdLayer},
     timeout::TimeoutLayer,
     trace::TraceLayer,
 };
-use uuid::Uuid;
 
-use crate::database::create_pool;
+use crate::database::{check_schema_compatibility, create_pool, verify_schema};
 use crate::health::HealthChecker;
-use crate::logging::{logging_middleware, slow_request_middleware, Logger};
+use crate::logging::{generate_request_id, logging_middleware, slow_request_middleware, Logger, SystemMetricsLogConfig, SystemMetricsReporter, SystemMetricsSample};
 use crate::metrics::MetricsCollector;
 
-// Generador de Request ID personalizado
+// Generador de Request ID personalizado (formato configurable con REQUEST_ID_FORMAT)
 #[derive(Clone, Default)]
 struct MakeRequestUuid;
 
 impl MakeRequestId for MakeRequestUuid {
     fn make_request_id<B>(&mut self, _request: &axum::http::Request<B>) -> Option<RequestId> {
-        let request_id = Uuid::new_v4().to_string().parse().ok()?;
+        let request_id = generate_request_id().parse().ok()?;
         Some(RequestId::new(request_id))
     }
 }
@@ -48,7 +60,7 @@ async fn root_handler() -> axum::Json<serde_json::Value> {
         "version": env!("CARGO_PKG_VERSION"),
         "status": "running",
         "environment": std::env::var("ENVIRONMENT").unwrap_or_else(|_| "development".to_string()),
-        "timestamp": chrono::Utc::now(),
+        "timestamp": crate::models::timestamp::now(),
         "endpoints": {
             "health": "/health",
             "metrics": "/metrics/public",
@@ -72,6 +84,40 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Cargar variables de entorno
     dotenv::dotenv().ok();
     let environment = std::env::var("ENVIRONMENT").unwrap_or_else(|_| "development".to_string());
+
+    // Configuración recargable en caliente (rate limits, CORS, watchdog; CONFIG_FILE opcional)
+    config::init().map_err(|errors| {
+        tracing::error!(errors = ?errors, "🚨 Configuración inválida");
+        format!("configuración inválida: {}", errors.join("; "))
+    })?;
+
+    // Claves JWT (HS256 o RS256) cargadas una sola vez
+    let jwt = auth::init_jwt_config()
+        .map_err(|e| {
+            tracing::error!(error = %e, "🚨 Configuración JWT inválida");
+            e
+        })?;
+    tracing::info!(
+        algorithm = ?jwt.algorithm,
+        key_id = %jwt.key_id,
+        previous_keys = jwt.previous_key_count(),
+        transport = if auth::cookie::cookie_transport_enabled() { "cookie" } else { "header" },
+        "🔑 Firma de tokens JWT configurada"
+    );
+    // Un secreto inseguro impide arrancar en producción; en desarrollo solo se advierte
+    if let Err(problem) = jwt.validate() {
+        if environment == "production" {
+            tracing::error!(event = "insecure_jwt_secret", problem = %problem, "🚨 Secreto JWT inseguro en producción");
+            return Err(format!("configuración JWT insegura: {}", problem).into());
+        }
+        tracing::warn!(
+            event = "insecure_jwt_secret",
+            environment = %environment,
+            problem = %problem,
+            "⚠️⚠️⚠️ Secreto JWT inseguro: {} (no usar así en producción)",
+            problem
+        );
+    }
     
     // Crear pool de conexiones a DB
     tracing::info!("📊 Conectando a base de datos...");
@@ -82,21 +128,92 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         })?;
     tracing::info!("✅ Conexión a base de datos establecida");
 
+    // Verificar índices y restricciones críticas
+    verify_schema(&pool).await
+        .map_err(|e| {
+            tracing::error!(error = %e, "🚨 Verificación de esquema fallida");
+            e
+        })?;
+
+    // Comando de mantenimiento: cifrar/recifrar secretos almacenados y salir
+    if std::env::args().nth(1).as_deref() == Some("encrypt-secrets") {
+        let updated = crypto::backfill_encrypted_columns(&pool).await?;
+        tracing::info!(updated = updated, "🔐 Backfill de secretos cifrados completado");
+        return Ok(());
+    }
+
+    // Los secretos cifrados requieren una clave válida
+    crypto::verify_encryption_config(&pool).await
+        .map_err(|e| {
+            tracing::error!(error = %e, "🚨 Configuración de cifrado inválida");
+            e
+        })?;
+
+    auth::password::warn_if_weak_cost();
+
+    // Registro de llamadas HTTP salientes (persistencia opcional con OUTBOUND_LOG_PERSIST)
+    outbound::outbound_log().enable_persistence(pool.clone());
+
+    // Cargar lista de dominios de email desechables
+    let disposable_domains = validation::denylist().domain_count();
+    tracing::info!(domains = disposable_domains, "📧 Lista de dominios desechables cargada");
+
+    let common_passwords = validation::password_policy().common_password_count();
+    tracing::info!(common_passwords = common_passwords, "🔑 Política de contraseñas cargada");
+
+    if !validation::captcha::is_enabled() {
+        tracing::warn!("⚠️ CAPTCHA_SECRET no configurado: el formulario de contacto no exige captcha");
+    }
+
+    if !auth::magic_link::is_enabled() {
+        tracing::info!("🔗 MAGIC_LINK_SECRET no configurado: acceso por enlace desactivado");
+    }
+
     // Inicializar sistemas de monitoreo
     let health_checker = Arc::new(HealthChecker::new(pool.clone()));
-    let metrics_collector = Arc::new(MetricsCollector::new(10000)); // Máximo 10k métricas en memoria
+    // Columnas/tipos que espera esta versión (en modo estricto bloquea /health/ready)
+    health_checker.set_schema_compatibility(check_schema_compatibility(&pool).await);
+    let max_metrics = std::env::var("METRICS_MAX")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10000); // Máximo 10k métricas en memoria por defecto
+    let metrics_sample_rate = std::env::var("METRICS_SAMPLE_RATE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1); // 1 de cada N requests al buffer; 1 = todos
+    let metrics_collector = Arc::new(MetricsCollector::new(max_metrics, metrics_sample_rate));
     
     tracing::info!("📈 Sistemas de monitoreo inicializados");
 
+    // Warm-up de caches en segundo plano; /health/ready no pasa hasta que termine
+    tokio::spawn(health_checker.clone().run_warmup());
+    tokio::spawn(health_checker.clone().run_watchdog());
+    // Historial de estado para la página de estado, independiente de los polls a /health
+    tokio::spawn(health_checker.clone().run_status_sampler());
+
     // Configurar CORS
     let cors = CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
         .allow_headers([
             axum::http::header::CONTENT_TYPE,
             axum::http::header::AUTHORIZATION,
             axum::http::header::ACCEPT,
+            axum::http::HeaderName::from_static(envelope::middleware::ENVELOPE_HEADER),
+            axum::http::HeaderName::from_static("x-timezone"),
+            axum::http::HeaderName::from_static(database::query_log::DEBUG_QUERIES_HEADER),
+        ])
+        // Headers de vencimiento del token legibles desde el frontend
+        .expose_headers([
+            axum::http::HeaderName::from_static("x-token-expires-in"),
+            axum::http::HeaderName::from_static("x-token-refresh-recommended"),
+            axum::http::HeaderName::from_static("server-timing"),
         ])
-        .allow_origin("http://localhost:5173".parse::<HeaderValue>()?);
+        // Con AUTH_TRANSPORT=cookie el navegador debe poder enviar la cookie de sesión
+        .allow_credentials(auth::cookie::cookie_transport_enabled())
+        // Orígenes permitidos según la configuración vigente (CORS_ALLOWED_ORIGINS, recargable)
+        .allow_origin(AllowOrigin::predicate(|origin, _| {
+            config::app_config().cors_origins.contains(origin)
+        }));
 
     // Crear middleware stack profesional - ORDEN CORREGIDO
     let middleware_stack = ServiceBuilder::new()
@@ -123,25 +240,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .layer(TraceLayer::new_for_http());
 
     // Crear rutas principales de la API
-    let api_routes = routes::create_routes();
+    let api_routes = routes::create_routes(pool.clone(), metrics_collector.clone());
 
     // Crear rutas de health y métricas (sin auth)
     let health_routes = Router::new()
         .route("/health", get(handlers::health::health_check))
         .route("/health/live", get(handlers::health::liveness_check))
         .route("/health/ready", get(handlers::health::readiness_check))
+        .route("/health/startup", get(handlers::health::startup_check))
+        .route("/health/db", get(handlers::health::database_check))
         .route("/status", get(handlers::health::status_check))
+        .route("/status/page", get(handlers::health::status_page))
         .route("/info", get(handlers::health::server_info))
         .with_state(health_checker.clone());
 
+    // Métricas de admin: requieren token y rol de admin
     let metrics_routes = Router::new()
         .route("/metrics", get(handlers::metrics::get_metrics))
-        .route("/metrics/public", get(handlers::metrics::get_public_metrics))
         .route("/metrics/endpoints/top", get(handlers::metrics::get_top_endpoints))
         .route("/metrics/endpoints/slow", get(handlers::metrics::get_slowest_endpoints))
         .route("/metrics/status-distribution", get(handlers::metrics::get_status_distribution))
         .route("/metrics/hourly", get(handlers::metrics::get_hourly_stats))
         .route("/metrics/endpoint/:method/:path", get(handlers::metrics::get_endpoint_metrics))
+        .route_layer(middleware::from_fn(auth::require_role(models::user::Role::Admin)))
+        .route_layer(middleware::from_fn_with_state(pool.clone(), auth::auth_middleware))
+        // También accesibles con API key de scope metrics:read
+        .route_layer(middleware::from_fn_with_state(auth::api_keys::SCOPE_METRICS_READ, auth::required_scope_middleware))
+        .route("/metrics/public", get(handlers::metrics::get_public_metrics))
         .with_state(metrics_collector.clone());
 
     // Configurar middleware para registrar métricas
@@ -171,6 +296,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         })
     };
 
+    // Panel de administración embebido (desactivable por configuración)
+    let dashboard_routes = if handlers::dashboard::is_enabled() {
+        Router::new().route("/admin", get(handlers::dashboard::admin_dashboard))
+    } else {
+        tracing::info!("🔒 Panel de administración deshabilitado");
+        Router::new()
+    };
+
+    // Configuración del modo chaos (CHAOS_MODE)
+    let chaos_config = Arc::new(chaos::ChaosConfig::from_env(&environment));
+
     // Construir aplicación completa
 let app = Router::new()
     // Rutas principales de la API
@@ -178,14 +314,25 @@ let app = Router::new()
     // Rutas de monitoreo y salud
     .merge(health_routes)
     .merge(metrics_routes)
+    .merge(dashboard_routes)
     // Ruta raíz para verificación básica
     .route("/", get(root_handler))
+    // Claves públicas de firma de tokens (JWT_ALGORITHM=RS256)
+    .route("/.well-known/jwks.json", get(handlers::auth::jwks))
+    // Tiempo dentro del router (auth + handler) para Server-Timing
+    .layer(middleware::from_fn(logging::timing::router_timing_middleware))
+    // Inyección de fallos para pruebas (solo fuera de producción)
+    .layer(middleware::from_fn_with_state(chaos_config, chaos::chaos_middleware))
+    // Headers de deprecación y retiro de rutas (registro en envelope::sunset)
+    .layer(middleware::from_fn_with_state(metrics_collector.clone(), envelope::route_deprecation_middleware))
+    // Envelope opcional de respuestas (header X-Api-Envelope)
+    .layer(middleware::from_fn(envelope::envelope_middleware))
     // Aplicar middleware de métricas a toda la app
     .layer(metrics_middleware)
     // AGREGAR ESTA LÍNEA: Aplicar logging a toda la app
     .layer(middleware_stack)
     // State compartido
-    .with_state(pool);
+    .with_state(pool.clone());
 
     // Configurar dirección y puerto
     let host = std::env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
@@ -196,28 +343,119 @@ let app = Router::new()
     let listener = tokio::net::TcpListener::bind(&bind_address).await?;
     let local_addr = listener.local_addr()?;
 
-    // Configurar tarea de limpieza de métricas (cada 1 hora)
+    // Apagado ordenado compartido por HTTP y gRPC (lo disparan SIGTERM, Ctrl+C o el watchdog de recursos)
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let shutdown_checker = health_checker.clone();
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = shutdown_checker.shutdown_requested() => {}
+            _ = server::termination_signal() => {}
+        }
+        let _ = shutdown_tx.send(true);
+    });
+    let shutdown_signal = |mut rx: tokio::sync::watch::Receiver<bool>| async move {
+        let _ = rx.wait_for(|requested| *requested).await;
+    };
+
+    // API gRPC interna opcional (GRPC_ENABLED), en su propio puerto
+    let grpc_config = grpc::GrpcConfig::from_env();
+    grpc_config.validate().map_err(|e| {
+        tracing::error!(error = %e, "🚨 Configuración gRPC inválida");
+        e
+    })?;
+    let grpc_server = if grpc_config.enabled {
+        let grpc_address = std::net::SocketAddr::new(local_addr.ip(), grpc_config.port);
+        let grpc_shutdown = shutdown_signal(shutdown_rx.clone());
+        let (grpc_pool, grpc_health) = (pool.clone(), health_checker.clone());
+        Some(tokio::spawn(async move {
+            if let Err(e) = grpc::serve(grpc_config, grpc_address, grpc_pool, grpc_health, grpc_shutdown).await {
+                tracing::error!(error = %e, "🚨 Error del servidor gRPC");
+            }
+        }))
+    } else {
+        None
+    };
+
+    // Configurar tarea de limpieza de métricas y del rate limiter en memoria (cada 1 hora)
     let cleanup_collector = metrics_collector.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(Duration::from_secs(3600)); // 1 hora
         loop {
             interval.tick().await;
             cleanup_collector.cleanup_old_metrics(Duration::from_secs(86400)); // 24 horas
+
+            let purged = auth::rate_limit::rate_limiter().purge_idle();
+            if purged > 0 {
+                tracing::debug!(event = "rate_limit_purged", purged = purged, "🧹 Claves de rate limit inactivas eliminadas");
+            }
         }
     });
 
-    // Configurar task de logging de métricas del sistema (cada 5 minutos)
+    // Configurar tarea de limpieza de tokens revocados ya expirados (cada 1 hora)
+    let cleanup_pool = pool.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(3600)); // 1 hora
+        loop {
+            interval.tick().await;
+            match auth::revocation::purge_expired(&cleanup_pool).await {
+                Ok(purged) if purged > 0 => {
+                    tracing::info!(event = "revoked_tokens_purged", purged = purged, "🧹 Tokens revocados expirados eliminados");
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!(error = %e, "⚠️ Error al purgar tokens revocados"),
+            }
+            // Sesiones revocadas o inactivas más allá de la vida de un refresh token
+            match auth::sessions::purge_stale(&cleanup_pool, auth::jwt_config().refresh_expiration_days).await {
+                Ok(purged) if purged > 0 => {
+                    tracing::info!(event = "sessions_purged", purged = purged, "🧹 Sesiones vencidas eliminadas");
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!(error = %e, "⚠️ Error al purgar sesiones"),
+            }
+            match auth::magic_link::purge_expired(&cleanup_pool).await {
+                Ok(purged) if purged > 0 => {
+                    tracing::info!(event = "magic_links_purged", purged = purged, "🧹 Enlaces de acceso vencidos eliminados");
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!(error = %e, "⚠️ Error al purgar enlaces de acceso"),
+            }
+            match events::purge_expired(&cleanup_pool).await {
+                Ok(purged) if purged > 0 => {
+                    tracing::info!(event = "domain_events_purged", purged = purged, "🧹 Eventos de dominio fuera de la retención eliminados");
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!(error = %e, "⚠️ Error al purgar eventos de dominio"),
+            }
+            // Bajas de cuenta cuyo plazo de restauración terminó
+            match auth::account_deletion::anonymize_expired(&cleanup_pool).await {
+                Ok(anonymized) if anonymized > 0 => {
+                    tracing::info!(event = "deleted_users_anonymized", anonymized = anonymized, "🧹 Cuentas dadas de baja anonimizadas");
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!(error = %e, "⚠️ Error al anonimizar cuentas dadas de baja"),
+            }
+        }
+    });
+
+    // Configurar task de logging de métricas del sistema (solo loguea cambios relevantes)
     let system_metrics_checker = health_checker.clone();
+    let system_metrics_config = SystemMetricsLogConfig::from_env();
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(300)); // 5 minutos
+        let mut interval = tokio::time::interval(Duration::from_secs(system_metrics_config.interval_secs));
+        let mut reporter = SystemMetricsReporter::new(system_metrics_config);
         loop {
             interval.tick().await;
             let health = system_metrics_checker.check_health().await;
+            let reasons = reporter.observe(SystemMetricsSample::from_health(&health));
+            if reasons.is_empty() {
+                continue;
+            }
             Logger::log_system_metrics(
                 health.system.cpu_usage_percent,
                 health.system.memory_used_mb * 1024 * 1024, // Convertir a bytes
                 0, // active_connections - podríamos implementar esto
                 health.database.pool_size,
+                &reasons,
             );
         }
     });
@@ -236,6 +474,7 @@ let app = Router::new()
     tracing::info!("   📊 Métricas Públicas: http://{}/metrics/public", local_addr);
     tracing::info!("   🔐 API Auth: http://{}/api/v1/auth/*", local_addr);
     tracing::info!("   👥 API Users: http://{}/api/v1/users/*", local_addr);
+    tracing::info!("   📨 Contacto: http://{}/api/v1/contact", local_addr);
     tracing::info!("   ℹ️  Info del Servidor: http://{}/info", local_addr);
 
     if environment == "development" {
@@ -245,15 +484,17 @@ let app = Router::new()
 
     // Iniciar servidor
     tracing::info!("🎯 Servidor listo para recibir conexiones");
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
-    )
+    server::serve(listener, app, server::ServerConfig::from_env(), shutdown_signal(shutdown_rx))
     .await
     .map_err(|e| {
         tracing::error!(error = %e, "🚨 Error fatal del servidor");
         e
     })?;
 
+    // Esperar a que el servidor gRPC termine sus llamadas en curso
+    if let Some(grpc_server) = grpc_server {
+        let _ = grpc_server.await;
+    }
+
     Ok(())
 }
\ No newline at end of file
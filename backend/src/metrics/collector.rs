@@ -1,8 +1,10 @@
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::time::{Duration, Instant};
 use serde::{Serialize, Deserialize};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Timelike, Utc};
+use chrono_tz::Tz;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RequestMetric {
@@ -10,6 +12,7 @@ pub struct RequestMetric {
     pub path: String,
     pub status: u16,
     pub duration_ms: u64,
+    #[serde(with = "crate::models::timestamp")]
     pub timestamp: DateTime<Utc>,
     pub user_id: Option<i32>,
 }
@@ -24,11 +27,13 @@ pub struct EndpointStats {
     pub avg_response_time_ms: f64,
     pub min_response_time_ms: u64,
     pub max_response_time_ms: u64,
+    #[serde(with = "crate::models::timestamp")]
     pub last_accessed: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MetricsSnapshot {
+    #[serde(with = "crate::models::timestamp")]
     pub timestamp: DateTime<Utc>,
     pub uptime_seconds: u64,
     pub total_requests: u64,
@@ -41,30 +46,187 @@ pub struct MetricsSnapshot {
     pub error_endpoints: Vec<EndpointStats>,
     pub status_code_distribution: HashMap<u16, u64>,
     pub hourly_stats: Vec<HourlyStats>,
+    pub collector: CollectorStats,
+}
+
+// Estado interno del colector (tamaño de buffers y efectividad de la limpieza)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectorStats {
+    pub sample_rate: u64,
+    pub metrics_buffer_len: usize,
+    pub metrics_buffer_max: usize,
+    pub endpoint_stats_len: usize,
+    pub cap_evictions_total: u64,
+    pub cap_eviction_events: u64,
+    pub age_evictions_total: u64,
+    pub age_cleanup_runs: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HourlyStats {
+    #[serde(with = "crate::models::timestamp")]
     pub hour: DateTime<Utc>,
+    // Inicio de la hora en la zona de reporte (METRICS_TIMEZONE), con su offset
+    pub local_hour: String,
+    pub hour_of_day: u32,
     pub requests: u64,
     pub avg_response_time_ms: f64,
     pub error_rate_percent: f64,
 }
 
+// Contadores de un endpoint; se actualizan sin el lock de escritura del mapa
+struct EndpointCounters {
+    path: String,
+    method: String,
+    total_requests: AtomicU64,
+    success_requests: AtomicU64,
+    error_requests: AtomicU64,
+    total_response_time_ms: AtomicU64,
+    min_response_time_ms: AtomicU64,
+    max_response_time_ms: AtomicU64,
+    last_accessed_ms: AtomicI64,
+}
+
+impl EndpointCounters {
+    fn new(method: String, path: String) -> Self {
+        Self {
+            path,
+            method,
+            total_requests: AtomicU64::new(0),
+            success_requests: AtomicU64::new(0),
+            error_requests: AtomicU64::new(0),
+            total_response_time_ms: AtomicU64::new(0),
+            min_response_time_ms: AtomicU64::new(u64::MAX),
+            max_response_time_ms: AtomicU64::new(0),
+            last_accessed_ms: AtomicI64::new(Utc::now().timestamp_millis()),
+        }
+    }
+
+    fn record(&self, status: u16, duration_ms: u64) {
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+        if (200..400).contains(&status) {
+            self.success_requests.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.error_requests.fetch_add(1, Ordering::Relaxed);
+        }
+        self.total_response_time_ms.fetch_add(duration_ms, Ordering::Relaxed);
+        self.min_response_time_ms.fetch_min(duration_ms, Ordering::Relaxed);
+        self.max_response_time_ms.fetch_max(duration_ms, Ordering::Relaxed);
+        self.last_accessed_ms.fetch_max(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    // Cada campo se lee por separado: con requests concurrentes la vista puede
+    // mezclar valores de instantes muy cercanos, suficiente para métricas
+    fn stats(&self) -> EndpointStats {
+        let total_requests = self.total_requests.load(Ordering::Relaxed);
+        let avg_response_time_ms = if total_requests > 0 {
+            self.total_response_time_ms.load(Ordering::Relaxed) as f64 / total_requests as f64
+        } else {
+            0.0
+        };
+
+        EndpointStats {
+            path: self.path.clone(),
+            method: self.method.clone(),
+            total_requests,
+            success_requests: self.success_requests.load(Ordering::Relaxed),
+            error_requests: self.error_requests.load(Ordering::Relaxed),
+            avg_response_time_ms,
+            min_response_time_ms: self.min_response_time_ms.load(Ordering::Relaxed),
+            max_response_time_ms: self.max_response_time_ms.load(Ordering::Relaxed),
+            last_accessed: DateTime::from_timestamp_millis(self.last_accessed_ms.load(Ordering::Relaxed))
+                .unwrap_or_else(Utc::now),
+        }
+    }
+}
+
+// Uso de una ruta deprecada
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeprecatedRouteUsage {
+    pub total_calls: u64,
+    #[serde(with = "crate::models::timestamp")]
+    pub last_called_at: DateTime<Utc>,
+}
+
+// Zona horaria para agrupar y etiquetar estadísticas por hora
+// (METRICS_TIMEZONE, nombre IANA como "America/La_Paz"; por defecto UTC)
+pub fn reporting_timezone() -> Tz {
+    static TIMEZONE: OnceLock<Tz> = OnceLock::new();
+    *TIMEZONE.get_or_init(|| {
+        let name = match std::env::var("METRICS_TIMEZONE") {
+            Ok(name) if !name.trim().is_empty() => name,
+            _ => return Tz::UTC,
+        };
+
+        name.trim().parse().unwrap_or_else(|_| {
+            tracing::warn!(timezone = %name, "⚠️ METRICS_TIMEZONE inválida, usando UTC");
+            Tz::UTC
+        })
+    })
+}
+
+// Obtener el lock aunque esté envenenado (un panic previo mientras se sostenía):
+// las métricas pueden quedar con una escritura parcial, pero el colector sigue funcionando
+fn read_lock<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|poisoned| {
+        tracing::warn!("⚠️ Lock de métricas envenenado, recuperando");
+        lock.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+fn write_lock<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|poisoned| {
+        tracing::warn!("⚠️ Lock de métricas envenenado, recuperando");
+        lock.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
 pub struct MetricsCollector {
     start_time: Instant,
     metrics: Arc<RwLock<Vec<RequestMetric>>>,
-    endpoint_stats: Arc<RwLock<HashMap<String, EndpointStats>>>,
+    endpoint_stats: Arc<RwLock<HashMap<String, Arc<EndpointCounters>>>>,
     max_metrics: usize,
+    // Solo 1 de cada `sample_rate` requests se guarda en el buffer; los totales son exactos
+    sample_rate: u64,
+    sample_counter: AtomicU64,
+    total_requests: AtomicU64,
+    total_errors: AtomicU64,
+    // Métricas descartadas por superar max_metrics
+    cap_evictions: AtomicU64,
+    cap_eviction_events: AtomicU64,
+    // Métricas descartadas por antigüedad en cleanup_old_metrics
+    age_evictions: AtomicU64,
+    age_cleanup_runs: AtomicU64,
+    // Conteo aproximado de usuarios en línea (cacheado)
+    online_users_cache: RwLock<Option<(Instant, u64)>>,
+    // Llamadas a rutas deprecadas ("METHOD /path" -> uso)
+    deprecated_calls: RwLock<HashMap<String, DeprecatedRouteUsage>>,
 }
 
+// Ventana de actividad para considerar a un usuario "en línea"
+const ONLINE_WINDOW_MINUTES: i64 = 5;
+// Tiempo que se reutiliza el conteo de usuarios en línea
+const ONLINE_CACHE_TTL: Duration = Duration::from_secs(10);
+
 impl MetricsCollector {
-    pub fn new(max_metrics: usize) -> Self {
+    pub fn new(max_metrics: usize, sample_rate: u64) -> Self {
         Self {
             start_time: Instant::now(),
             metrics: Arc::new(RwLock::new(Vec::new())),
             endpoint_stats: Arc::new(RwLock::new(HashMap::new())),
             max_metrics,
+            sample_rate: sample_rate.max(1),
+            sample_counter: AtomicU64::new(0),
+            total_requests: AtomicU64::new(0),
+            total_errors: AtomicU64::new(0),
+            cap_evictions: AtomicU64::new(0),
+            cap_eviction_events: AtomicU64::new(0),
+            age_evictions: AtomicU64::new(0),
+            age_cleanup_runs: AtomicU64::new(0),
+            online_users_cache: RwLock::new(None),
+            deprecated_calls: RwLock::new(HashMap::new()),
         }
     }
 
@@ -77,6 +239,18 @@ impl MetricsCollector {
         duration_ms: u64,
         user_id: Option<i32>,
     ) {
+        // Contadores exactos, independientes del muestreo
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+        if status >= 400 {
+            self.total_errors.fetch_add(1, Ordering::Relaxed);
+        }
+        self.update_endpoint_stats(method.clone(), path.clone(), status, duration_ms);
+
+        // Requests fuera de la muestra no entran al buffer
+        if !self.sample_counter.fetch_add(1, Ordering::Relaxed).is_multiple_of(self.sample_rate) {
+            return;
+        }
+
         let metric = RequestMetric {
             method: method.clone(),
             path: path.clone(),
@@ -88,70 +262,54 @@ impl MetricsCollector {
 
         // Actualizar métricas globales
         {
-            let mut metrics = self.metrics.write().unwrap();
+            let mut metrics = write_lock(&self.metrics);
             metrics.push(metric);
             
             // Limitar el número de métricas en memoria
             if metrics.len() > self.max_metrics {
                 let drain_count = metrics.len() - self.max_metrics;
                 metrics.drain(0..drain_count);
+                self.cap_evictions.fetch_add(drain_count as u64, Ordering::Relaxed);
+                self.cap_eviction_events.fetch_add(1, Ordering::Relaxed);
             }
         }
-
-        // Actualizar estadísticas por endpoint
-        self.update_endpoint_stats(method, path, status, duration_ms);
     }
 
-    // Actualizar estadísticas por endpoint
+    // Actualizar estadísticas por endpoint: el lock de escritura solo se toma
+    // la primera vez que aparece un endpoint; el resto usa el de lectura
     fn update_endpoint_stats(&self, method: String, path: String, status: u16, duration_ms: u64) {
         let key = format!("{} {}", method, path);
-        let mut stats = self.endpoint_stats.write().unwrap();
-        
-        let endpoint_stat = stats.entry(key).or_insert(EndpointStats {
-            path: path.clone(),
-            method: method.clone(),
-            total_requests: 0,
-            success_requests: 0,
-            error_requests: 0,
-            avg_response_time_ms: 0.0,
-            min_response_time_ms: u64::MAX,
-            max_response_time_ms: 0,
-            last_accessed: Utc::now(),
-        });
+        let existing = read_lock(&self.endpoint_stats).get(&key).cloned();
 
-        // Actualizar contadores
-        endpoint_stat.total_requests += 1;
-        endpoint_stat.last_accessed = Utc::now();
-
-        if status >= 200 && status < 400 {
-            endpoint_stat.success_requests += 1;
-        } else {
-            endpoint_stat.error_requests += 1;
-        }
+        let counters = match existing {
+            Some(counters) => counters,
+            None => write_lock(&self.endpoint_stats)
+                .entry(key)
+                .or_insert_with(|| Arc::new(EndpointCounters::new(method, path)))
+                .clone(),
+        };
 
-        // Actualizar tiempos de respuesta
-        endpoint_stat.min_response_time_ms = endpoint_stat.min_response_time_ms.min(duration_ms);
-        endpoint_stat.max_response_time_ms = endpoint_stat.max_response_time_ms.max(duration_ms);
-        
-        // Calcular promedio móvil simple
-        let total_time = endpoint_stat.avg_response_time_ms * (endpoint_stat.total_requests - 1) as f64;
-        endpoint_stat.avg_response_time_ms = (total_time + duration_ms as f64) / endpoint_stat.total_requests as f64;
+        counters.record(status, duration_ms);
     }
 
     // Obtener snapshot completo de métricas
     pub fn get_metrics_snapshot(&self) -> MetricsSnapshot {
-        let metrics = self.metrics.read().unwrap();
-        let endpoint_stats = self.endpoint_stats.read().unwrap();
+        let metrics = read_lock(&self.metrics);
+        let endpoint_stats: Vec<EndpointStats> = read_lock(&self.endpoint_stats)
+            .values()
+            .map(|counters| counters.stats())
+            .collect();
         
         let uptime_seconds = self.start_time.elapsed().as_secs();
-        let total_requests = metrics.len() as u64;
+        let total_requests = self.total_requests.load(Ordering::Relaxed);
         
-        // Calcular requests por minuto (últimos 60 segundos)
+        // Calcular requests por minuto (últimos 60 segundos, escalado por el muestreo)
         let one_minute_ago = Utc::now() - chrono::Duration::minutes(1);
         let recent_requests = metrics
             .iter()
             .filter(|m| m.timestamp > one_minute_ago)
-            .count() as f64;
+            .count() as f64
+            * self.sample_rate as f64;
         
         // Calcular tiempo de respuesta promedio
         let avg_response_time_ms = if !metrics.is_empty() {
@@ -161,7 +319,7 @@ impl MetricsCollector {
         };
         
         // Calcular tasa de error
-        let error_requests = metrics.iter().filter(|m| m.status >= 400).count();
+        let error_requests = self.total_errors.load(Ordering::Relaxed);
         let error_rate_percent = if total_requests > 0 {
             (error_requests as f64 / total_requests as f64) * 100.0
         } else {
@@ -178,18 +336,18 @@ impl MetricsCollector {
             .len() as u64;
         
         // Top endpoints más usados
-        let mut most_used: Vec<EndpointStats> = endpoint_stats.values().cloned().collect();
+        let mut most_used: Vec<EndpointStats> = endpoint_stats.clone();
         most_used.sort_by(|a, b| b.total_requests.cmp(&a.total_requests));
         most_used.truncate(10);
         
         // Endpoints más lentos
-        let mut slowest: Vec<EndpointStats> = endpoint_stats.values().cloned().collect();
-        slowest.sort_by(|a, b| b.avg_response_time_ms.partial_cmp(&a.avg_response_time_ms).unwrap());
+        let mut slowest: Vec<EndpointStats> = endpoint_stats.clone();
+        slowest.sort_by(|a, b| b.avg_response_time_ms.total_cmp(&a.avg_response_time_ms));
         slowest.truncate(10);
         
         // Endpoints con más errores
         let mut error_endpoints: Vec<EndpointStats> = endpoint_stats
-            .values()
+            .iter()
             .filter(|stat| stat.error_requests > 0)
             .cloned()
             .collect();
@@ -199,12 +357,24 @@ impl MetricsCollector {
         // Distribución de códigos de estado
         let mut status_distribution = HashMap::new();
         for metric in metrics.iter() {
-            *status_distribution.entry(metric.status).or_insert(0) += 1;
+            *status_distribution.entry(metric.status).or_insert(0) += self.sample_rate;
         }
         
         // Estadísticas por hora (últimas 24 horas)
         let hourly_stats = self.calculate_hourly_stats(&metrics);
         
+        // Estado interno del colector
+        let collector = CollectorStats {
+            sample_rate: self.sample_rate,
+            metrics_buffer_len: metrics.len(),
+            metrics_buffer_max: self.max_metrics,
+            endpoint_stats_len: endpoint_stats.len(),
+            cap_evictions_total: self.cap_evictions.load(Ordering::Relaxed),
+            cap_eviction_events: self.cap_eviction_events.load(Ordering::Relaxed),
+            age_evictions_total: self.age_evictions.load(Ordering::Relaxed),
+            age_cleanup_runs: self.age_cleanup_runs.load(Ordering::Relaxed),
+        };
+        
         MetricsSnapshot {
             timestamp: Utc::now(),
             uptime_seconds,
@@ -218,6 +388,7 @@ impl MetricsCollector {
             error_endpoints,
             status_code_distribution: status_distribution,
             hourly_stats,
+            collector,
         }
     }
 
@@ -225,9 +396,13 @@ impl MetricsCollector {
     fn calculate_hourly_stats(&self, metrics: &[RequestMetric]) -> Vec<HourlyStats> {
         let mut hourly_map: HashMap<i64, Vec<&RequestMetric>> = HashMap::new();
         
-        // Agrupar métricas por hora
+        let timezone = reporting_timezone();
+        
+        // Agrupar métricas por hora local (el almacenamiento sigue en UTC)
         for metric in metrics.iter() {
-            let hour_timestamp = metric.timestamp.timestamp() / 3600 * 3600;
+            let local = metric.timestamp.with_timezone(&timezone);
+            let hour_timestamp = metric.timestamp.timestamp()
+                - (local.minute() as i64 * 60 + local.second() as i64);
             hourly_map.entry(hour_timestamp).or_default().push(metric);
         }
         
@@ -235,7 +410,8 @@ impl MetricsCollector {
         let mut hourly_stats: Vec<HourlyStats> = hourly_map
             .into_iter()
             .map(|(hour_timestamp, hour_metrics)| {
-                let requests = hour_metrics.len() as u64;
+                let sampled = hour_metrics.len() as u64;
+                let requests = sampled * self.sample_rate;
                 let avg_response_time_ms = if !hour_metrics.is_empty() {
                     hour_metrics.iter().map(|m| m.duration_ms as f64).sum::<f64>() / hour_metrics.len() as f64
                 } else {
@@ -243,14 +419,19 @@ impl MetricsCollector {
                 };
                 
                 let error_count = hour_metrics.iter().filter(|m| m.status >= 400).count();
-                let error_rate_percent = if requests > 0 {
-                    (error_count as f64 / requests as f64) * 100.0
+                let error_rate_percent = if sampled > 0 {
+                    (error_count as f64 / sampled as f64) * 100.0
                 } else {
                     0.0
                 };
                 
+                let hour = DateTime::from_timestamp(hour_timestamp, 0).unwrap_or(Utc::now());
+                let local_hour = hour.with_timezone(&timezone);
+                
                 HourlyStats {
-                    hour: DateTime::from_timestamp(hour_timestamp, 0).unwrap_or(Utc::now()),
+                    hour,
+                    local_hour: local_hour.to_rfc3339_opts(SecondsFormat::Secs, false),
+                    hour_of_day: local_hour.hour(),
                     requests,
                     avg_response_time_ms,
                     error_rate_percent,
@@ -266,21 +447,124 @@ impl MetricsCollector {
     // Obtener métricas de un endpoint específico
     pub fn get_endpoint_metrics(&self, method: &str, path: &str) -> Option<EndpointStats> {
         let key = format!("{} {}", method, path);
-        self.endpoint_stats.read().unwrap().get(&key).cloned()
+        read_lock(&self.endpoint_stats).get(&key).map(|counters| counters.stats())
+    }
+
+    // Usuarios autenticados distintos con actividad HTTP reciente (cacheado 10s)
+    pub fn online_users(&self) -> u64 {
+        if let Some((computed_at, count)) = *read_lock(&self.online_users_cache) {
+            if computed_at.elapsed() < ONLINE_CACHE_TTL {
+                return count;
+            }
+        }
+
+        let window_start = Utc::now() - chrono::Duration::minutes(ONLINE_WINDOW_MINUTES);
+        let count = read_lock(&self.metrics)
+            .iter()
+            .rev()
+            .take_while(|m| m.timestamp > window_start)
+            .filter_map(|m| m.user_id)
+            .collect::<std::collections::HashSet<_>>()
+            .len() as u64;
+
+        *write_lock(&self.online_users_cache) = Some((Instant::now(), count));
+        count
+    }
+
+    // Registrar una llamada a una ruta deprecada
+    pub fn record_deprecated_call(&self, route: &str) {
+        let mut calls = write_lock(&self.deprecated_calls);
+        let usage = calls.entry(route.to_string()).or_insert(DeprecatedRouteUsage {
+            total_calls: 0,
+            last_called_at: Utc::now(),
+        });
+        usage.total_calls += 1;
+        usage.last_called_at = Utc::now();
+    }
+
+    // Uso de una ruta deprecada desde el inicio del proceso
+    pub fn deprecated_route_usage(&self, route: &str) -> Option<DeprecatedRouteUsage> {
+        read_lock(&self.deprecated_calls).get(route).cloned()
+    }
+
+    // Contar requests retenidos desde `since` que cumplen una condición
+    // (estimación escalada por el muestreo)
+    pub fn count_requests_since<F>(&self, since: DateTime<Utc>, predicate: F) -> u64
+    where
+        F: Fn(&RequestMetric) -> bool,
+    {
+        read_lock(&self.metrics)
+            .iter()
+            .filter(|m| m.timestamp > since && predicate(m))
+            .count() as u64
+            * self.sample_rate
+    }
+
+    // Últimos requests de un usuario (más recientes primero)
+    pub fn get_user_activity(&self, user_id: i32, limit: usize) -> Vec<RequestMetric> {
+        read_lock(&self.metrics)
+            .iter()
+            .rev()
+            .filter(|m| m.user_id == Some(user_id))
+            .take(limit)
+            .cloned()
+            .collect()
     }
 
     // Limpiar métricas antiguas (para ser llamado periódicamente)
     pub fn cleanup_old_metrics(&self, older_than: Duration) {
         let cutoff_time = Utc::now() - chrono::Duration::from_std(older_than).unwrap();
         
-        let mut metrics = self.metrics.write().unwrap();
+        let mut metrics = write_lock(&self.metrics);
+        let before = metrics.len();
         metrics.retain(|metric| metric.timestamp > cutoff_time);
+        let evicted = (before - metrics.len()) as u64;
+        
+        self.age_evictions.fetch_add(evicted, Ordering::Relaxed);
+        self.age_cleanup_runs.fetch_add(1, Ordering::Relaxed);
         
         tracing::info!(
             event = "metrics_cleanup",
             metrics_retained = metrics.len(),
+            metrics_evicted = evicted,
+            endpoint_stats = read_lock(&self.endpoint_stats).len(),
             cutoff_time = %cutoff_time,
             "🧹 Limpieza de métricas antiguas"
         );
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poisoned_locks_do_not_stop_the_collector() {
+        let collector = Arc::new(MetricsCollector::new(100, 1));
+        collector.record_request("GET".into(), "/api/v1/users".into(), 200, 10, None);
+
+        // Envenenar ambos locks con un panic mientras se sostienen
+        let poisoner = collector.clone();
+        let result = std::thread::spawn(move || {
+            let _metrics = poisoner.metrics.write().unwrap();
+            let _stats = poisoner.endpoint_stats.write().unwrap();
+            panic!("panic con los locks tomados");
+        })
+        .join();
+        assert!(result.is_err());
+        assert!(collector.metrics.is_poisoned());
+        assert!(collector.endpoint_stats.is_poisoned());
+
+        collector.record_request("GET".into(), "/api/v1/users".into(), 500, 30, Some(1));
+
+        let snapshot = collector.get_metrics_snapshot();
+        assert_eq!(snapshot.total_requests, 2);
+        let stats = collector.get_endpoint_metrics("GET", "/api/v1/users").unwrap();
+        assert_eq!(stats.total_requests, 2);
+        assert_eq!(stats.error_requests, 1);
+        assert_eq!(collector.get_user_activity(1, 10).len(), 1);
+
+        // El veneno se limpia al recuperar el lock
+        assert!(!collector.metrics.is_poisoned());
+        assert!(!collector.endpoint_stats.is_poisoned());
+    }
+}
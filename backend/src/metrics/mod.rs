@@ -1,4 +1,5 @@
 pub mod collector;
+pub mod privacy;
 
 pub use collector::{
     MetricsCollector,
@@ -6,4 +7,5 @@ pub use collector::{
     EndpointStats,
     MetricsSnapshot,
     HourlyStats,
+    reporting_timezone,
 };
\ No newline at end of file
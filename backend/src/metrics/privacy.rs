@@ -0,0 +1,91 @@
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde_json::Value;
+use sha2::Sha256;
+use std::sync::OnceLock;
+use crate::metrics::RequestMetric;
+use crate::models::user::User;
+
+// Los user ids en respuestas de métricas se seudonimizan salvo que
+// METRICS_EXPOSE_USER_IDS=true o quien consulta sea super-admin
+pub fn can_view_user_ids(viewer: &User) -> bool {
+    let expose = std::env::var("METRICS_EXPOSE_USER_IDS")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+
+    expose || viewer.is_super_admin()
+}
+
+// Clave de los seudónimos: METRICS_PSEUDONYM_KEY o, sin ella, una aleatoria por proceso
+// (los seudónimos cambian al reiniciar). No se reutiliza el secreto JWT: con RS256 queda
+// en su valor por defecto, que es público
+fn pseudonym_key() -> &'static [u8] {
+    static KEY: OnceLock<Vec<u8>> = OnceLock::new();
+    KEY.get_or_init(|| {
+        std::env::var("METRICS_PSEUDONYM_KEY")
+            .ok()
+            .filter(|k| !k.trim().is_empty())
+            .map(String::into_bytes)
+            .unwrap_or_else(|| {
+                let mut key = vec![0u8; 32];
+                rand::thread_rng().fill_bytes(&mut key);
+                key
+            })
+    })
+}
+
+// Seudónimo estable por usuario; la clave evita revertirlo probando ids
+pub fn pseudonymize(user_id: i32) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(pseudonym_key()).expect("HMAC acepta claves de cualquier tamaño");
+    mac.update(b"metrics-user:");
+    mac.update(user_id.to_string().as_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let hex: String = digest.iter().take(6).map(|b| format!("{:02x}", b)).collect();
+    format!("u_{}", hex)
+}
+
+pub fn user_id_value(user_id: Option<i32>, raw: bool) -> Value {
+    match user_id {
+        Some(id) if raw => Value::from(id),
+        Some(id) => Value::from(pseudonymize(id)),
+        None => Value::Null,
+    }
+}
+
+// Segmentos de ruta seguidos de un user id (/users/:id, /inspect/user/:id, /impersonate/:user_id)
+const USER_ID_PATH_PREFIXES: &[&str] = &["users", "user", "impersonate"];
+
+// Reemplazar los user ids que aparecen en la ruta por su seudónimo
+pub fn redact_path(path: &str) -> String {
+    let mut previous = "";
+    path.split('/')
+        .map(|segment| {
+            let redacted = match segment.parse::<i32>() {
+                Ok(id) if USER_ID_PATH_PREFIXES.contains(&previous) => pseudonymize(id),
+                _ => segment.to_string(),
+            };
+            previous = segment;
+            redacted
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// Serializar métricas de requests aplicando la visibilidad de user ids
+// (tanto el campo user_id como los ids dentro de la ruta)
+pub fn redact_request_metrics(metrics: &[RequestMetric], raw: bool) -> Vec<Value> {
+    metrics
+        .iter()
+        .filter_map(|metric| {
+            let mut value = serde_json::to_value(metric).ok()?;
+            if let Some(object) = value.as_object_mut() {
+                object.insert("user_id".to_string(), user_id_value(metric.user_id, raw));
+                if !raw {
+                    object.insert("path".to_string(), Value::from(redact_path(&metric.path)));
+                }
+            }
+            Some(value)
+        })
+        .collect()
+}
@@ -1,10 +1,17 @@
 use serde::{Deserialize, Serialize};
+use crate::models::user::Role;
+use crate::validation::password_policy::PolicyViolation;
 
 // Request de login
 #[derive(Debug, Deserialize)]
 pub struct LoginRequest {
+    // Email o nombre de usuario ("email" se mantiene por compatibilidad)
+    #[serde(alias = "identifier")]
     pub email: String,
     pub password: String,
+    // "Recordarme": token de acceso de larga duración (JWT_REMEMBER_EXPIRATION_DAYS)
+    #[serde(default)]
+    pub remember_me: bool,
 }
 
 // Request de registro
@@ -13,25 +20,182 @@ pub struct RegisterRequest {
     pub name: String,
     pub email: String,
     pub password: String,
+    // Opcional: permite iniciar sesión sin el email
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+// Request de renovación de token
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+// Request de recuperación de contraseña
+#[derive(Debug, Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+// Request de restablecimiento de contraseña
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+// Request de enlace de acceso sin contraseña
+#[derive(Debug, Deserialize)]
+pub struct MagicLinkRequest {
+    pub email: String,
+}
+
+// Canje del enlace de acceso por tokens
+#[derive(Debug, Deserialize)]
+pub struct MagicLinkVerifyRequest {
+    pub token: String,
+}
+
+// Confirmación de baja de la cuenta (usuario autenticado)
+#[derive(Debug, Deserialize)]
+pub struct DeleteAccountRequest {
+    pub current_password: String,
+}
+
+// Request de cambio de contraseña (usuario autenticado)
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+// Request con un código TOTP (confirmar activación de 2FA)
+#[derive(Debug, Deserialize)]
+pub struct TotpCodeRequest {
+    pub code: String,
+}
+
+// Request para completar el login con 2FA
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorChallengeRequest {
+    pub challenge_token: String,
+    pub code: String,
+}
+
+// Request de creación de API key (admin); el dueño por defecto es el admin que la crea
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub user_id: Option<i32>,
+    pub expires_in_days: Option<i64>,
+}
+
+// Response de login cuando el usuario tiene 2FA activo
+#[derive(Debug, Serialize)]
+pub struct TwoFactorChallengeResponse {
+    pub two_factor_required: bool,
+    pub challenge_token: String,
+    pub expires_at: i64,
+}
+
+// Request de token de admin de larga duración (ttl_hours por defecto y máximo 168)
+#[derive(Debug, Default, Deserialize)]
+pub struct AdminTokenRequest {
+    pub ttl_hours: Option<i64>,
+}
+
+// Request de introspección de token; sin `token` se usa el del header Authorization
+#[derive(Debug, Default, Deserialize)]
+pub struct IntrospectRequest {
+    pub token: Option<String>,
+}
+
+// Response de introspección: solo `active: false` si el token no sirve
+#[derive(Debug, Default, Serialize)]
+pub struct IntrospectResponse {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_admin: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seconds_remaining: Option<i64>,
+}
+
+impl IntrospectResponse {
+    pub fn inactive() -> Self {
+        Self::default()
+    }
+
+    pub fn from_claims(claims: &Claims) -> Self {
+        Self {
+            active: true,
+            sub: Some(claims.sub.clone()),
+            email: Some(claims.email.clone()),
+            is_admin: Some(claims.is_admin),
+            exp: Some(claims.exp),
+            iat: Some(claims.iat),
+            seconds_remaining: Some((claims.exp as i64 - chrono::Utc::now().timestamp()).max(0)),
+        }
+    }
 }
 
 // Response de autenticación exitosa
 #[derive(Debug, Serialize)]
 pub struct AuthResponse {
     pub token: String,
+    // Vacío (y omitido) en tokens de admin, que no se refrescan
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub refresh_token: String,
     pub user: crate::models::user::PublicUser,
     pub expires_at: i64, // timestamp
 }
 
+// Tipos de token
+pub const TOKEN_TYPE_ACCESS: &str = "access";
+pub const TOKEN_TYPE_REFRESH: &str = "refresh";
+pub const TOKEN_TYPE_2FA_CHALLENGE: &str = "2fa_challenge";
+// Claims sintéticos de requests autenticados con API key (nunca se firman)
+pub const TOKEN_TYPE_API_KEY: &str = "api_key";
+
+fn default_token_type() -> String {
+    TOKEN_TYPE_ACCESS.to_string()
+}
+
 // Claims del JWT
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub sub: String,    // user_id
     pub email: String,
     pub name: String,
-    pub is_admin: bool,
+    pub is_admin: bool, // role == admin (se mantiene para clientes existentes)
+    #[serde(default)]
+    pub role: Role,
     pub exp: usize,     // expiration time
     pub iat: usize,     // issued at
+    #[serde(default = "default_token_type")]
+    pub typ: String,    // "access" | "refresh" | "2fa_challenge" | "api_key"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>, // id único del token
+    #[serde(default)]
+    pub iss: String,    // emisor (JWT_ISSUER)
+    #[serde(default)]
+    pub aud: String,    // audiencia (JWT_AUDIENCE)
+    #[serde(default)]
+    pub ver: i32,       // token_version del usuario al emitirlo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<i32>, // sesión a la que pertenece (ver auth::sessions)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub rem: bool,      // emitido con "recordarme": no sirve para operaciones sensibles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonated_by: Option<i32>, // admin que suplanta al usuario (ver generate_impersonation_token)
 }
 
 // Response de error de autenticación
@@ -39,6 +203,9 @@ pub struct Claims {
 pub struct AuthError {
     pub error: String,
     pub message: String,
+    // Reglas de la política de contraseñas incumplidas (solo en weak_password)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<PolicyViolation>,
 }
 
 impl AuthError {
@@ -46,11 +213,19 @@ impl AuthError {
         Self {
             error: error.to_string(),
             message: message.to_string(),
+            violations: Vec::new(),
+        }
+    }
+    
+    pub fn weak_password(violations: Vec<PolicyViolation>) -> Self {
+        Self {
+            violations,
+            ..Self::new("weak_password", "La contraseña no cumple la política de seguridad")
         }
     }
     
     pub fn invalid_credentials() -> Self {
-        Self::new("invalid_credentials", "Email o contraseña incorrectos")
+        Self::new("invalid_credentials", "Email, usuario o contraseña incorrectos")
     }
     
     pub fn user_not_found() -> Self {
@@ -61,8 +236,78 @@ impl AuthError {
         Self::new("email_exists", "Este email ya está registrado")
     }
     
+    pub fn username_exists() -> Self {
+        Self::new("username_exists", "Este nombre de usuario ya está registrado")
+    }
+    
+    pub fn invalid_username() -> Self {
+        Self::new(
+            "invalid_username",
+            "El nombre de usuario debe tener entre 3 y 30 letras o números",
+        )
+    }
+    
+    pub fn disposable_email() -> Self {
+        Self::new("disposable_email", "No se permiten emails de dominios desechables")
+    }
+    
+    pub fn fresh_login_required() -> Self {
+        Self::new(
+            "fresh_login_required",
+            "Esta operación requiere iniciar sesión de nuevo sin \"recordarme\"",
+        )
+    }
+    
     pub fn invalid_token() -> Self {
-        Self::new("invalid_token", "Token inválido o expirado")
+        Self::new("invalid_token", "Token inválido")
+    }
+    
+    pub fn token_expired() -> Self {
+        Self::new("token_expired", "El token expiró")
+    }
+    
+    // Error de verificación de un JWT: distingue token vencido de token inválido
+    pub fn from_jwt_error(error: &jsonwebtoken::errors::Error) -> Self {
+        match error.kind() {
+            jsonwebtoken::errors::ErrorKind::ExpiredSignature => Self::token_expired(),
+            _ => Self::invalid_token(),
+        }
+    }
+    
+    pub fn invalid_refresh_token() -> Self {
+        Self::new("invalid_refresh_token", "Refresh token inválido, expirado o revocado")
+    }
+    
+    pub fn insufficient_scope(scope: &str) -> Self {
+        Self::new(
+            "insufficient_scope",
+            &format!("La API key no tiene el scope requerido: {}", scope),
+        )
+    }
+    
+    pub fn concurrency_limit(max: usize) -> Self {
+        Self::new(
+            "concurrency_limit",
+            &format!("Demasiados requests simultáneos (máximo {})", max),
+        )
+    }
+    
+    pub fn account_locked(retry_after_minutes: u64) -> Self {
+        Self::new(
+            "account_locked",
+            &format!(
+                "Demasiados intentos fallidos. Intenta de nuevo en {} minuto(s)",
+                retry_after_minutes
+            ),
+        )
+    }
+    
+    pub fn invalid_totp_code() -> Self {
+        Self::new("invalid_totp_code", "Código de verificación inválido o expirado")
+    }
+    
+    pub fn token_revoked() -> Self {
+        Self::new("token_revoked", "El token fue revocado")
     }
     
     pub fn unauthorized() -> Self {
@@ -72,4 +317,16 @@ impl AuthError {
     pub fn forbidden() -> Self {
         Self::new("forbidden", "No tienes permisos para esta acción")
     }
+
+    pub fn impersonation_not_allowed() -> Self {
+        Self::new("impersonation_not_allowed", "Acción no permitida durante una suplantación")
+    }
+
+    pub fn invalid_magic_link() -> Self {
+        Self::new("invalid_magic_link", "Enlace de acceso inválido o expirado")
+    }
+
+    pub fn magic_link_used() -> Self {
+        Self::new("magic_link_used", "El enlace de acceso ya fue utilizado")
+    }
 }
\ No newline at end of file
@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+// Motivo del mensaje de contacto
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContactSubject {
+    Fraud,
+    Support,
+    Account,
+    Other,
+}
+
+impl ContactSubject {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fraud => "fraud",
+            Self::Support => "support",
+            Self::Account => "account",
+            Self::Other => "other",
+        }
+    }
+}
+
+// Request del formulario público de contacto
+#[derive(Debug, Deserialize)]
+pub struct ContactRequest {
+    pub name: String,
+    pub email: String,
+    pub subject: ContactSubject,
+    pub message: String,
+    // Token del captcha (obligatorio solo si CAPTCHA_SECRET está configurado)
+    pub captcha_token: Option<String>,
+    // Honeypot: campo oculto en el formulario; si viene con contenido es un bot
+    #[serde(default)]
+    pub website: Option<String>,
+}
+
+// Mensaje de contacto tal como lo ven los admins
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ContactMessage {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+    pub subject: String,
+    pub message: String,
+    pub ip_address: String,
+    pub status: String, // "open" | "handled"
+    pub assigned_to: Option<i32>,
+    pub handled_by: Option<i32>,
+    #[serde(with = "crate::models::timestamp::option")]
+    pub handled_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::models::timestamp")]
+    pub created_at: DateTime<Utc>,
+}
+
+// Asignar un mensaje a un admin (por defecto al que hace el request)
+#[derive(Debug, Default, Deserialize)]
+pub struct AssignContactRequest {
+    pub admin_id: Option<i32>,
+}
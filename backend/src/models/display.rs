@@ -0,0 +1,119 @@
+use axum::{extract::FromRequestParts, http::request::Parts};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::convert::Infallible;
+use std::sync::OnceLock;
+
+// Los campos canónicos siguen en UTC (ver models::timestamp); esto solo agrega
+// versiones legibles para mostrar en la zona horaria del cliente
+
+// Zona por defecto (DISPLAY_TIMEZONE, nombre IANA; por defecto America/La_Paz)
+fn default_timezone() -> Tz {
+    static TIMEZONE: OnceLock<Tz> = OnceLock::new();
+    *TIMEZONE.get_or_init(|| {
+        std::env::var("DISPLAY_TIMEZONE")
+            .ok()
+            .and_then(|name| name.trim().parse().ok())
+            .unwrap_or(chrono_tz::America::La_Paz)
+    })
+}
+
+// Zona horaria de presentación pedida con el header X-Timezone (inválida = por defecto)
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayTimezone(pub Tz);
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for DisplayTimezone
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let timezone = parts
+            .headers
+            .get("x-timezone")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|name| name.trim().parse().ok())
+            .unwrap_or_else(default_timezone);
+
+        Ok(Self(timezone))
+    }
+}
+
+// Fecha y hora local, p. ej. "16/10/2025 14:05"
+pub fn format_local(value: &DateTime<Utc>, timezone: Tz) -> String {
+    value.with_timezone(&timezone).format("%d/%m/%Y %H:%M").to_string()
+}
+
+// Tiempo relativo en español, p. ej. "hace 3 horas" o "en 2 días"
+pub fn relative_es(value: &DateTime<Utc>, now: &DateTime<Utc>) -> String {
+    let seconds = (*now - *value).num_seconds();
+    let past = seconds >= 0;
+    let seconds = seconds.unsigned_abs();
+
+    if seconds < 60 {
+        return if past { "hace un momento".to_string() } else { "en un momento".to_string() };
+    }
+
+    let (amount, singular, plural) = match seconds {
+        s if s < 3_600 => (s / 60, "minuto", "minutos"),
+        s if s < 86_400 => (s / 3_600, "hora", "horas"),
+        s if s < 30 * 86_400 => (s / 86_400, "día", "días"),
+        s if s < 365 * 86_400 => (s / (30 * 86_400), "mes", "meses"),
+        s => (s / (365 * 86_400), "año", "años"),
+    };
+    let unit = if amount == 1 { singular } else { plural };
+
+    if past {
+        format!("hace {} {}", amount, unit)
+    } else {
+        format!("en {} {}", amount, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn afternoon_utc() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 10, 16, 18, 5, 0).unwrap()
+    }
+
+    #[test]
+    fn format_local_uses_la_paz_offset() {
+        // La Paz es UTC-4 todo el año (sin horario de verano)
+        assert_eq!(format_local(&afternoon_utc(), chrono_tz::America::La_Paz), "16/10/2025 14:05");
+        let january = Utc.with_ymd_and_hms(2025, 1, 16, 18, 5, 0).unwrap();
+        assert_eq!(format_local(&january, chrono_tz::America::La_Paz), "16/01/2025 14:05");
+    }
+
+    #[test]
+    fn format_local_crosses_the_date_line() {
+        let early = Utc.with_ymd_and_hms(2025, 10, 16, 2, 30, 0).unwrap();
+        assert_eq!(format_local(&early, chrono_tz::America::La_Paz), "15/10/2025 22:30");
+        assert_eq!(format_local(&early, Tz::UTC), "16/10/2025 02:30");
+    }
+
+    #[test]
+    fn relative_es_past_and_future() {
+        let now = afternoon_utc();
+        let cases = [
+            (Duration::seconds(-30), "hace un momento"),
+            (Duration::seconds(30), "en un momento"),
+            (Duration::minutes(-1), "hace 1 minuto"),
+            (Duration::minutes(-45), "hace 45 minutos"),
+            (Duration::hours(-3), "hace 3 horas"),
+            (Duration::hours(1), "en 1 hora"),
+            (Duration::days(-1), "hace 1 día"),
+            (Duration::days(2), "en 2 días"),
+            (Duration::days(-60), "hace 2 meses"),
+            (Duration::days(-400), "hace 1 año"),
+            (Duration::days(-800), "hace 2 años"),
+        ];
+        for (offset, expected) in cases {
+            assert_eq!(relative_es(&(now + offset), &now), expected, "{:?}", offset);
+        }
+    }
+}
@@ -1,2 +1,5 @@
 pub mod user;
-pub mod auth;
\ No newline at end of file
+pub mod auth;
+pub mod contact;
+pub mod display;
+pub mod timestamp;
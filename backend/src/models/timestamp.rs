@@ -0,0 +1,128 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Deserializer, Serializer};
+
+// Formato único para todos los timestamps de la API: RFC3339 en UTC con milisegundos
+// Uso: #[serde(with = "crate::models::timestamp")]
+pub fn serialize<S>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&format(value))
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&raw)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(serde::de::Error::custom)
+}
+
+// Formatear un timestamp con el formato estándar de la API
+pub fn format(value: &DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+// Timestamp actual ya formateado (para respuestas construidas con json!)
+pub fn now() -> String {
+    format(&Utc::now())
+}
+
+// Variante para campos opcionales (solo serialización): #[serde(with = "crate::models::timestamp::option")]
+pub mod option {
+    use chrono::{DateTime, Utc};
+    use serde::Serializer;
+
+    pub fn serialize<S>(value: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(value) => super::serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+    use crate::models::auth::AuthResponse;
+    use crate::models::user::{MinimalUser, Role, User};
+
+    fn instant() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 10, 16, 14, 5, 9).unwrap() + chrono::Duration::microseconds(123_456)
+    }
+
+    fn user() -> User {
+        User {
+            id: 7,
+            name: "Ana".to_string(),
+            email: "ana@ventalibre.bo".to_string(),
+            password_hash: Some("hash".to_string()),
+            role: Role::User,
+            is_active: true,
+            token_version: 1,
+            username: None,
+            created_at: instant(),
+            updated_at: instant(),
+        }
+    }
+
+    #[test]
+    fn format_is_rfc3339_utc_with_millis() {
+        assert_eq!(format(&instant()), "2025-10-16T14:05:09.123Z");
+        // Sin fracción también lleva los milisegundos
+        assert_eq!(format(&Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap()), "2025-01-02T03:04:05.000Z");
+    }
+
+    #[test]
+    fn public_user_shape() {
+        let value = serde_json::to_value(user().to_public()).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "id": 7,
+                "name": "Ana",
+                "email": "ana@ventalibre.bo",
+                "role": "user",
+                "is_admin": false,
+                "is_active": true,
+                "created_at": "2025-10-16T14:05:09.123Z"
+            })
+        );
+    }
+
+    #[test]
+    fn auth_response_user_uses_the_same_format() {
+        let response = AuthResponse {
+            token: "t".to_string(),
+            refresh_token: String::new(),
+            user: user().to_public(),
+            expires_at: 1_760_623_509,
+        };
+        let value = serde_json::to_value(response).unwrap();
+        assert_eq!(value["user"]["created_at"], "2025-10-16T14:05:09.123Z");
+        assert_eq!(value["expires_at"], 1_760_623_509);
+        assert!(value.get("refresh_token").is_none());
+    }
+
+    #[test]
+    fn minimal_user_member_since() {
+        let minimal = MinimalUser { id: 7, name: "Ana".to_string(), member_since: instant() };
+        assert_eq!(
+            serde_json::to_value(minimal).unwrap(),
+            json!({ "id": 7, "name": "Ana", "member_since": "2025-10-16T14:05:09.123Z" })
+        );
+    }
+
+    #[test]
+    fn deserialize_accepts_offsets() {
+        let parsed: DateTime<Utc> = deserialize(serde_json::Value::from("2025-10-16T10:05:09.123-04:00")).unwrap();
+        assert_eq!(format(&parsed), "2025-10-16T14:05:09.123Z");
+    }
+}
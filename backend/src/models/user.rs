@@ -1,5 +1,40 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use crate::models::display::{self, DisplayTimezone};
+
+// Rol del usuario, de menor a mayor privilegio (cada rol incluye los permisos de los anteriores)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    #[default]
+    User,
+    Moderator,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Moderator => "moderator",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+// La columna `role` es VARCHAR (ver migración users_roles)
+impl TryFrom<String> for Role {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "user" => Ok(Self::User),
+            "moderator" => Ok(Self::Moderator),
+            "admin" => Ok(Self::Admin),
+            other => Err(format!("rol desconocido: {}", other)),
+        }
+    }
+}
 
 // Modelo completo del usuario (para base de datos)
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
@@ -8,10 +43,17 @@ pub struct User {
     pub name: String,
     pub email: String,
     pub password_hash: Option<String>,
-    pub is_admin: bool,
+    #[sqlx(try_from = "String")]
+    pub role: Role,
     pub is_active: bool,
-    pub created_at: Option<DateTime<Utc>>,
-    pub updated_at: Option<DateTime<Utc>>,
+    // Se incrementa para invalidar todos los tokens emitidos al usuario
+    pub token_version: i32,
+    // Alternativa al email para iniciar sesión (en minúsculas)
+    pub username: Option<String>,
+    #[serde(with = "crate::models::timestamp")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::models::timestamp")]
+    pub updated_at: DateTime<Utc>,
 }
 
 // Usuario público (sin password_hash)
@@ -20,9 +62,38 @@ pub struct PublicUser {
     pub id: i32,
     pub name: String,
     pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    pub role: Role,
+    // Compatibilidad con clientes anteriores a los roles: role == admin
     pub is_admin: bool,
     pub is_active: bool,
-    pub created_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::models::timestamp")]
+    pub created_at: DateTime<Utc>,
+    // Solo de presentación (ver with_display); created_at sigue siendo el valor canónico
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at_local: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at_relative: Option<String>,
+}
+
+// Vista de un usuario que ven otros usuarios: sin email ni datos de la cuenta.
+// La completa (PublicUser) queda para el propio usuario y los admins
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MinimalUser {
+    pub id: i32,
+    pub name: String,
+    #[serde(with = "crate::models::timestamp")]
+    pub member_since: DateTime<Utc>,
+}
+
+impl PublicUser {
+    // Agregar fechas legibles en la zona horaria del cliente
+    pub fn with_display(mut self, timezone: DisplayTimezone) -> Self {
+        self.created_at_local = Some(display::format_local(&self.created_at, timezone.0));
+        self.created_at_relative = Some(display::relative_es(&self.created_at, &Utc::now()));
+        self
+    }
 }
 
 // DTO para crear usuario
@@ -38,7 +109,60 @@ pub struct CreateUserRequest {
 pub struct UpdateUserRequest {
     pub name: Option<String>,
     pub email: Option<String>,
+    // No se aplica: la contraseña solo se cambia en /auth/change-password (ver update_user)
     pub password: Option<String>,
+    // Campos privilegiados: solo se aplican si quien actualiza es admin
+    pub role: Option<Role>,
+    // Obsoleto: equivale a role admin (true) o user (false); `role` tiene prioridad
+    pub is_admin: Option<bool>,
+    pub is_active: Option<bool>,
+}
+
+// Cambios efectivos a aplicar, ya filtrados según el rol de quien actualiza
+#[derive(Debug, Default)]
+pub struct UserChanges {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub role: Option<Role>,
+    pub is_active: Option<bool>,
+}
+
+impl UpdateUserRequest {
+    // Mapear explícitamente los campos permitidos (protección contra mass-assignment)
+    pub fn allowed_changes(self, caller_is_admin: bool) -> UserChanges {
+        let mut changes = UserChanges {
+            name: self.name,
+            email: self.email,
+            ..Default::default()
+        };
+
+        if caller_is_admin {
+            changes.role = self
+                .role
+                .or_else(|| self.is_admin.map(|admin| if admin { Role::Admin } else { Role::User }));
+            changes.is_active = self.is_active;
+        }
+
+        changes
+    }
+
+    // Indica si el request toca credenciales o identidad (email, contraseña, rol): un token
+    // de suplantación no puede cambiarlos, un email nuevo permitiría robar la cuenta
+    pub fn has_identity_fields(&self) -> bool {
+        self.email.is_some() || self.password.is_some() || self.role.is_some() || self.is_admin.is_some()
+    }
+
+    // Indica si el request intenta modificar campos privilegiados
+    pub fn has_privileged_fields(&self) -> bool {
+        self.role.is_some() || self.is_admin.is_some() || self.is_active.is_some()
+    }
+}
+
+// DTO para fusionar cuentas duplicadas (admin)
+#[derive(Debug, Deserialize)]
+pub struct MergeUsersRequest {
+    pub source_id: i32,
+    pub target_id: i32,
 }
 
 impl User {
@@ -48,15 +172,33 @@ impl User {
             id: self.id,
             name: self.name.clone(),
             email: self.email.clone(),
-            is_admin: self.is_admin,
+            username: self.username.clone(),
+            role: self.role,
+            is_admin: self.is_admin(),
             is_active: self.is_active,
             created_at: self.created_at,
+            created_at_local: None,
+            created_at_relative: None,
         }
     }
     
-    // Verificar si el usuario es admin
+    // Verificar si el usuario activo tiene al menos el rol indicado
+    pub fn has_role(&self, role: Role) -> bool {
+        self.is_active && self.role >= role
+    }
+    
+    // Verificar si el usuario es admin (equivale a has_role(Role::Admin))
     pub fn is_admin(&self) -> bool {
-        self.is_admin && self.is_active
+        self.has_role(Role::Admin)
+    }
+    
+    // Super-admin: admin listado en SUPER_ADMIN_USER_IDS (ids separados por coma)
+    pub fn is_super_admin(&self) -> bool {
+        self.is_admin()
+            && std::env::var("SUPER_ADMIN_USER_IDS")
+                .unwrap_or_default()
+                .split(',')
+                .any(|id| id.trim().parse() == Ok(self.id))
     }
     
     // Verificar si el usuario está activo
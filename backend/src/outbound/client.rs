@@ -0,0 +1,133 @@
+use axum::body::Bytes;
+use reqwest::{RequestBuilder, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use crate::outbound::log::{outbound_log, OutboundCall};
+
+// Parámetros de query que nunca se guardan en el registro
+const SENSITIVE_PARAMS: &[&str] = &[
+    "access_token", "api_key", "client_secret", "code", "key", "password",
+    "secret", "sig", "signature", "token",
+];
+
+// Respuesta ya leída completa (el cuerpo se necesita para registrar fallos)
+pub struct OutboundResponse {
+    pub status: StatusCode,
+    pub body: Bytes,
+}
+
+impl OutboundResponse {
+    pub fn is_success(&self) -> bool {
+        self.status.is_success()
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+// Cliente compartido para todas las llamadas salientes (envíalas con `send`)
+pub fn http_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+// Bytes del cuerpo que se guardan en fallos (OUTBOUND_LOG_BODY_BYTES, por defecto 512)
+fn body_limit() -> usize {
+    static LIMIT: OnceLock<usize> = OnceLock::new();
+    *LIMIT.get_or_init(|| {
+        std::env::var("OUTBOUND_LOG_BODY_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(512)
+    })
+}
+
+// URL sin usuario/contraseña ni parámetros sensibles
+pub fn sanitize_url(url: &Url) -> String {
+    let mut url = url.clone();
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+
+    if url.query().is_some() {
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(key, value)| {
+                let value = if SENSITIVE_PARAMS.contains(&key.to_lowercase().as_str()) {
+                    "REDACTED".to_string()
+                } else {
+                    value.into_owned()
+                };
+                (key.into_owned(), value)
+            })
+            .collect();
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+
+    url.to_string()
+}
+
+// Enviar un request registrando destino, estado, duración y (en fallos) el cuerpo
+pub async fn send(destination: &str, request: RequestBuilder) -> Result<OutboundResponse, reqwest::Error> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    let method = request.method().to_string();
+    let url = sanitize_url(request.url());
+    let start = Instant::now();
+
+    let result = match client.execute(request).await {
+        Ok(response) => {
+            let status = response.status();
+            response.bytes().await.map(|body| OutboundResponse { status, body })
+        }
+        Err(e) => Err(e),
+    };
+
+    let (status, success, error, response_body) = match &result {
+        Ok(response) if response.is_success() => (Some(response.status.as_u16()), true, None, None),
+        Ok(response) => {
+            let body = &response.body[..response.body.len().min(body_limit())];
+            (
+                Some(response.status.as_u16()),
+                false,
+                None,
+                Some(String::from_utf8_lossy(body).into_owned()),
+            )
+        }
+        Err(e) => (e.status().map(|s| s.as_u16()), false, Some(e.to_string()), None),
+    };
+    let duration_ms = start.elapsed().as_millis() as u64;
+
+    if !success {
+        tracing::warn!(
+            event = "outbound_call_failed",
+            destination = %destination,
+            method = %method,
+            url = %url,
+            status = ?status,
+            duration_ms = duration_ms,
+            error = ?error,
+            "🌐 Llamada saliente fallida"
+        );
+    }
+
+    outbound_log().record(OutboundCall {
+        destination: destination.to_string(),
+        method,
+        url,
+        status,
+        success,
+        duration_ms,
+        error,
+        response_body,
+        timestamp: chrono::Utc::now(),
+    });
+
+    result
+}
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{OnceLock, RwLock};
+
+// Llamada HTTP saliente registrada (URL ya sin credenciales)
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboundCall {
+    pub destination: String,
+    pub method: String,
+    pub url: String,
+    pub status: Option<u16>,
+    pub success: bool,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+    // Solo en fallos, truncado a OUTBOUND_LOG_BODY_BYTES
+    pub response_body: Option<String>,
+    #[serde(with = "crate::models::timestamp")]
+    pub timestamp: DateTime<Utc>,
+}
+
+// Acumulado por destino desde el arranque
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DestinationStats {
+    pub total_calls: u64,
+    pub failed_calls: u64,
+    pub success_rate_percent: f64,
+    pub avg_duration_ms: f64,
+    #[serde(skip)]
+    total_duration_ms: u64,
+    pub last_failure_at: Option<String>,
+}
+
+// Registro acotado de llamadas salientes (OUTBOUND_LOG_MAX, por defecto 1000)
+// con persistencia opcional en outbound_calls (OUTBOUND_LOG_PERSIST=true)
+pub struct OutboundLog {
+    max_calls: usize,
+    calls: RwLock<VecDeque<OutboundCall>>,
+    destinations: RwLock<HashMap<String, DestinationStats>>,
+    pool: OnceLock<PgPool>,
+}
+
+impl OutboundLog {
+    fn from_env() -> Self {
+        let max_calls = std::env::var("OUTBOUND_LOG_MAX")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1000usize)
+            .max(1);
+
+        Self {
+            max_calls,
+            calls: RwLock::new(VecDeque::new()),
+            destinations: RwLock::new(HashMap::new()),
+            pool: OnceLock::new(),
+        }
+    }
+
+    // Activar la persistencia en BD si está configurada
+    pub fn enable_persistence(&self, pool: PgPool) {
+        let enabled = std::env::var("OUTBOUND_LOG_PERSIST")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        if enabled && self.pool.set(pool).is_ok() {
+            tracing::info!("🗄️ Persistencia de llamadas salientes habilitada");
+        }
+    }
+
+    pub fn record(&self, call: OutboundCall) {
+        {
+            let mut destinations = self.destinations.write().unwrap_or_else(|e| e.into_inner());
+            let stats = destinations.entry(call.destination.clone()).or_default();
+            stats.total_calls += 1;
+            stats.total_duration_ms += call.duration_ms;
+            if !call.success {
+                stats.failed_calls += 1;
+                stats.last_failure_at = Some(crate::models::timestamp::format(&call.timestamp));
+            }
+            stats.success_rate_percent =
+                (stats.total_calls - stats.failed_calls) as f64 / stats.total_calls as f64 * 100.0;
+            stats.avg_duration_ms = stats.total_duration_ms as f64 / stats.total_calls as f64;
+        }
+
+        if let Some(pool) = self.pool.get() {
+            let pool = pool.clone();
+            let call = call.clone();
+            tokio::spawn(async move {
+                if let Err(e) = persist(&pool, &call).await {
+                    tracing::warn!(error = %e, "⚠️ No se pudo persistir la llamada saliente");
+                }
+            });
+        }
+
+        let mut calls = self.calls.write().unwrap_or_else(|e| e.into_inner());
+        if calls.len() >= self.max_calls {
+            calls.pop_front();
+        }
+        calls.push_back(call);
+    }
+
+    // Llamadas más recientes primero, filtradas por destino y resultado
+    pub fn recent<F>(&self, limit: usize, predicate: F) -> Vec<OutboundCall>
+    where
+        F: Fn(&OutboundCall) -> bool,
+    {
+        self.calls
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .rev()
+            .filter(|call| predicate(call))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub fn destination_stats(&self) -> HashMap<String, DestinationStats> {
+        self.destinations
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+async fn persist(pool: &PgPool, call: &OutboundCall) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO outbound_calls
+            (destination, method, url, status, success, duration_ms, error, response_body, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+    )
+    .bind(&call.destination)
+    .bind(&call.method)
+    .bind(&call.url)
+    .bind(call.status.map(i32::from))
+    .bind(call.success)
+    .bind(call.duration_ms as i64)
+    .bind(&call.error)
+    .bind(&call.response_body)
+    .bind(call.timestamp)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub fn outbound_log() -> &'static OutboundLog {
+    static LOG: OnceLock<OutboundLog> = OnceLock::new();
+    LOG.get_or_init(OutboundLog::from_env)
+}
@@ -0,0 +1,5 @@
+pub mod client;
+pub mod log;
+
+pub use client::{http_client, send};
+pub use log::outbound_log;
@@ -0,0 +1,89 @@
+use axum::{
+    middleware,
+    routing::{delete, get, post},
+    Router,
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+use crate::auth::middleware::{auth_middleware, require_role};
+use crate::database::transaction_middleware;
+use crate::handlers::admin;
+use crate::metrics::MetricsCollector;
+use crate::models::user::Role;
+
+// Rutas de administración: requieren token válido y rol de admin (moderador para contacto)
+pub fn create_admin_routes(pool: PgPool, metrics_collector: Arc<MetricsCollector>) -> Router<PgPool> {
+    // Rutas que leen del colector de métricas
+    let metrics_routes = Router::new()
+        .route("/users/:id/activity", get(admin::get_user_activity))
+        .route("/deprecated-routes", get(admin::get_deprecated_routes))
+        .with_state(metrics_collector.clone());
+
+    // Inspección para soporte: necesita BD y colector de métricas
+    let inspect_routes = Router::new()
+        .route("/inspect/user/:id", get(admin::inspect_user))
+        .with_state((pool.clone(), metrics_collector));
+
+    let contact_routes = Router::new()
+        .route("/contact-messages", get(admin::list_contact_messages))
+        .route("/contact-messages/:id/assign", post(admin::assign_contact_message))
+        .route("/contact-messages/:id/handle", post(admin::handle_contact_message))
+        .route_layer(middleware::from_fn(require_role(Role::Moderator)))
+        .route_layer(middleware::from_fn_with_state(pool.clone(), auth_middleware));
+
+    Router::new()
+        .route("/disposable-domains/reload", post(admin::reload_disposable_domains))
+        .route("/config/reload", post(admin::reload_config))
+        .route(
+            "/users/merge",
+            post(admin::merge_users).layer(middleware::from_fn(transaction_middleware)),
+        )
+        .route("/impersonate/:user_id", post(admin::impersonate_user))
+        .route("/users/:id/restore", post(admin::restore_user))
+        .route("/deprecations", get(admin::get_deprecation_usage))
+        .route("/outbound-calls", get(admin::get_outbound_calls))
+        .route("/events", get(admin::list_domain_events))
+        .route("/api-keys", get(admin::list_api_keys).post(admin::create_api_key))
+        .route("/api-keys/:id", delete(admin::revoke_api_key))
+        .merge(metrics_routes)
+        .merge(inspect_routes)
+        .route_layer(middleware::from_fn(require_role(Role::Admin)))
+        .route_layer(middleware::from_fn_with_state(pool.clone(), auth_middleware))
+        // Mensajes de contacto: también los atienden los moderadores
+        .merge(contact_routes)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use crate::models::user::Role;
+    use crate::test_support::TestApp;
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL"]
+    async fn admin_routes_require_the_route_role() {
+        let app = TestApp::spawn().await;
+
+        // (rol, /admin/events solo admin, /admin/contact-messages desde moderador)
+        let expected = [
+            (Role::User, StatusCode::FORBIDDEN, StatusCode::FORBIDDEN),
+            (Role::Moderator, StatusCode::FORBIDDEN, StatusCode::OK),
+            (Role::Admin, StatusCode::OK, StatusCode::OK),
+        ];
+
+        for (role, events, contact) in expected {
+            let (_, token) = app.user_with_role(role).await;
+
+            let (status, _, body) = app.request(Method::GET, "/api/v1/admin/events", Some(&token), None).await;
+            assert_eq!(status, events, "{:?} en /admin/events: {}", role, body);
+            if status == StatusCode::FORBIDDEN {
+                assert_eq!(body["error"], "forbidden");
+            }
+
+            let (status, _, body) = app.request(Method::GET, "/api/v1/admin/contact-messages", Some(&token), None).await;
+            assert_eq!(status, contact, "{:?} en /admin/contact-messages: {}", role, body);
+        }
+
+        app.finish().await;
+    }
+}
@@ -1,16 +1,57 @@
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, post},
     Router,
 };
 use sqlx::PgPool;
-use crate::handlers::auth;
+use crate::auth::middleware::{auth_middleware, deny_impersonation, require_role};
+use crate::auth::rate_limit::auth_rate_limit_middleware;
+use crate::handlers::{auth, oauth};
+use crate::models::user::Role;
+
+pub fn create_auth_routes(pool: PgPool) -> Router<PgPool> {
+    // Rutas solo para admins
+    let admin_routes = Router::new()
+        .route("/admin-token", post(auth::issue_admin_token))
+        .route_layer(middleware::from_fn(require_role(Role::Admin)))
+        .route_layer(middleware::from_fn_with_state(pool.clone(), auth_middleware));
+
+    // Credenciales, sesiones y datos personales: no disponibles con un token de suplantación
+    let credential_routes = Router::new()
+        .route("/change-password", post(auth::change_password))
+        .route("/logout-all", post(auth::logout_all))
+        .route("/me/export", get(auth::export_personal_data))
+        .route("/sessions/:id", delete(auth::revoke_session))
+        .route("/devices/:id", delete(auth::revoke_device))
+        .route("/2fa/enable", post(auth::enable_two_factor))
+        .route("/2fa/verify", post(auth::verify_two_factor))
+        .route("/oauth/google/link", post(oauth::google_link))
+        .route_layer(middleware::from_fn(deny_impersonation))
+        .route_layer(middleware::from_fn_with_state(pool.clone(), auth_middleware));
 
-pub fn create_auth_routes() -> Router<PgPool> {
     Router::new()
+        // Rutas autenticadas
+        .route("/me", delete(auth::delete_account))
+        .route("/sessions", get(auth::list_sessions))
+        .route("/devices", get(auth::list_devices))
+        .route_layer(middleware::from_fn_with_state(pool, auth_middleware))
         // Rutas públicas (sin autenticación)
         .route("/register", post(auth::register))
         .route("/login", post(auth::login))
+        .route("/refresh", post(auth::refresh))
+        .route("/forgot-password", post(auth::forgot_password))
+        .route("/reset-password", post(auth::reset_password))
+        .route("/magic-link", post(auth::request_magic_link))
+        .route("/magic-link/verify", post(auth::verify_magic_link))
+        .route("/2fa/challenge", post(auth::two_factor_challenge))
+        .route("/oauth/google", get(oauth::google_authorize))
+        .route("/oauth/google/callback", get(oauth::google_callback))
         // Rutas que manejan autenticación internamente
         .route("/me", get(auth::get_current_user))
         .route("/logout", post(auth::logout))
+        .route("/introspect", post(auth::introspect))
+        .merge(credential_routes)
+        .merge(admin_routes)
+        // Límite de requests por IP en todas las rutas de auth
+        .route_layer(middleware::from_fn(auth_rate_limit_middleware))
 }
\ No newline at end of file
@@ -0,0 +1,8 @@
+use axum::{routing::post, Router};
+use sqlx::PgPool;
+use crate::handlers::contact;
+
+// Formulario público de contacto: sin autenticación (rate limit y captcha en el handler)
+pub fn create_contact_routes() -> Router<PgPool> {
+    Router::new().route("/", post(contact::submit_contact))
+}
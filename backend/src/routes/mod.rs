@@ -1,11 +1,17 @@
 pub mod users;
 pub mod auth;
+pub mod admin;
+pub mod contact;
 
 use axum::Router;
 use sqlx::PgPool;
+use std::sync::Arc;
+use crate::metrics::MetricsCollector;
 
-pub fn create_routes() -> Router<PgPool> {
+pub fn create_routes(pool: PgPool, metrics_collector: Arc<MetricsCollector>) -> Router<PgPool> {
     Router::new()
-        .nest("/users", users::create_user_routes())
-        .nest("/auth", auth::create_auth_routes())
-}
\ No newline at end of file
+        .nest("/users", users::create_user_routes(pool.clone()))
+        .nest("/auth", auth::create_auth_routes(pool.clone()))
+        .nest("/contact", contact::create_contact_routes())
+        .nest("/admin", admin::create_admin_routes(pool, metrics_collector))
+}
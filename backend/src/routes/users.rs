@@ -1,13 +1,28 @@
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{get, patch, post},
     Router,
 };
 use sqlx::PgPool;
-use crate::handlers::users;
+use crate::auth::middleware::{auth_middleware, require_role};
+use crate::handlers::{admin, users};
+use crate::models::user::Role;
+
+pub fn create_user_routes(pool: PgPool) -> Router<PgPool> {
+    let admin_routes = Router::new()
+        .route("/:id/impersonate", post(admin::impersonate_user))
+        .route_layer(middleware::from_fn(require_role(Role::Admin)))
+        .route_layer(middleware::from_fn_with_state(pool.clone(), auth_middleware));
 
-pub fn create_user_routes() -> Router<PgPool> {
     Router::new()
+        // Rutas autenticadas
+        .route("/:id", patch(users::update_user))
+        .route("/:id/revoke-tokens", post(users::revoke_user_tokens))
+        .route_layer(middleware::from_fn_with_state(pool.clone(), auth_middleware))
+        // Suplantación (solo admins); mismo handler que /admin/impersonate/:user_id
+        .merge(admin_routes)
+        // Rutas públicas
         .route("/", get(users::get_all_users))
         .route("/", post(users::create_user))
         .route("/:id", get(users::get_user_by_id))
-}
\ No newline at end of file
+}
@@ -0,0 +1,198 @@
+use axum::Router;
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto::Builder;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use std::env;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tower::Service;
+
+// Configuración de conexiones HTTP
+//   HTTP_KEEP_ALIVE=true                 keep-alive de HTTP/1.1
+//   HTTP_HEADER_READ_TIMEOUT_SECS=30     tiempo máximo para recibir los headers (HTTP/1.1)
+//   HTTP2_ENABLED=true                   aceptar HTTP/2 en texto plano (h2c) además de HTTP/1.1
+//   HTTP2_ONLY=false                     aceptar solo HTTP/2 (detrás de un proxy h2c)
+//   HTTP2_KEEP_ALIVE_INTERVAL_SECS=20    intervalo de pings HTTP/2 (0 lo desactiva)
+//   HTTP2_KEEP_ALIVE_TIMEOUT_SECS=20     espera máxima de la respuesta al ping
+//   HTTP2_MAX_CONCURRENT_STREAMS=200     streams simultáneos por conexión
+//   SHUTDOWN_TIMEOUT_SECS=30             espera máxima a las conexiones abiertas al apagar
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub http1_keep_alive: bool,
+    pub header_read_timeout: Duration,
+    pub http2_enabled: bool,
+    pub http2_only: bool,
+    pub http2_keep_alive_interval: Option<Duration>,
+    pub http2_keep_alive_timeout: Duration,
+    pub http2_max_concurrent_streams: u32,
+    pub shutdown_timeout: Duration,
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+impl ServerConfig {
+    pub fn from_env() -> Self {
+        let keep_alive_interval = env_or("HTTP2_KEEP_ALIVE_INTERVAL_SECS", 20u64);
+
+        Self {
+            http1_keep_alive: env_or("HTTP_KEEP_ALIVE", true),
+            header_read_timeout: Duration::from_secs(env_or("HTTP_HEADER_READ_TIMEOUT_SECS", 30)),
+            http2_enabled: env_or("HTTP2_ENABLED", true),
+            http2_only: env_or("HTTP2_ONLY", false),
+            http2_keep_alive_interval: if keep_alive_interval > 0 {
+                Some(Duration::from_secs(keep_alive_interval))
+            } else {
+                None
+            },
+            http2_keep_alive_timeout: Duration::from_secs(env_or("HTTP2_KEEP_ALIVE_TIMEOUT_SECS", 20)),
+            http2_max_concurrent_streams: env_or("HTTP2_MAX_CONCURRENT_STREAMS", 200),
+            shutdown_timeout: Duration::from_secs(env_or("SHUTDOWN_TIMEOUT_SECS", 30)),
+        }
+    }
+
+    fn builder(&self) -> Builder<TokioExecutor> {
+        let mut builder = Builder::new(TokioExecutor::new());
+
+        builder
+            .http1()
+            .keep_alive(self.http1_keep_alive)
+            .header_read_timeout(self.header_read_timeout)
+            .timer(TokioTimer::new());
+
+        builder
+            .http2()
+            .keep_alive_interval(self.http2_keep_alive_interval)
+            .keep_alive_timeout(self.http2_keep_alive_timeout)
+            .max_concurrent_streams(self.http2_max_concurrent_streams)
+            .timer(TokioTimer::new());
+
+        if self.http2_only {
+            builder.http2_only()
+        } else if !self.http2_enabled {
+            builder.http1_only()
+        } else {
+            builder
+        }
+    }
+}
+
+// Pausa tras un error del listener antes de volver a aceptar
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_secs(1);
+
+fn is_connection_error(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::ConnectionRefused
+            | std::io::ErrorKind::ConnectionAborted
+            | std::io::ErrorKind::ConnectionReset
+    )
+}
+
+// Se resuelve con Ctrl+C o SIGTERM (lo que envían los orquestadores al desplegar)
+pub async fn termination_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!(error = %e, "⚠️ No se pudo escuchar Ctrl+C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "⚠️ No se pudo escuchar SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!(signal = "SIGINT", "🛑 Señal de apagado recibida"),
+        _ = terminate => tracing::info!(signal = "SIGTERM", "🛑 Señal de apagado recibida"),
+    }
+}
+
+// Servidor HTTP con HTTP/1.1 y HTTP/2 configurables
+// Al resolverse `shutdown` deja de aceptar conexiones y espera a las abiertas
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    config: ServerConfig,
+    shutdown: impl Future<Output = ()>,
+) -> std::io::Result<()> {
+    tracing::info!(
+        http1_keep_alive = config.http1_keep_alive,
+        http2_enabled = config.http2_enabled,
+        http2_only = config.http2_only,
+        http2_keep_alive_interval = ?config.http2_keep_alive_interval,
+        http2_max_concurrent_streams = config.http2_max_concurrent_streams,
+        "🔌 Configuración de conexiones HTTP"
+    );
+
+    let builder = config.builder();
+    let mut make_service = app.into_make_service_with_connect_info::<SocketAddr>();
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+
+    loop {
+        let (stream, remote_addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(connection) => connection,
+                // Errores de una conexión concreta: se sigue aceptando
+                Err(e) if is_connection_error(&e) => continue,
+                // Errores del listener (EMFILE, ENFILE...): se reintenta tras una pausa para no
+                // girar en vacío inundando los logs, igual que axum::serve
+                Err(e) => {
+                    tracing::warn!(error = %e, "⚠️ Error aceptando conexión; reintento en 1s");
+                    tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                    continue;
+                }
+            },
+            _ = &mut shutdown => break,
+        };
+
+        let tower_service = match make_service.call(remote_addr).await {
+            Ok(service) => service,
+            Err(infallible) => match infallible {},
+        };
+
+        let io = TokioIo::new(stream);
+        let hyper_service = TowerToHyperService::new(tower_service);
+        let connection = graceful.watch(
+            builder
+                .serve_connection_with_upgrades(io, hyper_service)
+                .into_owned(),
+        );
+
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                tracing::debug!(error = %e, client = %remote_addr, "Conexión HTTP cerrada con error");
+            }
+        });
+    }
+
+    tracing::info!("🛑 Apagado ordenado: esperando conexiones abiertas");
+    if tokio::time::timeout(config.shutdown_timeout, graceful.shutdown()).await.is_err() {
+        tracing::warn!(
+            timeout_secs = config.shutdown_timeout.as_secs(),
+            "⚠️ Conexiones abiertas tras el tiempo de apagado; se cierran"
+        );
+    }
+
+    Ok(())
+}
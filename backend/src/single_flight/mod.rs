@@ -0,0 +1,171 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::OnceCell;
+
+// Contadores por instancia (ver stats); se exponen en GET /metrics
+#[derive(Default)]
+struct FlightCounters {
+    executed: AtomicU64,
+    coalesced: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FlightStats {
+    // Cálculos que realmente se ejecutaron
+    pub executed: u64,
+    // Llamadas que esperaron un cálculo ya en curso en vez de repetirlo
+    pub coalesced: u64,
+}
+
+fn registry() -> &'static Mutex<HashMap<&'static str, Arc<FlightCounters>>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<&'static str, Arc<FlightCounters>>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn stats() -> HashMap<&'static str, FlightStats> {
+    registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|(name, counters)| {
+            (
+                *name,
+                FlightStats {
+                    executed: counters.executed.load(Ordering::Relaxed),
+                    coalesced: counters.coalesced.load(Ordering::Relaxed),
+                },
+            )
+        })
+        .collect()
+}
+
+// Deduplicación de lecturas costosas concurrentes ("single flight"): el primer llamador
+// de una clave calcula y los que llegan mientras tanto esperan ese mismo resultado.
+// No es un caché: al terminar, la siguiente llamada vuelve a calcular
+pub struct SingleFlight<K, V> {
+    in_flight: Mutex<HashMap<K, Arc<OnceCell<V>>>>,
+    counters: Arc<FlightCounters>,
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    pub fn new(name: &'static str) -> Self {
+        let counters = registry()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(name)
+            .or_default()
+            .clone();
+
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+            counters,
+        }
+    }
+
+    // Si quien calcula se cancela, el siguiente en espera toma el relevo con su `compute`
+    pub async fn run<F, Fut>(&self, key: K, compute: F) -> V
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        let cell = {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            match in_flight.get(&key) {
+                Some(cell) => {
+                    self.counters.coalesced.fetch_add(1, Ordering::Relaxed);
+                    cell.clone()
+                }
+                None => {
+                    let cell = Arc::new(OnceCell::new());
+                    in_flight.insert(key.clone(), cell.clone());
+                    cell
+                }
+            }
+        };
+
+        let value = cell
+            .get_or_init(|| async {
+                self.counters.executed.fetch_add(1, Ordering::Relaxed);
+                compute().await
+            })
+            .await
+            .clone();
+
+        // Liberar la clave (solo si sigue siendo este cálculo y no uno posterior)
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        if in_flight.get(&key).is_some_and(|current| Arc::ptr_eq(current, &cell)) {
+            in_flight.remove(&key);
+        }
+
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn concurrent_calls_share_one_computation() {
+        let flight = Arc::new(SingleFlight::<&'static str, u64>::new("test_concurrent"));
+        let calls = Arc::new(AtomicU64::new(0));
+
+        let handles: Vec<_> = (0..20)
+            .map(|_| {
+                let flight = flight.clone();
+                let calls = calls.clone();
+                tokio::spawn(async move {
+                    flight
+                        .run("summary", || async move {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(100)).await;
+                            7
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            assert_eq!(handle.await.unwrap(), 7);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let stats = &stats()["test_concurrent"];
+        assert_eq!(stats.executed, 1);
+        assert_eq!(stats.coalesced, 19);
+    }
+
+    #[tokio::test]
+    async fn finished_results_are_not_cached() {
+        let flight = SingleFlight::<&'static str, u64>::new("test_sequential");
+
+        assert_eq!(flight.run("summary", || async { 1 }).await, 1);
+        assert_eq!(flight.run("summary", || async { 2 }).await, 2);
+        assert_eq!(stats()["test_sequential"].executed, 2);
+    }
+
+    #[tokio::test]
+    async fn distinct_keys_compute_separately() {
+        let flight = SingleFlight::<u32, u32>::new("test_keys");
+
+        let (a, b) = tokio::join!(
+            flight.run(1, || async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                10
+            }),
+            flight.run(2, || async { 20 }),
+        );
+        assert_eq!((a, b), (10, 20));
+        assert_eq!(stats()["test_keys"].coalesced, 0);
+    }
+}
@@ -0,0 +1,226 @@
+// Utilidades compartidas por las pruebas de integración de los handlers.
+// Las que usan base de datos necesitan TEST_DATABASE_URL (p. ej. postgres://postgres@localhost/postgres)
+// y se marcan #[ignore]: cargo test -- --ignored. Cada prueba trabaja en su propio schema
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{HeaderMap, Method, Request, StatusCode},
+    Router,
+};
+use serde_json::Value;
+use sqlx::{postgres::PgPoolOptions, Connection, Executor, PgConnection, PgPool};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+use crate::metrics::MetricsCollector;
+use crate::models::user::Role;
+
+// Contraseña que cumple la política por defecto
+pub const PASSWORD: &str = "Cielo-Rojo-2026";
+
+// Tabla base de usuarios: las migraciones del repositorio parten de ella
+const BASE_SCHEMA: &str = "
+    CREATE TABLE users (
+        id SERIAL PRIMARY KEY,
+        name VARCHAR(255) NOT NULL,
+        email VARCHAR(255) NOT NULL UNIQUE,
+        password_hash VARCHAR(255),
+        is_admin BOOLEAN NOT NULL DEFAULT false,
+        is_active BOOLEAN NOT NULL DEFAULT true,
+        created_at TIMESTAMPTZ DEFAULT NOW(),
+        updated_at TIMESTAMPTZ DEFAULT NOW()
+    );
+";
+
+// Rate limit y bloqueo de cuentas son globales al proceso: cada request sale de una IP distinta
+fn next_client_addr() -> SocketAddr {
+    static NEXT: AtomicU32 = AtomicU32::new(1);
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::from(0x0a00_0000 | n)), 40000)
+}
+
+// Email único por prueba (las pruebas corren en paralelo)
+pub fn unique_email(prefix: &str) -> String {
+    format!("{}.{}@ventalibre.bo", prefix, uuid::Uuid::new_v4().simple())
+}
+
+// Pool que no conecta hasta usarse: para rutas que responden antes de tocar la BD
+pub fn lazy_pool() -> PgPool {
+    PgPoolOptions::new()
+        .connect_lazy("postgres://localhost/venta_libre_sin_bd")
+        .expect("URL de base de datos válida")
+}
+
+pub fn api(pool: PgPool) -> Router {
+    Router::new()
+        .nest(
+            "/api/v1",
+            crate::routes::create_routes(pool.clone(), Arc::new(MetricsCollector::new(1000, 1))),
+        )
+        .with_state(pool)
+}
+
+pub async fn send(
+    app: &Router,
+    method: Method,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, HeaderMap, Value) {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .extension(ConnectInfo(next_client_addr()));
+    if let Some(token) = token {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+    let request = match body {
+        Some(body) => request
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    (status, headers, json)
+}
+
+pub struct TestApp {
+    pub pool: PgPool,
+    pub app: Router,
+    schema: String,
+}
+
+impl TestApp {
+    // Schema nuevo con la tabla base y todas las migraciones aplicadas
+    pub async fn spawn() -> Self {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL no está configurada");
+        let schema = format!("test_{}", uuid::Uuid::new_v4().simple());
+
+        let mut admin = PgConnection::connect(&url).await.expect("conexión a TEST_DATABASE_URL");
+        admin
+            .execute(format!("CREATE SCHEMA {}", schema).as_str())
+            .await
+            .expect("crear schema de prueba");
+        admin.close().await.ok();
+
+        let search_path = format!("SET search_path TO {}", schema);
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .after_connect(move |conn, _| {
+                let search_path = search_path.clone();
+                Box::pin(async move {
+                    conn.execute(search_path.as_str()).await?;
+                    Ok(())
+                })
+            })
+            .connect(&url)
+            .await
+            .expect("pool de prueba");
+
+        pool.execute(BASE_SCHEMA).await.expect("tabla base de usuarios");
+
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/migrations");
+        let mut migrations: Vec<_> = std::fs::read_dir(dir)
+            .expect("directorio de migraciones")
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "sql"))
+            .collect();
+        migrations.sort();
+        for path in migrations {
+            let sql = std::fs::read_to_string(&path).unwrap();
+            pool.execute(sql.as_str())
+                .await
+                .unwrap_or_else(|e| panic!("migración {}: {}", path.display(), e));
+        }
+
+        Self { app: api(pool.clone()), pool, schema }
+    }
+
+    pub async fn request(
+        &self,
+        method: Method,
+        uri: &str,
+        token: Option<&str>,
+        body: Option<Value>,
+    ) -> (StatusCode, HeaderMap, Value) {
+        send(&self.app, method, uri, token, body).await
+    }
+
+    // Alta directa en BD (sin pasar por el registro) con el hash y el rol indicados
+    pub async fn insert_user(&self, email: &str, password_hash: &str, role: Role) -> i32 {
+        sqlx::query_scalar(
+            "INSERT INTO users (name, email, password_hash, role, is_admin)
+             VALUES ('Usuario de prueba', $1, $2, $3, $4) RETURNING id",
+        )
+        .bind(email)
+        .bind(password_hash)
+        .bind(role.as_str())
+        .bind(role == Role::Admin)
+        .fetch_one(&self.pool)
+        .await
+        .unwrap()
+    }
+
+    // Usuario con el rol indicado y su token de acceso (login con PASSWORD)
+    pub async fn user_with_role(&self, role: Role) -> (i32, String) {
+        let email = unique_email(role.as_str());
+        let password_hash = crate::auth::password::hash_password(PASSWORD).unwrap();
+        let id = self.insert_user(&email, &password_hash, role).await;
+        let (status, body) = self.login(&email, PASSWORD).await;
+        assert_eq!(status, StatusCode::OK, "login: {}", body);
+        (id, body["token"].as_str().unwrap().to_string())
+    }
+
+    // Registro por la API; devuelve (id, token de acceso)
+    pub async fn register(&self, email: &str, username: Option<&str>) -> (i32, String) {
+        let (status, _, body) = self
+            .request(
+                Method::POST,
+                "/api/v1/auth/register",
+                None,
+                Some(serde_json::json!({
+                    "name": "Ana Quispe",
+                    "email": email,
+                    "password": PASSWORD,
+                    "username": username,
+                })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "registro: {}", body);
+        (
+            body["user"]["id"].as_i64().unwrap() as i32,
+            body["token"].as_str().unwrap().to_string(),
+        )
+    }
+
+    pub async fn login(&self, identifier: &str, password: &str) -> (StatusCode, Value) {
+        let (status, _, body) = self
+            .request(
+                Method::POST,
+                "/api/v1/auth/login",
+                None,
+                Some(serde_json::json!({ "identifier": identifier, "password": password })),
+            )
+            .await;
+        (status, body)
+    }
+
+    // Borrar el schema de la prueba
+    pub async fn finish(self) {
+        self.pool.close().await;
+        let url = std::env::var("TEST_DATABASE_URL").unwrap();
+        let mut admin = PgConnection::connect(&url).await.unwrap();
+        admin
+            .execute(format!("DROP SCHEMA {} CASCADE", self.schema).as_str())
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,60 @@
+use serde::Deserialize;
+use std::sync::OnceLock;
+use crate::outbound::{self, http_client};
+
+// Verificación de captcha (hCaptcha por defecto; reCAPTCHA y Turnstile usan el mismo formato).
+// Solo se exige si CAPTCHA_SECRET está configurado
+struct CaptchaConfig {
+    secret: String,
+    verify_url: String,
+}
+
+fn config() -> Option<&'static CaptchaConfig> {
+    static CONFIG: OnceLock<Option<CaptchaConfig>> = OnceLock::new();
+    CONFIG
+        .get_or_init(|| {
+            let secret = std::env::var("CAPTCHA_SECRET").ok().filter(|s| !s.trim().is_empty())?;
+            let verify_url = std::env::var("CAPTCHA_VERIFY_URL")
+                .unwrap_or_else(|_| "https://api.hcaptcha.com/siteverify".to_string());
+            Some(CaptchaConfig { secret, verify_url })
+        })
+        .as_ref()
+}
+
+pub fn is_enabled() -> bool {
+    config().is_some()
+}
+
+#[derive(Deserialize)]
+struct VerifyResponse {
+    success: bool,
+}
+
+// Verificar el token del cliente. Sin captcha configurado siempre es válido;
+// si el proveedor no responde se rechaza (mejor un reintento que spam)
+pub async fn verify(token: Option<&str>, remote_ip: &str) -> bool {
+    let Some(config) = config() else {
+        return true;
+    };
+    let Some(token) = token.filter(|t| !t.trim().is_empty()) else {
+        return false;
+    };
+
+    let request = http_client().post(&config.verify_url).form(&[
+        ("secret", config.secret.as_str()),
+        ("response", token),
+        ("remoteip", remote_ip),
+    ]);
+
+    match outbound::send("captcha", request).await {
+        Ok(response) if response.is_success() => response
+            .json::<VerifyResponse>()
+            .map(|r| r.success)
+            .unwrap_or(false),
+        Ok(_) => false,
+        Err(e) => {
+            tracing::error!(error = %e, "🚨 Error al verificar el captcha");
+            false
+        }
+    }
+}
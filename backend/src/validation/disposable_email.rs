@@ -0,0 +1,122 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::sync::{OnceLock, RwLock};
+
+// Dominios desechables conocidos incluidos por defecto
+const DEFAULT_DOMAINS: &[&str] = &[
+    "10minutemail.com",
+    "dispostable.com",
+    "fakeinbox.com",
+    "getnada.com",
+    "guerrillamail.com",
+    "maildrop.cc",
+    "mailinator.com",
+    "mintemail.com",
+    "sharklasers.com",
+    "temp-mail.org",
+    "tempmail.com",
+    "throwawaymail.com",
+    "trashmail.com",
+    "yopmail.com",
+];
+
+// Lista de dominios de email desechables (recargable en caliente)
+pub struct DisposableEmailDenylist {
+    domains: RwLock<HashSet<String>>,
+}
+
+impl DisposableEmailDenylist {
+    // Cargar la lista desde DISPOSABLE_EMAIL_DOMAINS (separados por coma)
+    // y DISPOSABLE_EMAIL_DOMAINS_FILE (un dominio por línea, '#' para comentarios)
+    pub fn from_env() -> Result<Self, std::io::Error> {
+        Ok(Self {
+            domains: RwLock::new(load_domains()?),
+        })
+    }
+
+    // Recargar la lista; si falla se conserva la lista anterior
+    pub fn reload(&self) -> Result<usize, std::io::Error> {
+        let domains = load_domains()?;
+        let count = domains.len();
+        *self.domains.write().unwrap_or_else(|e| e.into_inner()) = domains;
+
+        tracing::info!(
+            event = "disposable_domains_reloaded",
+            domains = count,
+            "📧 Lista de dominios desechables recargada"
+        );
+
+        Ok(count)
+    }
+
+    // Verificar si el dominio del email (o alguno de sus dominios padre) está en la lista
+    pub fn is_disposable(&self, email: &str) -> bool {
+        let domain = match email.trim().rsplit_once('@') {
+            Some((_, domain)) => domain.trim_end_matches('.').to_lowercase(),
+            None => return false,
+        };
+
+        let domains = self.domains.read().unwrap_or_else(|e| e.into_inner());
+        if domains.contains(&domain) {
+            return true;
+        }
+
+        domain
+            .match_indices('.')
+            .any(|(index, _)| domains.contains(&domain[index + 1..]))
+    }
+
+    pub fn domain_count(&self) -> usize {
+        self.domains.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+// Instancia global compartida por los handlers
+pub fn denylist() -> &'static DisposableEmailDenylist {
+    static DENYLIST: OnceLock<DisposableEmailDenylist> = OnceLock::new();
+    DENYLIST.get_or_init(|| {
+        DisposableEmailDenylist::from_env().unwrap_or_else(|e| {
+            tracing::warn!(
+                error = %e,
+                "⚠️ No se pudo leer DISPOSABLE_EMAIL_DOMAINS_FILE, usando lista por defecto"
+            );
+            DisposableEmailDenylist {
+                domains: RwLock::new(default_domains()),
+            }
+        })
+    })
+}
+
+fn default_domains() -> HashSet<String> {
+    DEFAULT_DOMAINS.iter().map(|d| d.to_string()).collect()
+}
+
+fn load_domains() -> Result<HashSet<String>, std::io::Error> {
+    let mut domains = default_domains();
+
+    if let Ok(list) = env::var("DISPOSABLE_EMAIL_DOMAINS") {
+        domains.extend(list.split(',').filter_map(normalize_domain));
+    }
+
+    if let Ok(path) = env::var("DISPOSABLE_EMAIL_DOMAINS_FILE") {
+        let content = fs::read_to_string(path)?;
+        domains.extend(
+            content
+                .lines()
+                .filter(|line| !line.trim_start().starts_with('#'))
+                .filter_map(normalize_domain),
+        );
+    }
+
+    Ok(domains)
+}
+
+fn normalize_domain(raw: &str) -> Option<String> {
+    let domain = raw.trim().trim_start_matches('@').trim_end_matches('.').to_lowercase();
+    if domain.is_empty() {
+        None
+    } else {
+        Some(domain)
+    }
+}
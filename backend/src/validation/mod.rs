@@ -0,0 +1,6 @@
+pub mod captcha;
+pub mod disposable_email;
+pub mod password_policy;
+
+pub use disposable_email::denylist;
+pub use password_policy::password_policy;
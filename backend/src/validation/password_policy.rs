@@ -0,0 +1,249 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::sync::OnceLock;
+
+// Lista incluida en el binario; COMMON_PASSWORDS_FILE la amplía (mismo formato)
+const BUNDLED_COMMON_PASSWORDS: &str = include_str!("../../static/common_passwords.txt");
+
+// Regla incumplida, con mensaje listo para mostrar al usuario
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyViolation {
+    pub rule: &'static str,
+    pub message: String,
+}
+
+impl PolicyViolation {
+    fn new(rule: &'static str, message: impl Into<String>) -> Self {
+        Self { rule, message: message.into() }
+    }
+}
+
+// Política de contraseñas (configurable por variables de entorno):
+// PASSWORD_MIN_LENGTH (8), PASSWORD_REQUIRE_MIXED_CASE (false), PASSWORD_REQUIRE_DIGIT (false),
+// PASSWORD_REQUIRE_SYMBOL (false), PASSWORD_DENY_COMMON (true), PASSWORD_DENY_EMAIL (true)
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_mixed_case: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub deny_common: bool,
+    pub deny_email: bool,
+    common_passwords: HashSet<String>,
+}
+
+fn env_flag(name: &str, default: bool) -> bool {
+    env::var(name)
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+        .unwrap_or(default)
+}
+
+impl PasswordPolicy {
+    pub fn from_env() -> Self {
+        Self {
+            min_length: env::var("PASSWORD_MIN_LENGTH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(8),
+            require_mixed_case: env_flag("PASSWORD_REQUIRE_MIXED_CASE", false),
+            require_digit: env_flag("PASSWORD_REQUIRE_DIGIT", false),
+            require_symbol: env_flag("PASSWORD_REQUIRE_SYMBOL", false),
+            deny_common: env_flag("PASSWORD_DENY_COMMON", true),
+            deny_email: env_flag("PASSWORD_DENY_EMAIL", true),
+            common_passwords: load_common_passwords(),
+        }
+    }
+
+    // Verificar una contraseña; `email` es el del dueño de la cuenta (si se conoce).
+    // Devuelve todas las reglas incumplidas, no solo la primera
+    pub fn check(&self, password: &str, email: Option<&str>) -> Result<(), Vec<PolicyViolation>> {
+        let mut violations = Vec::new();
+
+        if password.chars().count() < self.min_length {
+            violations.push(PolicyViolation::new(
+                "min_length",
+                format!("La contraseña debe tener al menos {} caracteres", self.min_length),
+            ));
+        }
+
+        if self.require_mixed_case
+            && !(password.chars().any(char::is_lowercase) && password.chars().any(char::is_uppercase))
+        {
+            violations.push(PolicyViolation::new(
+                "mixed_case",
+                "La contraseña debe combinar mayúsculas y minúsculas",
+            ));
+        }
+
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push(PolicyViolation::new("digit", "La contraseña debe incluir al menos un número"));
+        }
+
+        if self.require_symbol && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+            violations.push(PolicyViolation::new("symbol", "La contraseña debe incluir al menos un símbolo"));
+        }
+
+        if self.deny_common && self.is_common(password) {
+            violations.push(PolicyViolation::new(
+                "common_password",
+                "La contraseña es demasiado común; elige otra",
+            ));
+        }
+
+        if self.deny_email && contains_email_local_part(password, email) {
+            violations.push(PolicyViolation::new(
+                "contains_email",
+                "La contraseña no puede contener tu email",
+            ));
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    pub fn is_common(&self, password: &str) -> bool {
+        self.common_passwords.contains(&password.to_lowercase())
+    }
+
+    pub fn common_password_count(&self) -> usize {
+        self.common_passwords.len()
+    }
+}
+
+// La parte local del email (antes de '@'); se ignoran las de menos de 3 caracteres
+fn contains_email_local_part(password: &str, email: Option<&str>) -> bool {
+    let Some((local_part, _)) = email.and_then(|e| e.trim().split_once('@')) else {
+        return false;
+    };
+    let local_part = local_part.to_lowercase();
+
+    local_part.chars().count() >= 3 && password.to_lowercase().contains(&local_part)
+}
+
+fn parse_list(content: &str) -> impl Iterator<Item = String> + '_ {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_lowercase)
+}
+
+fn load_common_passwords() -> HashSet<String> {
+    let mut passwords: HashSet<String> = parse_list(BUNDLED_COMMON_PASSWORDS).collect();
+
+    if let Ok(path) = env::var("COMMON_PASSWORDS_FILE") {
+        match fs::read_to_string(&path) {
+            Ok(content) => passwords.extend(parse_list(&content)),
+            Err(e) => {
+                tracing::warn!(
+                    error = %e,
+                    path = %path,
+                    "⚠️ No se pudo leer COMMON_PASSWORDS_FILE, usando solo la lista incluida"
+                );
+            }
+        }
+    }
+
+    passwords
+}
+
+// Instancia global compartida por los handlers
+pub fn password_policy() -> &'static PasswordPolicy {
+    static POLICY: OnceLock<PasswordPolicy> = OnceLock::new();
+    POLICY.get_or_init(PasswordPolicy::from_env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn policy() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 8,
+            require_mixed_case: true,
+            require_digit: true,
+            require_symbol: true,
+            deny_common: true,
+            deny_email: true,
+            common_passwords: parse_list(BUNDLED_COMMON_PASSWORDS).collect(),
+        }
+    }
+
+    fn failed_rules(password: &str, email: Option<&str>) -> Vec<&'static str> {
+        match policy().check(password, email) {
+            Ok(()) => Vec::new(),
+            Err(violations) => violations.into_iter().map(|v| v.rule).collect(),
+        }
+    }
+
+    #[test]
+    fn accepts_password_meeting_every_rule() {
+        assert!(failed_rules("Cielo-Rojo-2026", Some("ana@example.com")).is_empty());
+    }
+
+    #[test]
+    fn rejects_short_password() {
+        assert_eq!(failed_rules("Ab1-xyz", None), vec!["min_length"]);
+    }
+
+    #[test]
+    fn rejects_missing_mixed_case() {
+        assert_eq!(failed_rules("cielo-rojo-2026", None), vec!["mixed_case"]);
+    }
+
+    #[test]
+    fn rejects_missing_digit() {
+        assert_eq!(failed_rules("Cielo-Rojo-Azul", None), vec!["digit"]);
+    }
+
+    #[test]
+    fn rejects_missing_symbol() {
+        assert_eq!(failed_rules("CieloRojo2026", None), vec!["symbol"]);
+    }
+
+    #[test]
+    fn rejects_common_password_ignoring_case() {
+        let policy = policy();
+        assert!(policy.is_common("123456"));
+        assert!(policy.is_common("PASSWORD"));
+        assert!(!policy.is_common("Cielo-Rojo-2026"));
+        assert!(failed_rules("123456", None).contains(&"common_password"));
+    }
+
+    #[test]
+    fn rejects_password_containing_email_local_part() {
+        assert_eq!(failed_rules("Mariela-2026!", Some("mariela@example.com")), vec!["contains_email"]);
+        // Partes locales muy cortas no se consideran
+        assert!(failed_rules("Cielo-Rojo-2026", Some("ro@example.com")).is_empty());
+    }
+
+    #[test]
+    fn reports_every_failed_rule() {
+        assert_eq!(
+            failed_rules("abc", None),
+            vec!["min_length", "mixed_case", "digit", "symbol"]
+        );
+    }
+
+    #[test]
+    fn common_password_lookup_is_fast() {
+        // Tamaño de una lista ampliada con COMMON_PASSWORDS_FILE (top 10.000)
+        let mut policy = policy();
+        policy
+            .common_passwords
+            .extend(parse_list(&(0..10_000).map(|i| format!("comun{}\n", i)).collect::<String>()));
+        assert!(policy.common_password_count() >= 10_000);
+        assert!(policy.is_common("COMUN9999"));
+
+        let start = Instant::now();
+        for i in 0..10_000 {
+            policy.is_common(&format!("Candidata-{}", i));
+        }
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}